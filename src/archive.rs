@@ -0,0 +1,323 @@
+//! Archival records of dependencies: Zenodo and DataCite DOIs, Software
+//! Heritage identifiers and citation files shipped with the crate itself.
+
+use crate::bibtex;
+use crate::citation_file;
+use crate::codes::Code;
+use crate::diagnostics;
+use crate::fetch::MetadataFetcher;
+use crate::registry;
+use crate::ResolvedDependency;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Deserialize)]
+struct ZenodoSearch {
+    hits: ZenodoHits,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZenodoHits {
+    hits: Vec<ZenodoHit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZenodoHit {
+    doi: Option<String>,
+    /// DOI resolving to the latest version of the record
+    conceptdoi: Option<String>,
+    metadata: ZenodoMetadata,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZenodoMetadata {
+    title: Option<String>,
+    publication_date: Option<String>,
+    #[serde(default)]
+    creators: Vec<ZenodoCreator>,
+    #[serde(default)]
+    related_identifiers: Vec<ZenodoIdentifier>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZenodoCreator {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZenodoIdentifier {
+    identifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataCiteSearch {
+    data: Vec<DataCiteRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataCiteRecord {
+    attributes: DataCiteAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataCiteAttributes {
+    doi: String,
+    #[serde(default)]
+    titles: Vec<DataCiteTitle>,
+    #[serde(default)]
+    creators: Vec<ZenodoCreator>,
+    publication_year: Option<u32>,
+    #[serde(default)]
+    dates: Vec<DataCiteDate>,
+    /// Landing page of the record
+    url: Option<String>,
+    #[serde(default)]
+    related_identifiers: Vec<DataCiteIdentifier>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DataCiteTitle {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataCiteDate {
+    date: String,
+    date_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataCiteIdentifier {
+    related_identifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwhVisit {
+    snapshot: Option<String>,
+}
+
+/// A Zenodo or DataCite record archiving a dependency.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivalRecord {
+    pub doi: String,
+    pub title: Option<String>,
+    pub creators: Vec<String>,
+    pub publication_date: Option<String>,
+    /// Service the record was found on: Zenodo or DataCite
+    pub archive: &'static str,
+}
+
+impl ArchivalRecord {
+    /// Publication date of the archived release, if it is a full date.
+    pub fn date(&self) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(self.publication_date.as_deref()?, "%Y-%m-%d").ok()
+    }
+}
+
+/// What is known about a dependency beyond its crates.io metadata.
+#[derive(Debug, Default)]
+pub struct ArchivalInfo {
+    pub record: Option<ArchivalRecord>,
+    pub swhid: Option<String>,
+    /// The dependency's own CITATION.cff or CITATION.bib
+    pub citation_file: Option<PathBuf>,
+    /// DOI mentioned in that citation file
+    pub citation_doi: Option<String>,
+}
+
+impl ArchivalInfo {
+    /// The DOI to cite, preferring the one chosen by the authors.
+    pub fn doi(&self) -> Option<&str> {
+        self.citation_doi.as_deref().or(self.record.as_ref().map(|r| r.doi.as_str()))
+    }
+
+    /// The entry the dependency's own citation file asks for, keyed `key`.
+    pub fn citation_entry(&self, key: &str) -> Option<String> {
+        citation_file::preferred_entry(self.citation_file.as_ref()?, key).ok()
+    }
+}
+
+/// The first DOI (`10.<registrant>/<suffix>`) in `text`.
+pub fn find_doi(text: &str) -> Option<&str> {
+    text.match_indices("10.").find_map(|(i, _)| {
+        let rest = &text[i + 3..];
+        let registrant = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if registrant < 4 || !rest[registrant..].starts_with('/') {
+            return None;
+        }
+        let len = text[i..]
+            .find(|c: char| c.is_whitespace() || matches!(c, '}' | '"' | '\'' | ',' | ')' | '>'))
+            .unwrap_or(text.len() - i);
+        Some(&text[i..i + len])
+    })
+}
+
+/// Repository URL without scheme variations, `.git` suffix or trailing slash.
+fn normalize_repository(url: &str) -> String {
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .replacen("http://", "https://", 1)
+}
+
+/// Whether `identifier` points at `repository`, a normalized, lowercase URL.
+fn points_at(identifier: &str, repository: &str) -> bool {
+    normalize_repository(identifier).to_lowercase().starts_with(repository)
+}
+
+/// Whether a record titled `title` archives the crate `name`: the title is the
+/// crate or repository name, optionally followed by a version or subtitle.
+fn titled_after(title: &str, name: &str) -> bool {
+    let title = title.to_lowercase();
+    let name = name.to_lowercase();
+    let project = title.split(':').next().unwrap_or(&title).trim();
+    let project = project.rsplit('/').next().unwrap_or(project);
+    project.split_whitespace().next() == Some(name.as_str())
+}
+
+/// Zenodo software record of the crate `name`: one whose related identifiers
+/// point at `repository_url`, or else one titled after the crate.
+pub async fn zenodo_record(fetcher: &MetadataFetcher, name: &str, repository_url: Option<&str>) -> Result<Option<ArchivalRecord>, String> {
+    let repository = repository_url.map(|url| normalize_repository(url).to_lowercase());
+    let query = match &repository {
+        Some(repository) => format!("\"{}\"", repository.trim_start_matches("https://")),
+        None => format!("title:\"{}\"", name),
+    };
+    let url = reqwest::Url::parse_with_params(
+        "https://zenodo.org/api/records",
+        &[("q", query.as_str()), ("type", "software"), ("size", "10"), ("sort", "mostrecent")],
+    ).map_err(|e| e.to_string())?;
+
+    let search: ZenodoSearch = fetcher.get_json(url.as_str()).await?;
+    Ok(search.hits.hits.into_iter()
+        .find(|hit| match &repository {
+            Some(repository) => hit.metadata.related_identifiers.iter().any(|id| points_at(&id.identifier, repository)),
+            None => hit.metadata.title.as_deref().is_some_and(|title| titled_after(title, name)),
+        })
+        .and_then(|hit| Some(ArchivalRecord {
+            doi: hit.conceptdoi.or(hit.doi)?,
+            title: hit.metadata.title,
+            creators: hit.metadata.creators.into_iter().map(|c| c.name).collect(),
+            publication_date: hit.metadata.publication_date,
+            archive: "Zenodo",
+        })))
+}
+
+/// DataCite software record of the crate `name`, matched like `zenodo_record`.
+/// DataCite also indexes DOIs minted by repositories other than Zenodo.
+pub async fn datacite_record(fetcher: &MetadataFetcher, name: &str, repository_url: Option<&str>) -> Result<Option<ArchivalRecord>, String> {
+    let repository = repository_url.map(|url| normalize_repository(url).to_lowercase());
+    let query = match &repository {
+        Some(repository) => format!("\"{}\"", repository.trim_start_matches("https://")),
+        None => format!("titles.title:\"{}\"", name),
+    };
+    let url = reqwest::Url::parse_with_params(
+        "https://api.datacite.org/dois",
+        &[("query", query.as_str()), ("resource-type-id", "software"), ("page[size]", "10"), ("sort", "-published")],
+    ).map_err(|e| e.to_string())?;
+
+    let search: DataCiteSearch = fetcher.get_json(url.as_str()).await?;
+    Ok(search.data.into_iter()
+        .map(|record| record.attributes)
+        .find(|record| match &repository {
+            Some(repository) => record.url.iter()
+                .chain(record.related_identifiers.iter().map(|id| &id.related_identifier))
+                .any(|id| points_at(id, repository)),
+            None => record.titles.iter().any(|t| titled_after(&t.title, name)),
+        })
+        .map(|record| ArchivalRecord {
+            publication_date: record.dates.iter()
+                .find(|d| d.date_type == "Issued")
+                .map(|d| d.date.clone())
+                .or(record.publication_year.map(|year| year.to_string())),
+            doi: record.doi,
+            title: record.titles.into_iter().next().map(|t| t.title),
+            creators: record.creators.into_iter().map(|c| c.name).collect(),
+            archive: "DataCite",
+        }))
+}
+
+/// DOI record of `dep`, searched on Zenodo and then DataCite by its repository
+/// URL, or by its crates.io name when it has no repository. Failed searches
+/// are reported as warnings.
+pub async fn doi_record(fetcher: &MetadataFetcher, dep: &ResolvedDependency) -> Option<ArchivalRecord> {
+    let repository = dep.repository();
+    // The names of local crates say nothing about what was archived
+    if repository.is_none() && dep.path.is_some() {
+        return None;
+    }
+    match zenodo_record(fetcher, &dep.name, repository).await {
+        Ok(Some(record)) => return Some(record),
+        Ok(None) => {}
+        Err(e) => diagnostics::warning(Code::FetchFailed, format!("Zenodo lookup for {} failed: {}", dep.name, e)),
+    }
+    match datacite_record(fetcher, &dep.name, repository).await {
+        Ok(record) => record,
+        Err(e) => {
+            diagnostics::warning(Code::FetchFailed, format!("DataCite lookup for {} failed: {}", dep.name, e));
+            None
+        }
+    }
+}
+
+/// The BibTeX entry registered for `doi`, keyed `key`. A failed request is
+/// reported as a warning.
+pub async fn doi_entry(fetcher: &MetadataFetcher, doi: &str, key: &str) -> Option<String> {
+    let bib = match fetcher.doi_bibtex(doi).await {
+        Ok(bib) => bib,
+        Err(e) => {
+            diagnostics::warning(Code::FetchFailed, format!("Could not fetch the BibTeX entry of DOI {}: {}", doi, e));
+            return None;
+        }
+    };
+    let entry = bibtex::entries(&bib).first().map(|(_, entry)| bibtex::rekey(entry, key));
+    if entry.is_none() {
+        diagnostics::warning(Code::FetchFailed, format!("The BibTeX entry of DOI {} could not be read.", doi));
+    }
+    entry
+}
+
+/// SWHID of the latest Software Heritage snapshot of `repository_url`.
+pub async fn swhid(fetcher: &MetadataFetcher, repository_url: &str) -> Result<Option<String>, String> {
+    let url = format!(
+        "https://archive.softwareheritage.org/api/1/origin/{}/visit/latest/?require_snapshot=true",
+        normalize_repository(repository_url)
+    );
+    let visit: Option<SwhVisit> = fetcher.get_json_opt(&url).await?;
+    Ok(visit.and_then(|v| v.snapshot).map(|snapshot| format!("swh:1:snp:{}", snapshot)))
+}
+
+/// SWHID of `repository_url`, the repository of `name`. A failed lookup is
+/// reported as a warning.
+pub async fn archived_swhid(fetcher: &MetadataFetcher, name: &str, repository_url: &str) -> Option<String> {
+    match swhid(fetcher, repository_url).await {
+        Ok(id) => id,
+        Err(e) => {
+            diagnostics::warning(Code::FetchFailed, format!("Software Heritage lookup for {} failed: {}", name, e));
+            None
+        }
+    }
+}
+
+/// Look up the archival records of `dep`. Failed lookups are reported as
+/// warnings and leave the corresponding field empty.
+pub async fn lookup(fetcher: &MetadataFetcher, dep: &ResolvedDependency) -> ArchivalInfo {
+    let mut info = ArchivalInfo::default();
+
+    if let Some(dir) = &dep.local_source {
+        info.citation_file = registry::citation_files(dir).into_iter().next();
+        info.citation_doi = info.citation_file.as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|content| find_doi(&content).map(str::to_string));
+    }
+
+    info.record = doi_record(fetcher, dep).await;
+    if let Some(repository) = dep.repository() {
+        info.swhid = archived_swhid(fetcher, &dep.name, repository).await;
+    }
+    info
+}
@@ -0,0 +1,463 @@
+//! Reading existing BibTeX text.
+
+use chrono::NaiveDate;
+use std::fmt;
+
+/// Byte range of the entry starting at the `@` at `start`, up to and
+/// including its closing brace.
+fn entry_end(bib: &str, start: usize) -> Option<usize> {
+    let open = start + bib[start..].find('{')?;
+    let mut depth = 0usize;
+    for (i, c) in bib[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// All entries in `bib`, as `(key, text)` pairs.
+pub fn entries(bib: &str) -> Vec<(&str, &str)> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = bib[pos..].find('@') {
+        let start = pos + offset;
+        let Some(end) = entry_end(bib, start) else { break };
+        let text = &bib[start..end];
+        if let Some(key) = entry_key(text) {
+            found.push((key, text));
+        }
+        pos = end;
+    }
+    found
+}
+
+/// The citation key of an entry.
+pub fn entry_key(entry: &str) -> Option<&str> {
+    let open = entry.find('{')?;
+    let rest = &entry[open + 1..];
+    let key = rest[..rest.find(',')?].trim();
+    (!key.is_empty()).then_some(key)
+}
+
+/// The entry with citation key `key`.
+pub fn find_entry<'a>(bib: &'a str, key: &str) -> Option<&'a str> {
+    entries(bib).into_iter().find(|(k, _)| *k == key).map(|(_, text)| text)
+}
+
+/// `entry` with its citation key replaced by `key`.
+pub fn rekey(entry: &str, key: &str) -> String {
+    match (entry.find('{'), entry_key(entry)) {
+        (Some(open), Some(old)) => {
+            let key_start = open + 1 + entry[open + 1..].find(old).unwrap_or(0);
+            format!("{}{}{}", &entry[..key_start], key, &entry[key_start + old.len()..])
+        }
+        _ => entry.to_string(),
+    }
+}
+
+/// An entry parsed by `parse`, borrowing from the BibTeX text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry<'a> {
+    pub entry_type: &'a str,
+    pub key: &'a str,
+    pub fields: Vec<Field<'a>>,
+    /// Byte range of the entry, from its `@` to its closing brace
+    pub span: std::ops::Range<usize>,
+}
+
+/// A field of a parsed entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field<'a> {
+    pub name: &'a str,
+    /// The value as written, with braces or quotes
+    pub value: &'a str,
+    /// Byte range of the value
+    pub span: std::ops::Range<usize>,
+}
+
+impl<'a> Entry<'a> {
+    /// The value of field `name`, without its outer braces or quotes.
+    pub fn field(&self, name: &str) -> Option<&'a str> {
+        self.fields.iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+            .map(|field| unquote(field.value))
+    }
+}
+
+/// `value` without the braces or quotes around it.
+pub fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value.strip_prefix('{').and_then(|v| v.strip_suffix('}'))
+        .or_else(|| value.strip_prefix('"').and_then(|v| v.strip_suffix('"')))
+        .unwrap_or(value)
+}
+
+/// Line number of byte `pos` in `text`, from 1.
+pub fn line_of(text: &str, pos: usize) -> usize {
+    text[..pos].matches('\n').count() + 1
+}
+
+/// Where and how BibTeX text is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Reads entries strictly, reporting where the text is malformed.
+struct Parser<'a> {
+    bib: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error_at(&self, pos: usize, message: impl Into<String>) -> ParseError {
+        ParseError { line: line_of(self.bib, pos), message: message.into() }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.bib[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.bib[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// A type, key or field name, or an unquoted value.
+    fn identifier(&mut self) -> &'a str {
+        let rest = &self.bib[self.pos..];
+        let len = rest.find(|c: char| c.is_whitespace() || "{}(),=#\"".contains(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn expect(&mut self, c: char, what: &str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(found) => Err(self.error(format!("expected {} but found {:?}", what, found))),
+            None => Err(self.error(format!("expected {} but the file ends", what))),
+        }
+    }
+
+    /// Skip a `{...}` group or `"..."` string, whose braces must balance.
+    fn delimited(&mut self, close: char, what: &str) -> Result<(), ParseError> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 && close == '}' => return Ok(()),
+                '}' if depth == 0 => return Err(self.error_at(start, format!("unbalanced }} in {}", what))),
+                '}' => depth -= 1,
+                '"' if depth == 0 && close == '"' => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(self.error_at(start, format!("unbalanced braces in {}", what)))
+    }
+
+    /// A field value: braced, quoted or bare parts joined with `#`.
+    fn value(&mut self, what: &str) -> Result<&'a str, ParseError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some('{') => self.delimited('}', what)?,
+                Some('"') => self.delimited('"', what)?,
+                Some(_) if !self.identifier().is_empty() => {}
+                _ => return Err(self.error(format!("expected the value of {}", what))),
+            }
+            let end = self.pos;
+            self.skip_whitespace();
+            if self.peek() != Some('#') {
+                return Ok(self.bib[start..end].trim());
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn entry(&mut self, start: usize) -> Result<Option<Entry<'a>>, ParseError> {
+        self.pos = start + 1;
+        let entry_type = self.identifier();
+        if entry_type.is_empty() {
+            return Err(self.error("expected an entry type after @"));
+        }
+        self.skip_whitespace();
+        if ["comment", "preamble", "string"].iter().any(|t| entry_type.eq_ignore_ascii_case(t)) {
+            if self.peek() != Some('{') {
+                return Err(self.error(format!("expected {{ after @{}", entry_type)));
+            }
+            self.delimited('}', &format!("@{}", entry_type))?;
+            return Ok(None);
+        }
+        self.expect('{', &format!("{{ after @{}", entry_type))?;
+        self.skip_whitespace();
+        let key = self.identifier();
+        if key.is_empty() {
+            return Err(self.error(format!("@{} entry without a citation key", entry_type)));
+        }
+        let mut fields = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => break,
+                Some(',') => self.pos += 1,
+                Some(found) => return Err(self.error(format!("expected , or }} after {} in entry {} but found {:?}", fields.last().map_or(String::from("the key"), |field: &Field| format!("field {}", field.name)), key, found))),
+                None => return Err(self.error_at(start, format!("entry {} is not closed", key))),
+            }
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => break,
+                None => return Err(self.error_at(start, format!("entry {} is not closed", key))),
+                Some(_) => {}
+            }
+            let name = self.identifier();
+            if name.is_empty() {
+                return Err(self.error(format!("expected a field name in entry {}", key)));
+            }
+            self.expect('=', &format!("= after field {} of entry {}", name, key))?;
+            self.skip_whitespace();
+            let value_start = self.pos;
+            let value = self.value(&format!("field {} of entry {}", name, key))?;
+            fields.push(Field { name, value, span: value_start..value_start + value.len() });
+        }
+        self.pos += 1;
+        Ok(Some(Entry { entry_type, key, fields, span: start..self.pos }))
+    }
+}
+
+/// The entries in `bib` that parse, and where the others are malformed:
+/// unbalanced braces, a missing `=` or comma, an unclosed entry. Reading
+/// resumes at the next line starting with `@`. Text outside entries is a
+/// comment, as for BibTeX.
+pub fn parse_all(bib: &str) -> (Vec<Entry<'_>>, Vec<ParseError>) {
+    let mut parser = Parser { bib, pos: 0 };
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(offset) = bib[parser.pos..].find('@') {
+        let start = parser.pos + offset;
+        // `%` comments out the rest of the line, e.g. an email address
+        let line_start = bib[..start].rfind('\n').map_or(0, |i| i + 1);
+        if bib[line_start..start].contains('%') {
+            parser.pos = start + 1;
+            continue;
+        }
+        match parser.entry(start) {
+            Ok(entry) => entries.extend(entry),
+            Err(e) => {
+                errors.push(e);
+                parser.pos = bib[start + 1..].find("\n@").map_or(bib.len(), |i| start + 2 + i);
+            }
+        }
+    }
+    (entries, errors)
+}
+
+/// Check that `bib` parses back, and that its keys and field names are
+/// unique. Returns a description of each problem.
+pub fn validate(bib: &str) -> Vec<String> {
+    let (entries, errors) = parse_all(bib);
+    let mut problems: Vec<String> = errors.iter().map(ParseError::to_string).collect();
+    let mut keys = std::collections::BTreeSet::new();
+    for entry in &entries {
+        if !keys.insert(entry.key) {
+            problems.push(format!("duplicate key {}", entry.key));
+        }
+        let mut names = std::collections::BTreeSet::new();
+        for field in &entry.fields {
+            if !names.insert(field.name.to_lowercase()) {
+                problems.push(format!("field {} repeated in entry {}", field.name, entry.key));
+            }
+        }
+    }
+    problems
+}
+
+/// `generated` merged into `existing`: the entries of `existing` whose keys
+/// `generated` has are replaced in place, the others are kept as they are,
+/// and the generated entries `existing` lacks are appended with the comments
+/// before them. `existing` must parse, so that no entry is lost.
+pub fn merge(existing: &str, generated: &str) -> Result<String, ParseError> {
+    let (old, errors) = parse_all(existing);
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let (new, _) = parse_all(generated);
+    let find = |entries: &[Entry<'_>], key: &str| entries.iter().position(|entry| entry.key.eq_ignore_ascii_case(key));
+
+    let mut merged = String::new();
+    let mut pos = 0;
+    for entry in &old {
+        merged.push_str(&existing[pos..entry.span.start]);
+        match find(&new, entry.key) {
+            Some(i) => merged.push_str(&generated[new[i].span.clone()]),
+            None => merged.push_str(&existing[entry.span.clone()]),
+        }
+        pos = entry.span.end;
+    }
+    merged.push_str(&existing[pos..]);
+
+    let mut end = 0;
+    for entry in &new {
+        if find(&old, entry.key).is_none() {
+            if !merged.is_empty() {
+                merged.truncate(merged.trim_end().len());
+                merged.push_str("\n\n");
+            }
+            merged.push_str(generated[end..entry.span.end].trim_start_matches('\n'));
+            merged.push('\n');
+        }
+        end = entry.span.end;
+    }
+    Ok(merged)
+}
+
+/// `existing` with the values of `fields` in each entry replaced by those of
+/// the entry with the same key in `generated`, leaving everything else as
+/// written. Fields an entry lacks are not added, and entries `generated`
+/// lacks are kept. Returns the text and the number of values changed.
+pub fn update(existing: &str, generated: &str, fields: &[&str]) -> Result<(String, usize), ParseError> {
+    let (old, errors) = parse_all(existing);
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let (new, _) = parse_all(generated);
+    let mut updated = String::new();
+    let mut pos = 0;
+    let mut changed = 0;
+    for entry in &old {
+        let Some(source) = new.iter().find(|new| new.key.eq_ignore_ascii_case(entry.key)) else { continue };
+        for field in entry.fields.iter().filter(|field| fields.iter().any(|name| field.name.eq_ignore_ascii_case(name))) {
+            let Some(value) = source.fields.iter().find(|new| new.name.eq_ignore_ascii_case(field.name)).map(|new| new.value) else { continue };
+            if value != field.value {
+                updated.push_str(&existing[pos..field.span.start]);
+                updated.push_str(value);
+                pos = field.span.end;
+                changed += 1;
+            }
+        }
+    }
+    updated.push_str(&existing[pos..]);
+    Ok((updated, changed))
+}
+
+/// The date an entry records: its biblatex `date`, else its `year` and
+/// `month`, in any of the forms `--month-format` writes. The day is the
+/// first of the month when there is none.
+pub fn entry_date(entry: &Entry<'_>) -> Option<NaiveDate> {
+    if let Some(date) = entry.field("date") {
+        let mut parts = date.trim().splitn(3, '-').map(|part| part.parse::<u32>().ok());
+        let year = parts.next()?? as i32;
+        let month = parts.next().unwrap_or(Some(1))?;
+        let day = parts.next().unwrap_or(Some(1))?;
+        return NaiveDate::from_ymd_opt(year, month, day);
+    }
+    let year = entry.field("year")?.trim().parse().ok()?;
+    let month = match entry.field("month").map(|m| m.trim().to_lowercase()) {
+        None => 1,
+        Some(month) => month.parse().ok().or_else(|| {
+            const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+            MONTHS.iter().position(|m| month.starts_with(m)).map(|i| i as u32 + 1)
+        })?,
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIB: &str = "% cargo-cite fingerprint: 0000\n@misc{serde,\n\ttitle = {serde},\n\tversion = {1.0.0},\n\tyear = 2020,\n}\n\n@misc{rand,\n\ttitle = \"rand\" # { crate},\n\tversion = {0.8.0},\n}\n";
+
+    #[test]
+    fn parse_all_reads_fields() {
+        let (entries, errors) = parse_all(BIB);
+        assert!(errors.is_empty());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].entry_type, "misc");
+        assert_eq!(entries[0].key, "serde");
+        assert_eq!(entries[0].field("version"), Some("1.0.0"));
+        assert_eq!(entries[0].field("year"), Some("2020"));
+        assert_eq!(entries[1].field("title"), Some("\"rand\" # { crate}"));
+    }
+
+    #[test]
+    fn parse_all_resumes_after_malformed_entry() {
+        // The entry is not closed, so the next one starts inside it
+        let bib = "@misc{broken,\n\ttitle = {unclosed},\n@misc{ok,\n\ttitle = {fine},\n}\n";
+        let (entries, errors) = parse_all(bib);
+        assert_eq!(entries.iter().map(|e| e.key).collect::<Vec<_>>(), ["ok"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+    }
+
+    #[test]
+    fn parse_all_skips_commented_out_at() {
+        let (entries, errors) = parse_all("% by jane@example.org\n@misc{a, title = {A}}\n");
+        assert!(errors.is_empty());
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "a");
+    }
+
+    #[test]
+    fn validate_reports_duplicates() {
+        let bib = "@misc{a, title = {A}, Title = {B}}\n@misc{a, title = {C}}\n";
+        assert_eq!(validate(bib), ["field Title repeated in entry a", "duplicate key a"]);
+        assert!(validate(BIB).is_empty());
+    }
+
+    #[test]
+    fn merge_replaces_in_place_and_appends() {
+        let existing = "% mine\n@misc{serde, title = {old}}\n\n@book{manual, title = {Kept}}\n";
+        let generated = "@misc{serde, title = {new}}\n\n% added\n@misc{rand, title = {rand}}\n";
+        let merged = merge(existing, generated).unwrap();
+        assert_eq!(merged, "% mine\n@misc{serde, title = {new}}\n\n@book{manual, title = {Kept}}\n\n% added\n@misc{rand, title = {rand}}\n");
+    }
+
+    #[test]
+    fn merge_refuses_malformed_existing() {
+        assert!(merge("@misc{a, title = {A}\n", "@misc{a, title = {B}}\n").is_err());
+    }
+
+    #[test]
+    fn update_changes_only_listed_fields() {
+        let existing = "@misc{serde,\n  title = {My title},\n  version = {1.0.0},\n}\n@misc{local, version = {0.1}}\n";
+        let generated = "@misc{serde, title = {serde}, version = {1.0.200}, year = 2024}\n";
+        let (updated, changed) = update(existing, generated, &["version", "year"]).unwrap();
+        assert_eq!(changed, 1);
+        assert_eq!(updated, "@misc{serde,\n  title = {My title},\n  version = {1.0.200},\n}\n@misc{local, version = {0.1}}\n");
+    }
+
+    #[test]
+    fn entry_date_reads_date_or_year_and_month() {
+        let (entries, _) = parse_all("@misc{a, date = {2024-05-03}}\n@misc{b, year = 2023, month = may}\n@misc{c, year = {2022}}\n");
+        let dates: Vec<_> = entries.iter().map(entry_date).collect();
+        assert_eq!(dates, [NaiveDate::from_ymd_opt(2024, 5, 3), NaiveDate::from_ymd_opt(2023, 5, 1), NaiveDate::from_ymd_opt(2022, 1, 1)]);
+    }
+}
@@ -0,0 +1,77 @@
+//! On-disk cache of API responses in `fetch::cache_dir`, so that
+//! repeated runs do not fetch the same metadata again.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long responses are reused by default.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    url: String,
+    /// Unix timestamp of the request
+    fetched: i64,
+    /// `None` when the resource was not found
+    body: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    ttl: Duration,
+    /// Fetch everything again, replacing the cached responses
+    refresh: bool,
+}
+
+/// 64-bit FNV-1a, stable across Rust releases unlike the std hasher.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, ttl: Duration, refresh: bool) -> Self {
+        Cache { dir, ttl, refresh }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(url)))
+    }
+
+    fn read(&self, url: &str) -> Option<Cached> {
+        let content = fs::read_to_string(self.path(url)).ok()?;
+        serde_json::from_str::<Cached>(&content).ok().filter(|cached| cached.url == url)
+    }
+
+    /// The cached response to `url`, `Some(None)` recording that it was not
+    /// found. Responses older than the TTL are not used.
+    pub fn get(&self, url: &str) -> Option<Option<String>> {
+        if self.refresh {
+            return None;
+        }
+        let cached = self.read(url)?;
+        let age = Utc::now().timestamp().saturating_sub(cached.fetched);
+        u64::try_from(age).is_ok_and(|age| age < self.ttl.as_secs()).then_some(cached.body)
+    }
+
+    /// Like `get`, however old the response is.
+    pub fn get_stale(&self, url: &str) -> Option<Option<String>> {
+        self.read(url).map(|cached| cached.body)
+    }
+
+    /// Record the response to `url`. The cache is best effort: failures to
+    /// write it are ignored.
+    pub fn put(&self, url: &str, body: Option<&str>) {
+        let cached = Cached {
+            url: url.to_string(),
+            fetched: Utc::now().timestamp(),
+            body: body.map(str::to_string),
+        };
+        if let Ok(json) = serde_json::to_string(&cached) {
+            let _ = fs::create_dir_all(&self.dir).and_then(|_| fs::write(self.path(url), json));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Citation files shipped by dependencies: CITATION.bib used as is, and
+//! CITATION.cff converted to BibTeX, citing its `preferred-citation` (such as
+//! a journal article) rather than the software when it has one.
+
+use crate::bibtex;
+use crate::codes::Code;
+use crate::diagnostics;
+use crate::registry;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Cff {
+    title: Option<String>,
+    /// Unquoted versions such as `1.0` are YAML numbers
+    version: Option<Value>,
+    doi: Option<String>,
+    date_released: Option<Value>,
+    url: Option<String>,
+    repository_code: Option<String>,
+    #[serde(default)]
+    authors: Vec<CffPerson>,
+    #[serde(default)]
+    identifiers: Vec<CffIdentifier>,
+    preferred_citation: Option<CffReference>,
+}
+
+/// A work cited instead of the software, such as the paper describing it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CffReference {
+    #[serde(rename = "type")]
+    kind: String,
+    title: Option<String>,
+    #[serde(default)]
+    authors: Vec<CffPerson>,
+    journal: Option<String>,
+    /// Proceedings or book a conference paper or chapter appears in
+    collection_title: Option<String>,
+    conference: Option<CffEntity>,
+    publisher: Option<CffEntity>,
+    volume: Option<Value>,
+    issue: Option<Value>,
+    start: Option<Value>,
+    end: Option<Value>,
+    pages: Option<Value>,
+    year: Option<Value>,
+    month: Option<Value>,
+    doi: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CffEntity {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct CffPerson {
+    given_names: Option<String>,
+    name_particle: Option<String>,
+    family_names: Option<String>,
+    name_suffix: Option<String>,
+    /// Name of an entity such as an organisation
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CffIdentifier {
+    #[serde(rename = "type")]
+    kind: String,
+    value: String,
+}
+
+/// A YAML scalar as text.
+fn scalar(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+impl CffPerson {
+    /// The name in BibTeX's `von Last, Jr, First` form; entities are braced
+    /// so that BibTeX does not split them.
+    fn bibtex_name(&self) -> Option<String> {
+        let Some(family) = &self.family_names else {
+            return self.name.as_ref().map(|name| format!("{{{}}}", name));
+        };
+        let last = match &self.name_particle {
+            Some(particle) => format!("{} {}", particle, family),
+            None => family.clone(),
+        };
+        Some([Some(last), self.name_suffix.clone(), self.given_names.clone()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(", "))
+    }
+}
+
+/// BibTeX entry types of CFF reference types; others are cited as `misc`.
+const ENTRY_TYPES: &[(&str, &str)] = &[
+    ("article", "article"),
+    ("conference-paper", "inproceedings"),
+    ("book", "book"),
+    ("thesis", "phdthesis"),
+    ("report", "techreport"),
+];
+
+impl CffReference {
+    /// The page range, from `start` and `end` or else `pages`.
+    fn pages(&self) -> Option<String> {
+        match (scalar(self.start.as_ref()), scalar(self.end.as_ref())) {
+            (Some(start), Some(end)) => Some(format!("{}--{}", start, end)),
+            (Some(start), None) => Some(start),
+            _ => scalar(self.pages.as_ref()),
+        }
+    }
+
+    /// The referenced work as a BibTeX entry keyed `key`.
+    fn to_bibtex(&self, key: &str) -> Result<String, String> {
+        let title = self.title.as_deref().ok_or("the preferred citation has no title")?;
+        let entry_type = ENTRY_TYPES.iter()
+            .find(|(kind, _)| *kind == self.kind)
+            .map_or("misc", |(_, entry_type)| entry_type);
+        let authors: Vec<String> = self.authors.iter().filter_map(CffPerson::bibtex_name).collect();
+        let venue = match entry_type {
+            "article" => self.journal.as_ref().map(|journal| ("journal", journal)),
+            "inproceedings" => self.collection_title.as_ref()
+                .or(self.conference.as_ref().and_then(|c| c.name.as_ref()))
+                .map(|name| ("booktitle", name)),
+            _ => None,
+        };
+        let fields = [
+            ("title", Some(title.to_string())),
+            ("author", Some(authors.join(" and ")).filter(|a| !a.is_empty())),
+            venue.map_or(("journal", None), |(field, name)| (field, Some(name.clone()))),
+            ("publisher", self.publisher.as_ref().and_then(|p| p.name.clone())),
+            ("volume", scalar(self.volume.as_ref())),
+            ("number", scalar(self.issue.as_ref())),
+            ("pages", self.pages()),
+            ("year", scalar(self.year.as_ref())),
+            ("month", scalar(self.month.as_ref())),
+            ("doi", self.doi.clone()),
+            ("url", self.url.clone()),
+        ];
+
+        let mut entry = format!("@{}{{{},\n", entry_type, key);
+        for (field, value) in fields {
+            if let Some(value) = value {
+                entry.push_str(&format!("\t{} = {{{}}},\n", field, value));
+            }
+        }
+        entry.push_str("}\n");
+        Ok(entry)
+    }
+}
+
+impl Cff {
+    fn doi(&self) -> Option<&str> {
+        self.doi.as_deref()
+            .or(self.identifiers.iter().find(|id| id.kind == "doi").map(|id| id.value.as_str()))
+    }
+
+    /// The software described by the file as a BibTeX entry keyed `key`.
+    fn to_bibtex(&self, key: &str) -> Result<String, String> {
+        let title = self.title.as_deref().ok_or("the file has no title")?;
+        let authors: Vec<String> = self.authors.iter().filter_map(CffPerson::bibtex_name).collect();
+        let date = scalar(self.date_released.as_ref())
+            .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok());
+
+        let mut entry = format!("@misc{{{},\n", key);
+        entry.push_str(&format!("\ttitle={{{}}},\n", title));
+        if !authors.is_empty() {
+            entry.push_str(&format!("\tauthor = {{{}}},\n", authors.join(" and ")));
+        }
+        if let Some(version) = scalar(self.version.as_ref()) {
+            entry.push_str(&format!("\tversion = {{{}}},\n", version));
+        }
+        if let Some(doi) = self.doi() {
+            entry.push_str(&format!("\tdoi = {{{}}},\n", doi));
+        }
+        if let Some(url) = self.repository_code.as_ref().or(self.url.as_ref()) {
+            entry.push_str(&format!("\turl = {{{}}},\n", url));
+        }
+        if let Some(date) = date {
+            entry.push_str(&format!("\tyear = {},\n", date.year()));
+            entry.push_str(&format!("\tmonth = {},\n", date.month()));
+        }
+        entry.push_str("}\n");
+        Ok(entry)
+    }
+}
+
+/// The entry the citation file at `path` asks for, keyed `key`: the first
+/// entry of a CITATION.bib, or the preferred citation of a CITATION.cff and
+/// otherwise the software it describes.
+pub fn preferred_entry(path: &Path, key: &str) -> Result<String, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if path.extension().is_some_and(|e| e == "bib") {
+        return bibtex::entries(&content).first()
+            .map(|(_, entry)| bibtex::rekey(entry, key))
+            .ok_or_else(|| String::from("the file has no BibTeX entry"));
+    }
+    let cff: Cff = serde_yaml::from_str(&content).map_err(|e| e.to_string())?;
+    match &cff.preferred_citation {
+        Some(reference) => reference.to_bibtex(key),
+        None => cff.to_bibtex(key),
+    }
+}
+
+/// The entry asked for by the citation file shipped in the crate source at
+/// `dir`, keyed `key`. An unreadable file is reported as a warning.
+pub fn shipped_entry(dir: &Path, key: &str) -> Option<String> {
+    let path = registry::citation_files(dir).into_iter().next()?;
+    match preferred_entry(&path, key) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            diagnostics::warning(Code::InvalidCitationFile, format!("Could not use {:?}: {}", path, e));
+            None
+        }
+    }
+}
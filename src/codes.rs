@@ -0,0 +1,242 @@
+//! Stable codes attached to warnings and errors, explained by `--explain`.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    MissingAuthors,
+    FetchFailed,
+    InvalidManifest,
+    InvalidConfig,
+    ReadmeDrift,
+    FormatterFailed,
+    HookFailed,
+    NoManifest,
+    PathAccess,
+    EnvironmentCheck,
+    ProcessingFailed,
+    InvalidCommand,
+    RevisionNotFound,
+    DependencyGraph,
+    InvalidCitationFile,
+    DepositFailed,
+    OutOfDate,
+    LintFailed,
+    EditedByHand,
+}
+
+const ALL: &[Code] = &[
+    Code::MissingAuthors,
+    Code::FetchFailed,
+    Code::InvalidManifest,
+    Code::InvalidConfig,
+    Code::ReadmeDrift,
+    Code::FormatterFailed,
+    Code::HookFailed,
+    Code::NoManifest,
+    Code::PathAccess,
+    Code::EnvironmentCheck,
+    Code::ProcessingFailed,
+    Code::InvalidCommand,
+    Code::RevisionNotFound,
+    Code::DependencyGraph,
+    Code::InvalidCitationFile,
+    Code::DepositFailed,
+    Code::OutOfDate,
+    Code::LintFailed,
+    Code::EditedByHand,
+];
+
+impl Code {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Code::MissingAuthors => "CITE001",
+            Code::FetchFailed => "CITE002",
+            Code::InvalidManifest => "CITE003",
+            Code::InvalidConfig => "CITE004",
+            Code::ReadmeDrift => "CITE005",
+            Code::FormatterFailed => "CITE006",
+            Code::HookFailed => "CITE007",
+            Code::NoManifest => "CITE008",
+            Code::PathAccess => "CITE009",
+            Code::EnvironmentCheck => "CITE010",
+            Code::ProcessingFailed => "CITE011",
+            Code::InvalidCommand => "CITE012",
+            Code::RevisionNotFound => "CITE013",
+            Code::DependencyGraph => "CITE014",
+            Code::InvalidCitationFile => "CITE015",
+            Code::DepositFailed => "CITE016",
+            Code::OutOfDate => "CITE017",
+            Code::LintFailed => "CITE018",
+            Code::EditedByHand => "CITE019",
+        }
+    }
+
+    /// Longer description of the problem and how to resolve it.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            Code::MissingAuthors => "\
+The package has no authors, so the generated citation has an empty author
+field. Reference managers and journals generally reject such entries.
+
+Cargo no longer requires the `authors` key, so many manifests omit it. Either
+add it back to the [package] table of Cargo.toml:
+
+    authors = [\"Jane Doe <jane@example.com>\"]
+
+or declare the citation authors in cargo-cite.toml without touching the
+manifest:
+
+    [package]
+    authors = [\"Jane Doe\"]",
+            Code::FetchFailed => "\
+Metadata for a dependency could not be fetched, so its entry lacks the
+description, authors and repository URL normally taken from crates.io.
+
+Common causes are a missing network connection, a proxy that is not
+configured, rate limiting by crates.io, or a crate name that does not exist on
+crates.io.
+
+Run `cargo cite doctor` to check connectivity, then generate again.",
+            Code::InvalidManifest => "\
+A Cargo.toml file could not be read or parsed and was skipped.
+
+Check the reported TOML error and that the file is readable.",
+            Code::InvalidConfig => "\
+The cargo-cite configuration file, or the credentials.toml holding API
+tokens, could not be read or contains an unknown or malformed setting.
+Nothing is generated until it is fixed.
+
+The TOML error names the offending key. Unknown keys are rejected so that
+typos do not silently disable a setting; see the README for the supported
+tables and keys.",
+            Code::ReadmeDrift => "\
+The citation embedded in the README between the cargo-cite markers no longer
+matches the citation file, typically after a version bump.
+
+Regenerate the citation file and the README section:
+
+    cargo cite --overwrite --readme-append",
+            Code::FormatterFailed => "\
+The formatter selected with --format failed. Formats without a built-in
+implementation are delegated to a `cargo-cite-format-<name>` executable on
+PATH, which must exist, read JSON from stdin and exit successfully.
+
+Check the spelling of the format, that the plugin is installed, and the
+plugin's own error output.",
+            Code::HookFailed => "\
+A post-generate hook from cargo-cite.toml exited unsuccessfully or could not
+be started. The citation files have already been written.
+
+Run the hook command by hand from the search directory to see its error.",
+            Code::NoManifest => "\
+No Cargo.toml was found where cargo-cite looked, or the given directory does
+not exist.
+
+Pass the crate directory with --path. In --dependencies mode, subdirectories
+are searched up to --max-depth levels.",
+            Code::PathAccess => "\
+A directory entry could not be accessed while searching for Cargo.toml files,
+usually because of permissions or a broken symbolic link. The entry was
+skipped and the search continued.",
+            Code::EnvironmentCheck => "\
+A `cargo cite doctor` check failed. The message includes the suggested fix;
+once applied, run `cargo cite doctor` again.",
+            Code::ProcessingFailed => "\
+An unexpected error, such as a failed write of the citation file, occurred
+while processing a manifest. The manifest was skipped.
+
+Check permissions on the crate directory and the reported error.",
+            Code::InvalidCommand => "\
+The command line named a command cargo-cite does not know.
+
+Run `cargo cite --help` for the available options and commands.",
+            Code::RevisionNotFound => "\
+The revision given with --as-of could not be found, the manifest did not
+exist at that revision, or no release of a dependency matching its version
+requirement had been published by then. --as-of reads history from git, so
+the crate must be inside a git repository with the tag or commits fetched.
+
+Pass a tag, a commit, or a date as YYYY-MM-DD. For a shallow clone, fetch the
+history first:
+
+    git fetch --unshallow --tags",
+            Code::DependencyGraph => "\
+`cargo metadata` could not resolve the dependency graph needed by
+--transitive or the feature options (--features, --all-features and
+--no-default-features). Without --transitive, every dependency declared in
+Cargo.toml was cited regardless of features; with it, only the direct
+dependencies.
+
+cargo must be installed and, for crates not downloaded yet, able to reach
+their registry. Run the same command by hand to see the full error:
+
+    cargo metadata --format-version 1 --manifest-path path/to/Cargo.toml",
+            Code::InvalidCitationFile => "\
+A dependency ships a CITATION.cff or CITATION.bib that could not be read: the
+CFF file is not valid YAML or lacks a title, or the BibTeX file holds no
+entry. The dependency was cited from its crates.io metadata instead.
+
+Report the problem upstream, or pass --no-citation-files to stop reading
+dependencies' citation files.",
+            Code::DepositFailed => "\
+`cargo cite deposit --zenodo` could not deposit the package on Zenodo. The
+message gives Zenodo's answer.
+
+The Zenodo token (CARGO_CITE_ZENODO_TOKEN, ZENODO_TOKEN or `zenodo` in the
+[tokens] of credentials.toml) must be a personal access token created under
+Applications in the Zenodo account settings, with the deposit:write scope and, for
+--publish, deposit:actions. Tokens of zenodo.org and of its sandbox
+(--zenodo-sandbox) are distinct. The package must be inside a git repository,
+whose HEAD is uploaded. When the package's DOI is a Zenodo DOI, a new version
+of that record is created, which requires owning it.",
+            Code::OutOfDate => "\
+`cargo cite --check` found a citation file that is missing, or that differs
+from what cargo-cite generates from the current Cargo.toml, typically after a
+version bump. The diff shows what regenerating it would change.
+
+Run `cargo cite` with the same options and --overwrite to update the file,
+then commit it.",
+            Code::LintFailed => "\
+`cargo cite lint` found problems in a citation file, or found no citation file
+to check. Each problem names the file, the line and the entry:
+
+- malformed: the file cannot be parsed, e.g. because of an unbalanced brace
+- duplicate-key: two BibTeX entries share a citation key
+- missing-field: an entry lacks its authors, version, year, or URL or DOI
+- version-mismatch: the cited version of the package differs from Cargo.toml,
+  or that of a dependency no longer matches its requirement
+
+Regenerate generated files with `cargo cite --overwrite`, and fix files
+written by hand. --message-format json reports the problems as one object.",
+            Code::EditedByHand => "\
+A citation file was changed since cargo-cite generated it: its content no
+longer matches the fingerprint on its last line. --overwrite would discard
+those changes, such as corrected author names, so the file was kept.
+
+Keep the changes in cargo-cite.toml or Cargo.toml instead, so that they
+survive regeneration, then pass --force to overwrite the file. For BibTeX
+files, --merge keeps entries written by hand and --update only refreshes the
+version fields.",
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Code {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s.trim().to_ascii_uppercase();
+        ALL.iter()
+            .copied()
+            .find(|code| code.as_str() == wanted)
+            .ok_or_else(|| format!("unknown code {:?}", s))
+    }
+}
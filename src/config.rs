@@ -0,0 +1,258 @@
+//! Project configuration read from `cargo-cite.toml`.
+//!
+//! ```toml
+//! [package]
+//! maintainers = ["Jane Doe <jane@example.com>"]
+//! entry-type = "software"
+//!
+//! [dependencies]
+//! exclude = ["*-sys", "*-derive"]
+//!
+//! [overrides.some-crate]
+//! doi = "10.5281/zenodo.1234567"
+//! authors = ["Jane Doe"]
+//!
+//! [readme]
+//! template = "Please cite {{name}} v{{version}} (doi:{{doi}})."
+//!
+//! [hooks]
+//! post-generate = "bibtex-tidy CITATION.bib"
+//! ```
+
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = "cargo-cite.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub package: PackageConfig,
+    pub dependencies: DependenciesConfig,
+    /// Corrections to the citations of dependencies, by crate name
+    pub overrides: BTreeMap<String, DependencyOverride>,
+    pub readme: ReadmeConfig,
+    pub hooks: Hooks,
+    /// Directory of the crate whose `[package]` table this is; other
+    /// workspace members read their own cargo-cite.toml
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+/// Citation details for the package that Cargo.toml does not capture, from
+/// the `[package]` table of cargo-cite.toml or `[package.metadata.cite]` in
+/// Cargo.toml.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PackageConfig {
+    /// Original authors, replacing the `authors` list from Cargo.toml
+    pub authors: Option<Vec<String>>,
+    /// Current maintainers, cited separately from the authors
+    pub maintainers: Vec<String>,
+    /// DOI under which the package is archived
+    pub doi: Option<String>,
+    /// ORCID iDs, keyed by author name
+    pub orcid: BTreeMap<String, String>,
+    /// BibTeX entry type replacing `misc`, e.g. `software`
+    pub entry_type: Option<String>,
+    /// Citation key replacing the crate name
+    pub key: Option<String>,
+    /// Corrections to the citations of dependencies, by crate name, from
+    /// `[package.metadata.cite.overrides]`
+    pub overrides: BTreeMap<String, DependencyOverride>,
+    /// Unknown keys left out of `[package.metadata.cite]`
+    #[serde(skip)]
+    pub ignored: Vec<String>,
+}
+
+impl PackageConfig {
+    const KEYS: &'static [&'static str] = &["authors", "maintainers", "doi", "orcid", "entry-type", "key", "overrides"];
+
+    /// These settings, with those left unset taken from `fallback`.
+    pub fn or(&self, fallback: &PackageConfig) -> PackageConfig {
+        let mut orcid = fallback.orcid.clone();
+        orcid.extend(self.orcid.clone());
+        PackageConfig {
+            authors: self.authors.clone().or_else(|| fallback.authors.clone()),
+            maintainers: if self.maintainers.is_empty() { fallback.maintainers.clone() } else { self.maintainers.clone() },
+            doi: self.doi.clone().or_else(|| fallback.doi.clone()),
+            orcid,
+            entry_type: self.entry_type.clone().or_else(|| fallback.entry_type.clone()),
+            key: self.key.clone().or_else(|| fallback.key.clone()),
+            overrides: fallback.overrides.clone().into_iter().chain(self.overrides.clone()).collect(),
+            ignored: Vec::new(),
+        }
+    }
+}
+
+/// Corrections to a dependency's citation, winning over fetched metadata.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DependencyOverride {
+    /// Citation key replacing the generated one
+    pub key: Option<String>,
+    pub authors: Option<Vec<String>>,
+    /// Description cited in the note
+    pub description: Option<String>,
+    pub doi: Option<String>,
+    /// Repository or homepage
+    pub url: Option<String>,
+    /// SPDX license expression
+    pub license: Option<String>,
+    /// BibTeX entry type replacing `misc`
+    pub entry_type: Option<String>,
+    /// Complete BibTeX entry cited verbatim, under its own key unless `key`
+    /// is set
+    pub bibtex: Option<String>,
+}
+
+impl DependencyOverride {
+    const KEYS: &'static [&'static str] = &["key", "authors", "description", "doi", "url", "license", "entry-type", "bibtex"];
+
+    /// Whether fields of the generated entry are corrected, so that no entry
+    /// the crate ships or registers for its DOI may be cited verbatim.
+    pub fn edits_fields(&self) -> bool {
+        self.authors.is_some() || self.description.is_some() || self.url.is_some() || self.license.is_some() || self.entry_type.is_some()
+    }
+}
+
+/// Remove the keys of `table` not in `known`, returning them after `prefix`.
+fn strip_unknown(table: &mut toml::Table, known: &[&str], prefix: &str) -> Vec<String> {
+    let unknown: Vec<String> = table.keys().filter(|key| !known.contains(&key.as_str())).cloned().collect();
+    for key in &unknown {
+        table.remove(key);
+    }
+    unknown.into_iter().map(|key| format!("{}{}", prefix, key)).collect()
+}
+
+/// `[package.metadata.cite]` in Cargo.toml. Unlike cargo-cite.toml, the
+/// manifest is shared with other tools and other versions of cargo-cite, so
+/// unknown keys are left out and listed in `ignored` rather than rejected.
+pub fn embedded_package_config<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PackageConfig, D::Error> {
+    let mut table = toml::Table::deserialize(deserializer)?;
+    let mut ignored = strip_unknown(&mut table, PackageConfig::KEYS, "");
+    if let Some(toml::Value::Table(overrides)) = table.get_mut("overrides") {
+        for (name, correction) in overrides.iter_mut() {
+            if let toml::Value::Table(correction) = correction {
+                ignored.extend(strip_unknown(correction, DependencyOverride::KEYS, &format!("overrides.{}.", name)));
+            }
+        }
+    }
+    let mut config = PackageConfig::deserialize(toml::Value::Table(table)).map_err(serde::de::Error::custom)?;
+    config.ignored = ignored;
+    Ok(config)
+}
+
+/// Which dependencies are cited, adding to `--exclude` and `--only`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DependenciesConfig {
+    /// Glob patterns of crate names left out, e.g. `*-sys`
+    pub exclude: Vec<String>,
+    /// Glob patterns of the crate names cited, the others being left out
+    pub only: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ReadmeConfig {
+    /// Text of the "Citing" section, with `{{variable}}` placeholders
+    pub template: Option<String>,
+    /// File holding the template, relative to the configuration file
+    pub template_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Hooks {
+    /// Shell commands run after citation files have been written.
+    pub post_generate: OneOrMany,
+}
+
+/// A single string or a list of strings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for OneOrMany {
+    fn default() -> Self {
+        OneOrMany::Many(Vec::new())
+    }
+}
+
+impl OneOrMany {
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            OneOrMany::One(s) => std::slice::from_ref(s),
+            OneOrMany::Many(v) => v,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from `explicit` if given, otherwise from
+    /// `cargo-cite.toml` in `dir` when present. Returns the default
+    /// configuration and no path when there is nothing to load.
+    pub fn load(explicit: Option<&Path>, dir: &Path) -> Result<(Config, Option<PathBuf>), String> {
+        let path = match explicit {
+            Some(p) => p.to_path_buf(),
+            None => {
+                let candidate = dir.join(CONFIG_FILE);
+                if !candidate.is_file() {
+                    return Ok((Config::default(), None));
+                }
+                candidate
+            }
+        };
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read config file {:?}: {}", path, e))?;
+        let mut config: Config = toml::from_str(&content)
+            .map_err(|e| format!("Invalid config file {:?}:\n{}", path, e.to_string().trim_end()))?;
+        // `[package.overrides]` is the same table as in Cargo.toml
+        let overrides = std::mem::take(&mut config.package.overrides);
+        for (name, correction) in overrides {
+            config.overrides.entry(name).or_insert(correction);
+        }
+        if let Some(file) = config.readme.template_file.take() {
+            if config.readme.template.is_some() {
+                return Err(format!("Invalid config file {:?}: set either template or template-file under [readme]", path));
+            }
+            let file = path.parent().unwrap_or(Path::new(".")).join(file);
+            let template = fs::read_to_string(&file)
+                .map_err(|e| format!("Could not read README template {:?}: {}", file, e))?;
+            config.readme.template = Some(template);
+        }
+        Ok((config, Some(path)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Metadata {
+        #[serde(deserialize_with = "embedded_package_config")]
+        cite: PackageConfig,
+    }
+
+    #[test]
+    fn embedded_package_config_ignores_unknown_keys() {
+        let metadata: Metadata = toml::from_str("[cite]\nkey = \"mine\"\nfuture = 1\n[cite.overrides.serde]\nurl = \"https://serde.rs\"\nmirror = true\n").unwrap();
+        assert_eq!(metadata.cite.key.as_deref(), Some("mine"));
+        assert_eq!(metadata.cite.overrides["serde"].url.as_deref(), Some("https://serde.rs"));
+        assert_eq!(metadata.cite.ignored, ["future", "overrides.serde.mirror"]);
+    }
+
+    #[test]
+    fn config_file_rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("[package]\nfuture = 1\n").is_err());
+        assert!(toml::from_str::<Config>("[overrides.serde]\nmirror = true\n").is_err());
+    }
+}
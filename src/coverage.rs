@@ -0,0 +1,70 @@
+//! `cargo cite coverage`: per-dependency citation quality and its gaps.
+
+use crate::stats::Citability;
+use crate::ResolvedDependency;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Debug, Serialize)]
+pub struct DependencyScore {
+    pub name: String,
+    pub version: Option<String>,
+    pub score: usize,
+    pub max_score: usize,
+    /// Missing details, worded as what to add
+    pub missing: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoverageReport {
+    pub dependencies: Vec<DependencyScore>,
+}
+
+impl DependencyScore {
+    fn of(dep: &ResolvedDependency) -> Self {
+        let citability = Citability::of(dep);
+        let checks = [
+            (citability.authors, "authors"),
+            (citability.repository, "repository URL"),
+            (citability.doi, "DOI"),
+            (citability.citation_file, "CITATION.cff/CITATION.bib"),
+        ];
+        DependencyScore {
+            name: dep.name.clone(),
+            version: dep.version.clone(),
+            score: checks.iter().filter(|(ok, _)| *ok).count(),
+            max_score: checks.len(),
+            missing: checks.iter().filter(|(ok, _)| !*ok).map(|(_, what)| *what).collect(),
+        }
+    }
+}
+
+impl CoverageReport {
+    /// Score each distinct dependency, least citable first.
+    pub fn collect(dependencies: &[ResolvedDependency]) -> Self {
+        let mut seen = BTreeSet::new();
+        let mut scores: Vec<DependencyScore> = dependencies.iter()
+            .filter(|dep| seen.insert(dep.name.as_str()))
+            .map(DependencyScore::of)
+            .collect();
+        scores.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.name.cmp(&b.name)));
+        CoverageReport { dependencies: scores }
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = String::from("Citation coverage (least citable first):\n");
+        for dep in &self.dependencies {
+            text.push_str(&format!("  {}/{}  {}", dep.score, dep.max_score, dep.name));
+            if let Some(version) = &dep.version {
+                text.push_str(&format!(" {}", version));
+            }
+            if !dep.missing.is_empty() {
+                text.push_str(&format!("  (missing: {})", dep.missing.join(", ")));
+            }
+            text.push('\n');
+        }
+        let complete = self.dependencies.iter().filter(|d| d.missing.is_empty()).count();
+        text.push_str(&format!("{} of {} dependencies are fully citable.", complete, self.dependencies.len()));
+        text
+    }
+}
@@ -0,0 +1,142 @@
+//! Access tokens for the APIs cargo-cite queries, which raise their rate
+//! limits and give access to private repositories.
+//!
+//! Each token is taken from `CARGO_CITE_<SERVICE>_TOKEN`, the service's usual
+//! environment variable, or the `[tokens]` table of the user's
+//! `credentials.toml`, kept outside of projects so that it is not committed:
+//!
+//! ```toml
+//! [tokens]
+//! github = "ghp_..."
+//! zenodo = "..."
+//! ```
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+pub const CREDENTIALS_FILE: &str = "credentials.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    GitHub,
+    GitLab,
+    Codeberg,
+    Zenodo,
+}
+
+const ALL: &[Service] = &[Service::GitHub, Service::GitLab, Service::Codeberg, Service::Zenodo];
+
+impl Service {
+    /// Key of the token in the `[tokens]` table.
+    fn key(self) -> &'static str {
+        match self {
+            Service::GitHub => "github",
+            Service::GitLab => "gitlab",
+            Service::Codeberg => "codeberg",
+            Service::Zenodo => "zenodo",
+        }
+    }
+
+    /// The environment variable the service's own tools read the token from.
+    fn common_var(self) -> &'static str {
+        match self {
+            Service::GitHub => "GITHUB_TOKEN",
+            Service::GitLab => "GITLAB_TOKEN",
+            Service::Codeberg => "CODEBERG_TOKEN",
+            Service::Zenodo => "ZENODO_TOKEN",
+        }
+    }
+
+    /// Prefix of the API URLs the token is sent to as a bearer token. Zenodo
+    /// tokens are only sent when depositing.
+    fn api_prefix(self) -> Option<&'static str> {
+        match self {
+            Service::GitHub => Some("https://api.github.com/"),
+            Service::GitLab => Some("https://gitlab.com/api/"),
+            Service::Codeberg => Some("https://codeberg.org/api/"),
+            Service::Zenodo => None,
+        }
+    }
+
+    /// How to provide the token, for error messages.
+    pub fn hint(self) -> String {
+        format!(
+            "Set CARGO_CITE_{}_TOKEN or {}, or add {} to the [tokens] of {}.",
+            self.key().to_uppercase(),
+            self.common_var(),
+            self.key(),
+            credentials_path().map_or_else(|| String::from(CREDENTIALS_FILE), |p| p.display().to_string()),
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CredentialsFile {
+    tokens: BTreeMap<String, String>,
+}
+
+/// `$XDG_CONFIG_HOME/cargo-cite/credentials.toml`, defaulting to
+/// `~/.config/cargo-cite/credentials.toml`.
+pub fn credentials_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("cargo-cite").join(CREDENTIALS_FILE))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    tokens: BTreeMap<&'static str, String>,
+}
+
+impl Credentials {
+    /// The tokens set in the environment.
+    pub fn from_env() -> Self {
+        Credentials::with_file(BTreeMap::new())
+    }
+
+    /// The tokens set in the environment or the credentials file.
+    pub fn load() -> Result<Self, String> {
+        let file = match credentials_path() {
+            Some(path) if path.is_file() => {
+                let content = fs::read_to_string(&path).map_err(|e| format!("could not read {:?}: {}", path, e))?;
+                let tokens = toml::from_str::<CredentialsFile>(&content).map_err(|e| format!("invalid {:?}: {}", path, e))?.tokens;
+                if let Some(key) = tokens.keys().find(|key| !ALL.iter().any(|s| s.key() == key.as_str())) {
+                    return Err(format!("unknown service {:?} under [tokens] in {:?} (expected github, gitlab, codeberg or zenodo)", key, path));
+                }
+                tokens
+            }
+            _ => BTreeMap::new(),
+        };
+        Ok(Credentials::with_file(file))
+    }
+
+    fn with_file(mut file: BTreeMap<String, String>) -> Self {
+        let var = |name: String| env::var(name).ok().filter(|t| !t.is_empty());
+        let tokens = ALL.iter()
+            .filter_map(|&service| {
+                let token = var(format!("CARGO_CITE_{}_TOKEN", service.key().to_uppercase()))
+                    .or_else(|| var(service.common_var().to_string()))
+                    .or_else(|| file.remove(service.key()).filter(|t| !t.is_empty()))?;
+                Some((service.key(), token))
+            })
+            .collect();
+        Credentials { tokens }
+    }
+
+    pub fn token(&self, service: Service) -> Option<&str> {
+        self.tokens.get(service.key()).map(String::as_str)
+    }
+
+    /// The token to send with a request to `url`, if one is configured.
+    pub fn for_url(&self, url: &str) -> Option<&str> {
+        let service = ALL.iter().find(|s| s.api_prefix().is_some_and(|prefix| url.starts_with(prefix)))?;
+        self.token(*service)
+    }
+}
@@ -0,0 +1,333 @@
+//! Reporting of progress messages, diagnostics and generated entries.
+//!
+//! Messages are printed as human-oriented text by default. With
+//! `--message-format json` every message becomes one JSON object per line on
+//! stdout, tagged with a cargo-style `reason` field.
+//!
+//! `--message-format gha` prints warnings and errors as GitHub Actions
+//! workflow commands, which annotate the files they concern on pull requests.
+//!
+//! `--quiet` leaves only errors, and `-v`/`-vv` add the requests made and
+//! details of how manifests and lockfiles were read. Human messages go to
+//! stderr when the citations themselves are written to stdout.
+
+use crate::codes::Code;
+use crate::progress;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+    /// GitHub Actions workflow commands
+    GitHubActions,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            "gha" => Ok(MessageFormat::GitHubActions),
+            other => Err(format!("unknown message format {:?} (expected \"human\", \"json\" or \"gha\")", other)),
+        }
+    }
+}
+
+/// How much is reported, from `--quiet` and `--verbose`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Errors only
+    Quiet,
+    Normal,
+    /// Also the requests made (`-v`)
+    Verbose,
+    /// Also parsing details (`-vv`)
+    Debug,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Debug,
+    Info,
+    Note,
+    Warning,
+    Error,
+}
+
+impl Level {
+    fn prefix(self, code: Option<Code>) -> String {
+        let label = match self {
+            Level::Info => return String::new(),
+            Level::Debug => "Debug",
+            Level::Note => "Note",
+            Level::Warning => "Warning",
+            Level::Error => "Error",
+        };
+        match code {
+            Some(code) => format!("{}[{}]: ", label, code),
+            None => format!("{}: ", label),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Package,
+    Dependency,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+enum Message<'a> {
+    Diagnostic {
+        level: Level,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        code: Option<&'static str>,
+        /// File the diagnostic is about
+        #[serde(skip_serializing_if = "Option::is_none")]
+        file: Option<&'a Path>,
+        message: &'a str,
+    },
+    CitationEntry {
+        key: &'a str,
+        kind: EntryKind,
+        manifest_path: &'a Path,
+        content: &'a str,
+    },
+    Summary {
+        processed: usize,
+        skipped: usize,
+        warnings: usize,
+    },
+}
+
+static FORMAT: OnceLock<MessageFormat> = OnceLock::new();
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+/// Whether stdout carries the generated citations.
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+/// Warnings reported so far, counted in the summary.
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_message_format(format: MessageFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub fn message_format() -> MessageFormat {
+    FORMAT.get().copied().unwrap_or(MessageFormat::Human)
+}
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    let _ = VERBOSITY.set(verbosity);
+}
+
+pub fn verbosity() -> Verbosity {
+    VERBOSITY.get().copied().unwrap_or(Verbosity::Normal)
+}
+
+/// Send human messages to stderr, as the citations are written to stdout.
+pub fn reserve_stdout() {
+    STDOUT_RESERVED.store(true, Ordering::Relaxed);
+}
+
+fn print_human(text: &str) {
+    progress::suspend(|| match STDOUT_RESERVED.load(Ordering::Relaxed) {
+        true => eprintln!("{}", text),
+        false => println!("{}", text),
+    });
+}
+
+fn emit_json(message: &Message) {
+    if let Ok(line) = serde_json::to_string(message) {
+        println!("{}", line);
+    }
+}
+
+/// `text` escaped for a workflow command, as are its properties with
+/// `property`.
+fn escape_workflow(text: &str, property: bool) -> String {
+    let mut escaped = text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    if property {
+        escaped = escaped.replace(':', "%3A").replace(',', "%2C");
+    }
+    escaped
+}
+
+/// `file` relative to the checked out repository, as annotations expect.
+fn workspace_relative(file: &Path) -> PathBuf {
+    let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+    let root = env::var_os("GITHUB_WORKSPACE").map(PathBuf::from).or_else(|| env::current_dir().ok());
+    root.and_then(|root| fs::canonicalize(root).ok())
+        .and_then(|root| file.strip_prefix(root).ok().map(Path::to_path_buf))
+        .unwrap_or(file)
+}
+
+fn diagnostic(level: Level, code: Option<Code>, file: Option<&Path>, message: &str) {
+    match message_format() {
+        MessageFormat::GitHubActions if !matches!(level, Level::Info) => {
+            let command = match level {
+                Level::Debug => "debug",
+                Level::Note | Level::Info => "notice",
+                Level::Warning => "warning",
+                Level::Error => "error",
+            };
+            let mut properties = Vec::new();
+            if let Some(file) = file {
+                properties.push(format!("file={}", escape_workflow(&workspace_relative(file).display().to_string(), true)));
+            }
+            if let Some(code) = code {
+                properties.push(format!("title={}", code));
+            }
+            let properties = if properties.is_empty() { String::new() } else { format!(" {}", properties.join(",")) };
+            print_human(&format!("::{}{}::{}", command, properties, escape_workflow(message.trim(), false)));
+        }
+        MessageFormat::Human | MessageFormat::GitHubActions => {
+            // Continuation lines are aligned with the text following the prefix
+            // and leading blank lines stay ahead of the prefix.
+            let prefix = level.prefix(code);
+            let indent = " ".repeat(prefix.len());
+            let body = message.trim_start_matches('\n');
+            let mut text = String::from(&message[..message.len() - body.len()]);
+            text.push_str(&prefix);
+            for (i, line) in body.split('\n').enumerate() {
+                if i > 0 {
+                    text.push('\n');
+                    text.push_str(&indent);
+                }
+                text.push_str(line);
+            }
+            print_human(&text);
+        }
+        MessageFormat::Json => emit_json(&Message::Diagnostic {
+            level,
+            code: code.map(Code::as_str),
+            file,
+            message: message.trim(),
+        }),
+    }
+}
+
+/// Requests made, shown with `-v`.
+pub fn verbose(message: impl AsRef<str>) {
+    if verbosity() >= Verbosity::Verbose {
+        diagnostic(Level::Debug, None, None, message.as_ref());
+    }
+}
+
+/// Details of how inputs were read, shown with `-vv`.
+pub fn debug(message: impl AsRef<str>) {
+    if verbosity() >= Verbosity::Debug {
+        diagnostic(Level::Debug, None, None, message.as_ref());
+    }
+}
+
+/// Progress and status information.
+pub fn info(message: impl AsRef<str>) {
+    if verbosity() > Verbosity::Quiet {
+        diagnostic(Level::Info, None, None, message.as_ref());
+    }
+}
+
+pub fn note(message: impl AsRef<str>) {
+    if verbosity() > Verbosity::Quiet {
+        diagnostic(Level::Note, None, None, message.as_ref());
+    }
+}
+
+/// Warnings are counted in the summary even when `--quiet` hides them.
+pub fn warning(code: Code, message: impl AsRef<str>) {
+    WARNINGS.fetch_add(1, Ordering::Relaxed);
+    if verbosity() > Verbosity::Quiet {
+        diagnostic(Level::Warning, Some(code), None, message.as_ref());
+    }
+}
+
+/// A warning about `file`, which annotations point at.
+pub fn warning_in(code: Code, file: &Path, message: impl AsRef<str>) {
+    WARNINGS.fetch_add(1, Ordering::Relaxed);
+    if verbosity() > Verbosity::Quiet {
+        diagnostic(Level::Warning, Some(code), Some(file), message.as_ref());
+    }
+}
+
+pub fn error(code: Code, message: impl AsRef<str>) {
+    diagnostic(Level::Error, Some(code), None, message.as_ref());
+}
+
+/// An error about `file`, which annotations point at.
+pub fn error_in(code: Code, file: &Path, message: impl AsRef<str>) {
+    diagnostic(Level::Error, Some(code), Some(file), message.as_ref());
+}
+
+/// Report a generated citation entry. Only machine-readable output carries
+/// entries on stdout; human output writes them to the citation files.
+pub fn entry(key: &str, kind: EntryKind, manifest_path: &Path, content: &str) {
+    if message_format() == MessageFormat::Json {
+        emit_json(&Message::CitationEntry { key, kind, manifest_path, content });
+    }
+}
+
+/// Report a structured result such as statistics. Human output prints `text`,
+/// to stderr when stdout carries citations; machine-readable output emits
+/// `data` tagged with `reason`.
+pub fn report<T: Serialize>(reason: &str, data: &T, text: &str) {
+    match message_format() {
+        MessageFormat::Human | MessageFormat::GitHubActions => print_human(text.trim_end()),
+        MessageFormat::Json => {
+            let mut value = serde_json::to_value(data).unwrap_or(serde_json::Value::Null);
+            if let serde_json::Value::Object(fields) = &mut value {
+                fields.insert(String::from("reason"), serde_json::Value::from(reason));
+            }
+            println!("{}", value);
+        }
+    }
+}
+
+/// The number of warnings reported so far.
+pub fn warning_count() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// Start counting warnings again, for a new run in the same process.
+pub fn reset_warning_count() {
+    WARNINGS.store(0, Ordering::Relaxed);
+}
+
+/// Report the final processed/skipped counts and the number of warnings.
+pub fn summary(processed: usize, skipped: usize) {
+    let warnings = warning_count();
+    match message_format() {
+        MessageFormat::Human | MessageFormat::GitHubActions => {
+            if verbosity() > Verbosity::Quiet && (processed > 0 || skipped > 0) {
+                let mut text = String::from("\nSummary:");
+                if processed > 0 {
+                    text.push_str(&format!("\n- Successfully processed: {} file{}",
+                        processed,
+                        if processed == 1 { "" } else { "s" }
+                    ));
+                }
+                if skipped > 0 {
+                    text.push_str(&format!("\n- Skipped due to errors: {} file{}",
+                        skipped,
+                        if skipped == 1 { "" } else { "s" }
+                    ));
+                }
+                if warnings > 0 {
+                    text.push_str(&format!("\n- Warnings: {}", warnings));
+                }
+                print_human(&text);
+            }
+        }
+        MessageFormat::Json => emit_json(&Message::Summary { processed, skipped, warnings }),
+    }
+}
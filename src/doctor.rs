@@ -0,0 +1,97 @@
+//! `cargo cite doctor`: checks of the environment cargo-cite depends on.
+
+use crate::codes::Code;
+use crate::config::Config;
+use crate::diagnostics;
+use crate::fetch::{self, MetadataFetcher};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Outcome of a single check: a description of what was found, or of what
+/// went wrong together with how to fix it.
+type Check = Result<String, (String, String)>;
+
+async fn check_endpoint(fetcher: &MetadataFetcher, name: &str, url: &str) -> Check {
+    match fetcher.probe(url).await {
+        Ok(status) if status < 500 && status != 429 => Ok(format!("{} reachable (HTTP {})", name, status)),
+        Ok(status) => Err((
+            format!("{} answered with HTTP {}", name, status),
+            String::from("The service may be rate limiting or unavailable; retry later."),
+        )),
+        Err(e) => Err((
+            format!("{} unreachable: {}", name, e),
+            String::from("Check your network connection, firewall and HTTPS_PROXY settings."),
+        )),
+    }
+}
+
+fn check_cache_dir() -> Check {
+    let dir = fetch::cache_dir().ok_or_else(|| (
+        String::from("No cache directory could be determined"),
+        String::from("Set XDG_CACHE_HOME or HOME."),
+    ))?;
+    let probe = dir.join(".doctor");
+    fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe))
+        .map(|_| format!("Cache directory {:?} is writable", dir))
+        .map_err(|e| (
+            format!("Cache directory {:?} is not writable: {}", dir, e),
+            String::from("Fix the directory permissions or point XDG_CACHE_HOME elsewhere."),
+        ))
+}
+
+fn check_config(explicit: Option<&Path>, dir: &Path) -> Check {
+    match Config::load(explicit, dir) {
+        Ok((_, Some(path))) => Ok(format!("Config file {:?} is valid", path)),
+        Ok((_, None)) => Ok(String::from("No config file (defaults in use)")),
+        Err(e) => Err((e, String::from("Correct the reported entry; see the README for the supported settings."))),
+    }
+}
+
+fn check_git() -> Check {
+    match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(output) => Err((
+            format!("git failed with {}", output.status),
+            String::from("Reinstall git or fix its configuration."),
+        )),
+        Err(e) => Err((
+            format!("git is not available: {}", e),
+            String::from("Install git and make sure it is on PATH."),
+        )),
+    }
+}
+
+/// Run all checks, reporting each one. Returns whether all of them passed.
+pub async fn run(fetcher: &MetadataFetcher, config_path: Option<&Path>, dir: &Path) -> bool {
+    diagnostics::info("Checking the cargo-cite environment...");
+    let checks = vec![
+        check_endpoint(fetcher, "crates.io API", &format!("{}/crates/serde", fetcher.registry_api_url())).await,
+        check_endpoint(fetcher, "GitHub API", "https://api.github.com/rate_limit").await,
+        check_cache_dir(),
+        check_config(config_path, dir),
+        check_git(),
+    ];
+
+    let mut failed = 0;
+    for check in checks {
+        match check {
+            Ok(message) => diagnostics::info(format!("[ok]   {}", message)),
+            Err((problem, fix)) => {
+                failed += 1;
+                diagnostics::warning(Code::EnvironmentCheck, format!("{}\nFix: {}", problem, fix));
+            }
+        }
+    }
+
+    if failed == 0 {
+        diagnostics::info("All checks passed.");
+    } else {
+        diagnostics::error(Code::EnvironmentCheck, format!("{} check{} failed.", failed, if failed == 1 { "" } else { "s" }));
+    }
+    failed == 0
+}
@@ -0,0 +1,317 @@
+//! `--enrich`: details of a dependency's repository from its host's API, such
+//! as the contributors and latest release.
+//!
+//! Each host is a `RepoHost`; supporting a new one takes an implementation
+//! and a `Host` variant.
+
+use crate::codes::Code;
+use crate::diagnostics;
+use crate::fetch::{parse_date, repository_path, MetadataFetcher};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// People cited at most per repository.
+const MAX_CONTRIBUTORS: usize = 10;
+
+/// GitLab access level of maintainers and owners.
+const GITLAB_MAINTAINER: u32 = 40;
+
+/// Repository host queried with `--enrich`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Host {
+    GitHub,
+    GitLab,
+    Codeberg,
+    Bitbucket,
+}
+
+const HOSTS: &[Host] = &[Host::GitHub, Host::GitLab, Host::Codeberg, Host::Bitbucket];
+
+impl FromStr for Host {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "github" => Ok(Host::GitHub),
+            "gitlab" => Ok(Host::GitLab),
+            "codeberg" => Ok(Host::Codeberg),
+            "bitbucket" => Ok(Host::Bitbucket),
+            other => Err(format!("unknown repository host {:?} (expected github, gitlab, codeberg or bitbucket)", other)),
+        }
+    }
+}
+
+/// What the repository host adds to a dependency's citation.
+#[derive(Debug, Clone, Default)]
+pub struct Enrichment {
+    pub description: Option<String>,
+    /// Display names of the people behind the repository: its top
+    /// contributors, or its maintainers on hosts that do not rank them
+    pub contributors: Vec<String>,
+    /// Tag of the latest release and its publication date
+    pub latest_release: Option<(String, NaiveDate)>,
+}
+
+impl Enrichment {
+    /// Publication date of `version`, when it is the latest release.
+    pub fn release_date(&self, version: &str) -> Option<NaiveDate> {
+        let (tag, date) = self.latest_release.as_ref()?;
+        (tag.trim_start_matches('v') == version).then_some(*date)
+    }
+}
+
+/// A repository host whose REST API describes the repositories it serves.
+trait RepoHost {
+    /// Domain of the host's repository URLs, e.g. `github.com`.
+    fn domain(&self) -> &'static str;
+
+    /// Details of the repository at `owner/name` `path` on this host.
+    async fn enrich(&self, fetcher: &MetadataFetcher, path: &str) -> Result<Enrichment, String>;
+}
+
+/// `owner` and `name` from an `owner/name[/...]` repository path.
+fn owner_and_name(path: &str) -> Result<(&str, &str), String> {
+    let mut parts = path.splitn(3, '/');
+    match (parts.next(), parts.next()) {
+        (Some(owner), Some(name)) => Ok((owner, name)),
+        _ => Err(format!("{:?} is not an owner/name repository path", path)),
+    }
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.filter(|t| !t.trim().is_empty())
+}
+
+struct GitHub;
+
+#[derive(Debug, Deserialize)]
+struct GitHubRepository {
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    published_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubContributor {
+    login: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitHubUser {
+    name: Option<String>,
+}
+
+impl RepoHost for GitHub {
+    fn domain(&self) -> &'static str {
+        "github.com"
+    }
+
+    async fn enrich(&self, fetcher: &MetadataFetcher, path: &str) -> Result<Enrichment, String> {
+        let (owner, name) = owner_and_name(path)?;
+        let api = format!("https://api.github.com/repos/{}/{}", owner, name);
+        let repository: GitHubRepository = fetcher.get_json(&api).await?;
+
+        // Repositories without releases answer 404
+        let release: Option<GitHubRelease> = fetcher.get_json_opt(&format!("{}/releases/latest", api)).await.unwrap_or_default();
+        let latest_release = release.and_then(|r| Some((r.tag_name, parse_date(r.published_at.as_deref()?)?)));
+
+        let contributors: Vec<GitHubContributor> = fetcher.get_json(&format!("{}/contributors?per_page={}", api, MAX_CONTRIBUTORS)).await.unwrap_or_default();
+        let mut names = Vec::new();
+        for contributor in contributors.iter().filter(|c| c.kind == "User") {
+            let user: Option<GitHubUser> = fetcher.get_json(&format!("https://api.github.com/users/{}", contributor.login)).await.ok();
+            names.push(non_empty(user.and_then(|u| u.name)).unwrap_or_else(|| contributor.login.clone()));
+        }
+
+        Ok(Enrichment {
+            description: non_empty(repository.description),
+            contributors: names,
+            latest_release,
+        })
+    }
+}
+
+struct GitLab;
+
+#[derive(Debug, Deserialize)]
+struct GitLabProject {
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabRelease {
+    tag_name: String,
+    released_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabMember {
+    name: String,
+    access_level: u32,
+}
+
+impl RepoHost for GitLab {
+    fn domain(&self) -> &'static str {
+        "gitlab.com"
+    }
+
+    async fn enrich(&self, fetcher: &MetadataFetcher, path: &str) -> Result<Enrichment, String> {
+        // Projects may sit in nested groups, so the whole path identifies them
+        let api = format!("https://gitlab.com/api/v4/projects/{}", path.replace('/', "%2F"));
+        let project: GitLabProject = fetcher.get_json(&api).await?;
+
+        // Releases are listed newest first
+        let releases: Vec<GitLabRelease> = fetcher.get_json(&format!("{}/releases?per_page=1", api)).await.unwrap_or_default();
+        let latest_release = releases.into_iter().next()
+            .and_then(|r| Some((r.tag_name, parse_date(r.released_at.as_deref()?)?)));
+
+        let members: Vec<GitLabMember> = fetcher.get_json(&format!("{}/members/all?per_page=100", api)).await.unwrap_or_default();
+        let maintainers = members.into_iter()
+            .filter(|m| m.access_level >= GITLAB_MAINTAINER)
+            .map(|m| m.name)
+            .take(MAX_CONTRIBUTORS)
+            .collect();
+
+        Ok(Enrichment {
+            description: non_empty(project.description),
+            contributors: maintainers,
+            latest_release,
+        })
+    }
+}
+
+struct Codeberg;
+
+#[derive(Debug, Deserialize)]
+struct GiteaRepository {
+    description: Option<String>,
+    owner: GiteaUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaUser {
+    login: String,
+    full_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaRelease {
+    tag_name: String,
+    published_at: Option<String>,
+}
+
+impl RepoHost for Codeberg {
+    fn domain(&self) -> &'static str {
+        "codeberg.org"
+    }
+
+    async fn enrich(&self, fetcher: &MetadataFetcher, path: &str) -> Result<Enrichment, String> {
+        let (owner, name) = owner_and_name(path)?;
+        let api = format!("https://codeberg.org/api/v1/repos/{}/{}", owner, name);
+        let repository: GiteaRepository = fetcher.get_json(&api).await?;
+
+        let release: Option<GiteaRelease> = fetcher.get_json_opt(&format!("{}/releases/latest", api)).await.unwrap_or_default();
+        let latest_release = release.and_then(|r| Some((r.tag_name, parse_date(r.published_at.as_deref()?)?)));
+
+        // Gitea does not rank contributors; the owning user maintains the repository
+        let owner = non_empty(repository.owner.full_name).unwrap_or(repository.owner.login);
+        Ok(Enrichment {
+            description: non_empty(repository.description),
+            contributors: vec![owner],
+            latest_release,
+        })
+    }
+}
+
+struct Bitbucket;
+
+#[derive(Debug, Deserialize)]
+struct BitbucketRepository {
+    description: Option<String>,
+    owner: BitbucketAccount,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketAccount {
+    display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketPage<T> {
+    values: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketTag {
+    name: String,
+    target: BitbucketCommit,
+}
+
+#[derive(Debug, Deserialize)]
+struct BitbucketCommit {
+    date: Option<String>,
+}
+
+impl RepoHost for Bitbucket {
+    fn domain(&self) -> &'static str {
+        "bitbucket.org"
+    }
+
+    async fn enrich(&self, fetcher: &MetadataFetcher, path: &str) -> Result<Enrichment, String> {
+        let (workspace, name) = owner_and_name(path)?;
+        let api = format!("https://api.bitbucket.org/2.0/repositories/{}/{}", workspace, name);
+        let repository: BitbucketRepository = fetcher.get_json(&api).await?;
+
+        // Bitbucket has no releases; the most recent tag stands in for one
+        let tags: Option<BitbucketPage<BitbucketTag>> = fetcher.get_json(&format!("{}/refs/tags?sort=-target.date&pagelen=1", api)).await.ok();
+        let latest_release = tags.and_then(|t| t.values.into_iter().next())
+            .and_then(|t| Some((t.name, parse_date(t.target.date.as_deref()?)?)));
+
+        Ok(Enrichment {
+            description: non_empty(repository.description),
+            contributors: vec![repository.owner.display_name],
+            latest_release,
+        })
+    }
+}
+
+impl Host {
+    /// Domain of the host's repository URLs.
+    fn domain(self) -> &'static str {
+        match self {
+            Host::GitHub => GitHub.domain(),
+            Host::GitLab => GitLab.domain(),
+            Host::Codeberg => Codeberg.domain(),
+            Host::Bitbucket => Bitbucket.domain(),
+        }
+    }
+
+    async fn enrich(self, fetcher: &MetadataFetcher, path: &str) -> Result<Enrichment, String> {
+        match self {
+            Host::GitHub => GitHub.enrich(fetcher, path).await,
+            Host::GitLab => GitLab.enrich(fetcher, path).await,
+            Host::Codeberg => Codeberg.enrich(fetcher, path).await,
+            Host::Bitbucket => Bitbucket.enrich(fetcher, path).await,
+        }
+    }
+}
+
+/// Details of the repository at `url` from its host's API, if the host is
+/// among `hosts`. Failures are reported as warnings.
+pub async fn enrich(fetcher: &MetadataFetcher, hosts: &[Host], url: &str) -> Option<Enrichment> {
+    let (domain, path) = repository_path(url)?;
+    let host = HOSTS.iter().copied().find(|h| h.domain() == domain && hosts.contains(h))?;
+    match host.enrich(fetcher, &path).await {
+        Ok(enrichment) => Some(enrichment),
+        Err(e) => {
+            diagnostics::warning(Code::FetchFailed, format!("Could not fetch repository details of {}: {}", url, e));
+            None
+        }
+    }
+}
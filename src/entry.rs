@@ -0,0 +1,457 @@
+//! The format-independent citation model that every formatter renders.
+
+use crate::diagnostics::{self, EntryKind};
+use crate::fetch::CrateInfo;
+use crate::{DependencyKind, PackageInfo, ResolvedDependency, SourceKind};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// One cited piece of software: the package itself or one of its dependencies.
+#[derive(Debug, Clone, Serialize)]
+pub struct CitationEntry {
+    pub key: String,
+    pub kind: EntryKind,
+    pub name: String,
+    /// Name a renamed dependency is imported as
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    /// Cited separately from the authors
+    pub maintainers: Vec<String>,
+    /// ORCID iDs of the authors, keyed by name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub orcid: BTreeMap<String, String>,
+    /// BibTeX entry type replacing `misc`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_type: Option<String>,
+    pub date: Option<NaiveDate>,
+    /// Date the URL was accessed, kept from the existing citation file while
+    /// the version is unchanged
+    #[serde(skip)]
+    pub accessed: Option<NaiveDate>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    /// License file of a crate without an SPDX license expression
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license_file: Option<String>,
+    pub keywords: Vec<String>,
+    /// crates.io category slugs of a dependency, e.g. `encoding`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    pub doi: Option<String>,
+    pub swhid: Option<String>,
+    /// Where a dependency comes from; `None` for the package
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceKind>,
+    /// Table the dependency is declared in; `None` for the package
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependency_kind: Option<DependencyKind>,
+    /// Target a dependency is limited to, e.g. `cfg(windows)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Alternative registry a dependency is published on, by name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    pub path: Option<String>,
+    pub git: Option<String>,
+    /// SHA-256 of the registry package locked in Cargo.lock
+    pub checksum: Option<String>,
+    /// Source locked in Cargo.lock, when not crates.io: an alternative
+    /// registry, or a git URL with the exact commit
+    pub locked_source: Option<String>,
+    /// Manifest declaring the package or dependency
+    pub manifest_path: PathBuf,
+    /// Metadata as fetched from crates.io or the local registry
+    pub metadata: Option<CrateInfo>,
+    /// Downloaded source in the local cargo registry or git checkouts, when
+    /// available
+    pub local_source: Option<PathBuf>,
+    /// Workspace members depending on a dependency, when several are cited
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub used_by: Vec<String>,
+    /// BibTeX entry chosen by the dependency's authors or registered for its
+    /// DOI, used verbatim
+    #[serde(skip)]
+    pub preferred_bibtex: Option<String>,
+}
+
+impl CitationEntry {
+    pub fn from_package(package: &PackageInfo, key: &str, manifest_path: PathBuf, date: NaiveDate) -> Self {
+        CitationEntry {
+            key: key.to_string(),
+            kind: EntryKind::Package,
+            name: package.name.clone(),
+            alias: None,
+            version: Some(package.version.clone()),
+            description: package.description.clone(),
+            authors: package.authors.clone(),
+            maintainers: package.maintainers.clone(),
+            orcid: package.orcid.clone(),
+            entry_type: package.entry_type.clone(),
+            date: Some(date),
+            accessed: None,
+            repository: package.repository.clone(),
+            homepage: None,
+            license: package.license.clone(),
+            license_file: package.license_file.clone(),
+            keywords: package.keywords.clone().unwrap_or_default(),
+            categories: Vec::new(),
+            doi: package.doi.clone(),
+            swhid: None,
+            source: None,
+            dependency_kind: None,
+            target: None,
+            registry: None,
+            path: None,
+            git: None,
+            checksum: None,
+            locked_source: None,
+            manifest_path,
+            metadata: None,
+            local_source: None,
+            used_by: Vec::new(),
+            preferred_bibtex: None,
+        }
+    }
+
+    pub fn from_dependency(dep: &ResolvedDependency) -> Self {
+        let metadata = dep.metadata.as_ref();
+        let enrichment = dep.enrichment.as_ref();
+        let archival = dep.archival.as_ref();
+        let categories = metadata.and_then(|m| m.categories.clone()).unwrap_or_default();
+        // Categories are cited by name after the crate's own keywords
+        let mut keywords = metadata.and_then(|m| m.keywords.clone()).unwrap_or_default();
+        for name in categories.iter().map(|slug| category_name(slug)) {
+            if !keywords.iter().any(|k| k.eq_ignore_ascii_case(&name)) {
+                keywords.push(name);
+            }
+        }
+        let mut entry = CitationEntry {
+            key: dep.key.clone(),
+            kind: EntryKind::Dependency,
+            name: dep.name.clone(),
+            alias: dep.alias.clone(),
+            version: dep.version.clone(),
+            description: metadata.and_then(|m| m.description.clone())
+                .or_else(|| enrichment.and_then(|e| e.description.clone())),
+            authors: archival.map(|r| r.creators.clone())
+                .filter(|a| !a.is_empty())
+                .or_else(|| metadata.and_then(|m| m.authors.clone()))
+                .filter(|a| !a.is_empty())
+                .or_else(|| enrichment.map(|e| e.contributors.clone()))
+                .filter(|a| !a.is_empty())
+                .unwrap_or_else(|| dep.owners.clone()),
+            maintainers: Vec::new(),
+            orcid: BTreeMap::new(),
+            entry_type: None,
+            date: archival.and_then(|r| r.date()).or(dep.date),
+            accessed: None,
+            repository: metadata.and_then(|m| m.repository.clone()),
+            homepage: metadata.and_then(|m| m.homepage.clone()),
+            license: metadata.and_then(|m| m.license.clone()),
+            license_file: metadata.and_then(|m| m.license_file.clone()),
+            keywords,
+            categories,
+            doi: dep.doi.clone(),
+            swhid: dep.swhid.clone(),
+            source: Some(dep.source_kind()),
+            dependency_kind: Some(dep.kind),
+            target: dep.target.clone(),
+            registry: dep.registry.clone(),
+            path: dep.path.clone(),
+            git: dep.git.clone(),
+            checksum: dep.lock.as_ref().and_then(|l| l.checksum.clone()),
+            locked_source: dep.lock.as_ref().and_then(|l| l.source_url()).map(str::to_string),
+            manifest_path: dep.manifest_path.clone(),
+            metadata: dep.metadata.clone(),
+            local_source: dep.local_source.clone(),
+            used_by: Vec::new(),
+            preferred_bibtex: dep.preferred_entry.clone(),
+        };
+        // Corrections win over everything fetched
+        if let Some(overrides) = &dep.overrides {
+            if let Some(authors) = &overrides.authors {
+                entry.authors = authors.clone();
+            }
+            if let Some(description) = &overrides.description {
+                entry.description = Some(description.clone());
+            }
+            if let Some(url) = &overrides.url {
+                entry.repository = Some(url.clone());
+            }
+            if let Some(license) = &overrides.license {
+                entry.license = Some(license.clone());
+            }
+            if let Some(entry_type) = &overrides.entry_type {
+                entry.entry_type = Some(entry_type.clone());
+            }
+        }
+        entry
+    }
+
+    /// The SPDX license expression, or where a custom license is found.
+    pub fn license_text(&self) -> Option<String> {
+        self.license.clone()
+            .or_else(|| self.license_file.as_ref().map(|file| format!("See {}", file)))
+    }
+
+    /// Whether this is a dependency published on crates.io.
+    pub fn is_registry(&self) -> bool {
+        self.source == Some(SourceKind::Registry) && self.registry.is_none()
+    }
+
+    /// Where the software lives: its git source, repository or homepage.
+    pub fn url(&self) -> Option<&str> {
+        self.git.as_deref()
+            .or(self.repository.as_deref())
+            .or(self.homepage.as_deref())
+    }
+
+    /// Page to link the entry to: crates.io for registry dependencies,
+    /// otherwise `url`.
+    pub fn link(&self) -> Option<String> {
+        if self.is_registry() {
+            return Some(format!("https://crates.io/crates/{}", self.name));
+        }
+        self.url().map(str::to_string)
+    }
+}
+
+/// Merge the entries citing the same crate at the same version and from the
+/// same source, as when several members of a workspace depend on it, so that
+/// each is cited once. `used_by` lists the packages declaring each, named by
+/// `package_name` from their manifest. A crate some members use as a normal
+/// dependency and others as a dev or build dependency is cited as a normal
+/// one, and the target and alias are dropped unless all agree.
+pub fn dedupe(entries: Vec<CitationEntry>, package_name: impl Fn(&Path) -> String) -> Vec<CitationEntry> {
+    let mut merged: Vec<CitationEntry> = Vec::with_capacity(entries.len());
+    for mut entry in entries {
+        let user = package_name(&entry.manifest_path);
+        let same = merged.iter_mut().find(|m| {
+            m.name == entry.name && m.version == entry.version && m.path == entry.path && m.git == entry.git && m.registry == entry.registry
+        });
+        let Some(first) = same else {
+            entry.used_by = vec![user];
+            merged.push(entry);
+            continue;
+        };
+        if !first.used_by.contains(&user) {
+            first.used_by.push(user);
+        }
+        if first.dependency_kind != entry.dependency_kind {
+            first.dependency_kind = Some(DependencyKind::Normal);
+        }
+        if first.target != entry.target {
+            first.target = None;
+        }
+        if first.alias != entry.alias {
+            first.alias = None;
+        }
+    }
+    for entry in &mut merged {
+        entry.used_by.sort();
+    }
+    merged
+}
+
+/// Readable name of the crates.io category `slug`, from its last part:
+/// `parser-implementations` is "Parser implementations", and
+/// `development-tools::testing` is "Testing".
+pub fn category_name(slug: &str) -> String {
+    let last = slug.rsplit("::").next().unwrap_or(slug).replace('-', " ");
+    let mut chars = last.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Rename the entries whose key `package` or an earlier entry already has,
+/// so that every key of a document is unique: a second `serde` becomes
+/// `serde-2`. Keys differing only in case collide, as BibTeX compares
+/// them case-insensitively. Verbatim BibTeX entries are renamed along.
+pub fn unique_keys(package: Option<&CitationEntry>, entries: &mut [CitationEntry]) {
+    let mut seen: BTreeSet<String> = package.map(|p| p.key.to_lowercase()).into_iter().collect();
+    for entry in entries {
+        if seen.insert(entry.key.to_lowercase()) {
+            continue;
+        }
+        let mut n = 2;
+        while seen.contains(&format!("{}-{}", entry.key, n).to_lowercase()) {
+            n += 1;
+        }
+        let key = format!("{}-{}", entry.key, n);
+        diagnostics::info(format!("Citation key {} is taken; citing {} as {}", entry.key, entry.name, key));
+        entry.preferred_bibtex = entry.preferred_bibtex.as_deref().map(|bibtex| crate::bibtex::rekey(bibtex, &key));
+        seen.insert(key.to_lowercase());
+        entry.key = key;
+    }
+}
+
+/// Order of the cited dependencies, selected with `--sort`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// As declared: by manifest, then dependency table, then name
+    #[default]
+    None,
+    /// Alphabetically by citation key
+    Name,
+    /// Normal, then dev, then build dependencies, each by key
+    Kind,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(SortOrder::None),
+            "name" => Ok(SortOrder::Name),
+            "kind" => Ok(SortOrder::Kind),
+            other => Err(format!("unknown sort order {:?} (expected name, kind or none)", other)),
+        }
+    }
+}
+
+/// Sort `entries` in `order`.
+pub fn sort(entries: &mut [CitationEntry], order: SortOrder) {
+    let rank = |entry: &CitationEntry| match entry.dependency_kind {
+        None => 0,
+        Some(DependencyKind::Normal) => 1,
+        Some(DependencyKind::Dev) => 2,
+        Some(DependencyKind::Build) => 3,
+    };
+    match order {
+        SortOrder::None => {}
+        SortOrder::Name => entries.sort_by_cached_key(|entry| entry.key.to_lowercase()),
+        SortOrder::Kind => entries.sort_by_cached_key(|entry| (rank(entry), entry.key.to_lowercase())),
+    }
+}
+
+/// How the cited dependencies are grouped, selected with `--group-by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Runtime, development and build dependencies
+    Kind,
+    /// The first crates.io category of each dependency
+    Category,
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "kind" => Ok(GroupBy::Kind),
+            "category" => Ok(GroupBy::Category),
+            other => Err(format!("unknown grouping {:?} (expected kind or category)", other)),
+        }
+    }
+}
+
+/// Dependencies cited in one section or file.
+#[derive(Debug, Clone)]
+pub struct Group {
+    /// Heading of the section, e.g. "Build dependencies"
+    pub title: String,
+    /// Suffix of the file name with `--split`, e.g. `build`
+    pub slug: String,
+    pub entries: Vec<CitationEntry>,
+}
+
+/// `entries` split into groups `by`, in order, leaving out empty ones. The
+/// entries keep their order within each group.
+pub fn group(entries: Vec<CitationEntry>, by: GroupBy) -> Vec<Group> {
+    match by {
+        GroupBy::Kind => {
+            let kinds = [
+                (DependencyKind::Normal, "Runtime dependencies", "runtime"),
+                (DependencyKind::Dev, "Development dependencies", "dev"),
+                (DependencyKind::Build, "Build dependencies", "build"),
+            ];
+            kinds.into_iter()
+                .map(|(kind, title, slug)| Group {
+                    title: title.to_string(),
+                    slug: slug.to_string(),
+                    entries: entries.iter().filter(|e| e.dependency_kind.unwrap_or(DependencyKind::Normal) == kind).cloned().collect(),
+                })
+                .filter(|group| !group.entries.is_empty())
+                .collect()
+        }
+        GroupBy::Category => {
+            // By category name, then the crates without one
+            let mut groups: BTreeMap<(bool, String), Group> = BTreeMap::new();
+            for entry in entries {
+                let (title, slug) = match entry.categories.first() {
+                    Some(category) => (category_name(category), category.replace("::", "-")),
+                    None => ("Other".to_string(), "other".to_string()),
+                };
+                groups.entry((slug == "other", title.clone()))
+                    .or_insert_with(|| Group { title, slug, entries: Vec::new() })
+                    .entries
+                    .push(entry);
+            }
+            groups.into_values().collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A dependency entry on `name` declared in `manifest`.
+    fn dependency(name: &str, version: &str, manifest: &str) -> CitationEntry {
+        let package: PackageInfo = toml::from_str(&format!("name = {:?}\nversion = {:?}", name, version)).unwrap();
+        let mut entry = CitationEntry::from_package(&package, name, PathBuf::from(manifest), NaiveDate::default());
+        entry.kind = EntryKind::Dependency;
+        entry.dependency_kind = Some(DependencyKind::Normal);
+        entry
+    }
+
+    fn keys(entries: &[CitationEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.key.as_str()).collect()
+    }
+
+    #[test]
+    fn unique_keys_numbers_repeated_keys() {
+        let package = dependency("serde", "1.0.0", "Cargo.toml");
+        let mut entries = vec![dependency("serde", "0.9.0", "Cargo.toml"), dependency("rand", "0.8.0", "Cargo.toml"), dependency("serde", "0.8.0", "Cargo.toml")];
+        unique_keys(Some(&package), &mut entries);
+        assert_eq!(keys(&entries), ["serde-2", "rand", "serde-3"]);
+
+        let mut entries = vec![dependency("Serde", "0.9.0", "Cargo.toml"), dependency("serde-2", "0.1.0", "Cargo.toml")];
+        unique_keys(Some(&package), &mut entries);
+        assert_eq!(keys(&entries), ["Serde-2", "serde-2-2"]);
+    }
+
+    #[test]
+    fn unique_keys_rekeys_verbatim_entries() {
+        let mut entries = vec![dependency("rand", "0.8.0", "Cargo.toml"), dependency("rand", "0.7.0", "Cargo.toml")];
+        entries[1].preferred_bibtex = Some("@software{rand,\n  title = {rand}\n}".to_string());
+        unique_keys(None, &mut entries);
+        assert_eq!(keys(&entries), ["rand", "rand-2"]);
+        assert!(entries[1].preferred_bibtex.as_deref().unwrap().starts_with("@software{rand-2,"));
+    }
+
+    #[test]
+    fn dedupe_merges_same_crate_and_version() {
+        let mut dev = dependency("serde", "1.0.0", "b/Cargo.toml");
+        dev.dependency_kind = Some(DependencyKind::Dev);
+        dev.target = Some("cfg(unix)".to_string());
+        let entries = vec![dependency("serde", "1.0.0", "c/Cargo.toml"), dev, dependency("serde", "0.9.0", "a/Cargo.toml"), dependency("serde", "1.0.0", "c/Cargo.toml")];
+        let merged = dedupe(entries, |path| path.parent().unwrap().display().to_string());
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].used_by, ["b", "c"]);
+        assert_eq!(merged[0].dependency_kind, Some(DependencyKind::Normal));
+        assert_eq!(merged[0].target, None);
+        assert_eq!(merged[1].version.as_deref(), Some("0.9.0"));
+        assert_eq!(merged[1].used_by, ["a"]);
+    }
+}
@@ -0,0 +1,656 @@
+//! Network lookups of crate and repository metadata.
+
+use crate::cache::Cache;
+use crate::codes::Code;
+use crate::credentials::{Credentials, Service};
+use crate::diagnostics;
+use crate::registries::AltRegistry;
+use chrono::{DateTime, NaiveDate};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USER_AGENT: &str = "cargo-cite";
+
+/// The crates.io web API, unless `--registry-api-url` points at a mirror.
+pub const CRATES_IO_API: &str = "https://crates.io/api/v1";
+
+/// Retries of a request that failed for reasons that may be transient.
+const MAX_RETRIES: u32 = 3;
+/// Wait before the first retry, doubled for each further one.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Longest `Retry-After` waited for; requests asked to wait longer fail.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Shortest interval between requests to the crates.io web API, as its
+/// crawler policy asks: https://crates.io/data-access#api. The sparse index
+/// and static.crates.io downloads are not limited.
+const CRATES_IO_API_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The crates.io sparse index, served from a CDN without the web API's rate
+/// limits. See https://doc.rust-lang.org/cargo/reference/registry-index.html.
+const SPARSE_INDEX: &str = "https://index.crates.io";
+
+#[derive(Debug, Deserialize)]
+struct CratesIoResponse {
+    #[serde(rename = "crate")]
+    crate_info: CrateInfo,
+    #[serde(default)]
+    versions: Vec<VersionInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionInfo {
+    license: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct OrcidPerson {
+    name: Option<OrcidName>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct OrcidName {
+    given_names: Option<OrcidValue>,
+    family_name: Option<OrcidValue>,
+    /// The name the researcher chose to be credited as
+    credit_name: Option<OrcidValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrcidValue {
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionsResponse {
+    versions: Vec<PublishedVersion>,
+}
+
+/// Where a registry serves its sparse index and web API, crates.io unless
+/// the dependency comes from an alternative registry.
+#[derive(Debug, Clone)]
+pub struct RegistryApi {
+    /// Absent for registries indexed in a git repository
+    index: Option<String>,
+    /// Absent for registries that have none
+    api: Option<String>,
+    /// Sent as the `Authorization` header, as cargo does
+    token: Option<String>,
+}
+
+impl RegistryApi {
+    fn index_url(&self, crate_name: &str) -> Result<String, String> {
+        self.index.as_ref()
+            .map(|index| format!("{}/{}", index, index_path(crate_name)))
+            .ok_or_else(|| String::from("the registry index is a git repository, which is only read through downloaded sources"))
+    }
+
+    fn api_url(&self, path: &str) -> Result<String, String> {
+        self.api.as_ref()
+            .map(|api| format!("{}/{}", api, path))
+            .ok_or_else(|| String::from("the registry has no web API"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexConfig {
+    api: Option<String>,
+}
+
+/// A release listed in the sparse index.
+#[derive(Debug, Deserialize)]
+pub struct IndexVersion {
+    pub vers: String,
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// Path of the index file of `crate_name`, as cargo lays out the index.
+fn index_path(crate_name: &str) -> String {
+    let name = crate_name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// The newest release in `versions` matching `req` that is not yanked.
+pub fn newest_release(versions: &[IndexVersion], req: &VersionReq) -> Option<Version> {
+    versions.iter()
+        .filter(|v| !v.yanked)
+        .filter_map(|v| Version::parse(&v.vers).ok())
+        .filter(|v| req.matches(v))
+        .max()
+}
+
+/// A release listed in the crates.io version history.
+#[derive(Debug, Deserialize)]
+pub struct PublishedVersion {
+    pub num: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub yanked: bool,
+    /// Absent for releases older than crates.io's record of publishers
+    pub published_by: Option<User>,
+}
+
+/// A crates.io user, as listed among owners and publishers.
+#[derive(Debug, Deserialize)]
+pub struct User {
+    pub login: String,
+    pub name: Option<String>,
+    /// `user` or `team`
+    #[serde(default)]
+    pub kind: Option<String>,
+}
+
+impl User {
+    /// The display name, or the login when there is none.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().filter(|n| !n.trim().is_empty()).unwrap_or(&self.login)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OwnersResponse {
+    users: Vec<User>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CrateInfo {
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub authors: Option<Vec<String>>,
+    /// Time the first version was published
+    pub created_at: Option<String>,
+    /// SPDX license expression of the newest version
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Read from downloaded sources only; crates.io does not report it
+    #[serde(default)]
+    pub license_file: Option<String>,
+    #[serde(default)]
+    pub keywords: Option<Vec<String>>,
+    /// crates.io category slugs, e.g. `parser-implementations`
+    #[serde(default)]
+    pub categories: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryResponse {
+    created_at: Option<String>,
+}
+
+/// Directory for cached API responses: `$XDG_CACHE_HOME/cargo-cite`, falling
+/// back to `~/.cache/cargo-cite`.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .or_else(|| env::var_os("LOCALAPPDATA").map(PathBuf::from))?;
+    Some(base.join("cargo-cite"))
+}
+
+/// The wait a rate-limited `response` asks for in seconds with `Retry-After`.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let seconds = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Parse an RFC 3339 timestamp as returned by the crates.io, GitHub and GitLab APIs.
+pub fn parse_date(timestamp: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| t.date_naive())
+}
+
+/// Split a repository URL into its host and `owner/name` path.
+pub(crate) fn repository_path(url: &str) -> Option<(&str, String)> {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    let (host, path) = rest.split_once('/')?;
+    // GitLab web URLs put views such as `/-/tree/main` after the project path
+    let path = path.split("/-/").next().unwrap_or(path);
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    if path.is_empty() {
+        return None;
+    }
+    Some((host.trim_start_matches("www."), path.to_string()))
+}
+
+pub struct MetadataFetcher {
+    client: reqwest::Client,
+    /// Responses reused across runs, unless `--no-cache`
+    cache: Option<Cache>,
+    /// Never access the network, answering from the cache alone
+    offline: bool,
+    /// The crates.io index and web API
+    crates_io: RegistryApi,
+    /// Tokens sent to the APIs of repository hosts and Zenodo
+    credentials: Credentials,
+    /// When the crates.io web API was last sent a request, held while
+    /// waiting for the next one to be allowed
+    crates_io_api_sent: tokio::sync::Mutex<Option<tokio::time::Instant>>,
+}
+
+impl Default for MetadataFetcher {
+    fn default() -> Self {
+        MetadataFetcher::new()
+    }
+}
+
+impl MetadataFetcher {
+    pub fn new() -> Self {
+        MetadataFetcher::with_cache(None)
+    }
+
+    /// A fetcher reusing the responses stored in `cache`.
+    pub fn with_cache(cache: Option<Cache>) -> Self {
+        MetadataFetcher {
+            client: reqwest::Client::new(),
+            cache,
+            offline: false,
+            crates_io: RegistryApi {
+                index: Some(SPARSE_INDEX.to_string()),
+                api: Some(CRATES_IO_API.to_string()),
+                token: None,
+            },
+            credentials: Credentials::from_env(),
+            crates_io_api_sent: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// The same fetcher, authenticating with `credentials`.
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// The token configured for `service`.
+    pub fn token(&self, service: Service) -> Option<&str> {
+        self.credentials.token(service)
+    }
+
+    /// The same fetcher, sending its requests with `client`.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The same fetcher, querying the crates.io web API at `url`, such as a
+    /// mirror, instead.
+    pub fn registry_api(mut self, url: &str) -> Self {
+        self.crates_io.api = Some(url.trim_end_matches('/').to_string());
+        self
+    }
+
+    pub fn registry_api_url(&self) -> &str {
+        self.crates_io.api.as_deref().unwrap_or(CRATES_IO_API)
+    }
+
+    /// Where `registry` serves its index and web API, the latter as its
+    /// index's config.json declares.
+    pub(crate) async fn alt_registry(&self, registry: &AltRegistry) -> RegistryApi {
+        let index = registry.sparse_index().map(str::to_string);
+        let api = match &index {
+            Some(index) => {
+                let url = format!("{}/config.json", index);
+                let config = self.get_json_auth::<IndexConfig>(&url, registry.token.as_deref()).await;
+                self.lookup(&format!("the configuration of registry {}", registry.name), config)
+                    .and_then(|config| config.api)
+                    .map(|api| format!("{}/api/v1", api.trim_end_matches('/')))
+            }
+            None => None,
+        };
+        RegistryApi { index, api, token: registry.token.clone() }
+    }
+
+    /// The same fetcher, failing every request that is not cached rather
+    /// than accessing the network.
+    pub fn offline(mut self) -> Self {
+        self.offline = true;
+        self
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// A `method` request to `url`, unless offline.
+    fn request(&self, method: reqwest::Method, url: &str) -> Result<reqwest::RequestBuilder, String> {
+        if self.offline {
+            return Err(format!("{} was not fetched: the network is not accessed with --offline", url));
+        }
+        Ok(self.client.request(method, url).header("User-Agent", USER_AGENT))
+    }
+
+    /// Send `request`, retrying it up to `MAX_RETRIES` times with exponential
+    /// backoff after connection failures, server errors and rate limiting,
+    /// when the server's `Retry-After` is followed. Requests that are not
+    /// idempotent are only retried when rate limited, as they were not handled.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let request = request.build().map_err(|e| e.to_string())?;
+        let idempotent = request.method() != reqwest::Method::POST;
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            if request.url().as_str().starts_with(CRATES_IO_API) {
+                self.crates_io_api_turn().await;
+            }
+            diagnostics::verbose(format!("{} {}", request.method(), request.url()));
+            let Some(this) = request.try_clone() else {
+                return self.client.execute(request).await.map_err(|e| e.to_string());
+            };
+            let result = self.client.execute(this).await;
+            let wait = match &result {
+                Ok(response) if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => Some(retry_after(response).unwrap_or(delay)),
+                Ok(response) if idempotent && response.status().is_server_error() => Some(delay),
+                Err(e) if idempotent && (e.is_connect() || e.is_timeout()) => Some(delay),
+                _ => None,
+            };
+            match wait {
+                Some(wait) if attempt < MAX_RETRIES && wait <= MAX_RETRY_AFTER => {
+                    diagnostics::verbose(format!("Retrying {} in {:.1}s", request.url(), wait.as_secs_f64()));
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                    delay *= 2;
+                }
+                _ => return result.map_err(|e| e.to_string()),
+            }
+        }
+    }
+
+    /// Wait until the crates.io web API may be sent another request, at most
+    /// one per `CRATES_IO_API_INTERVAL` however many lookups run at once.
+    async fn crates_io_api_turn(&self) {
+        let mut sent = self.crates_io_api_sent.lock().await;
+        if let Some(sent) = *sent {
+            tokio::time::sleep_until(sent + CRATES_IO_API_INTERVAL).await;
+        }
+        *sent = Some(tokio::time::Instant::now());
+    }
+
+    pub(crate) async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        self.get_json_opt(url).await?
+            .ok_or_else(|| format!("{} returned HTTP 404 Not Found", url))
+    }
+
+    /// Like `get_json`, but a 404 response yields `Ok(None)`.
+    pub(crate) async fn get_json_opt<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Option<T>, String> {
+        self.get_json_auth(url, None).await
+    }
+
+    /// Like `get_json_opt`, sending `token` as the `Authorization` header.
+    async fn get_json_auth<T: serde::de::DeserializeOwned>(&self, url: &str, token: Option<&str>) -> Result<Option<T>, String> {
+        self.get_text_opt(url, token).await?
+            .map(|body| serde_json::from_str::<T>(&body))
+            .transpose()
+            .map_err(|e| format!("unexpected response from {}: {}", url, e))
+    }
+
+    /// The body of the response to `url`, from the cache when it holds it,
+    /// `None` for a 404 response.
+    async fn get_text_opt(&self, url: &str, token: Option<&str>) -> Result<Option<String>, String> {
+        let cached = self.cache.as_ref().and_then(|cache| match self.offline {
+            // Even outdated responses beat none
+            true => cache.get_stale(url),
+            false => cache.get(url),
+        });
+        Ok(match cached {
+            Some(body) => {
+                diagnostics::debug(format!("Cached response for {}", url));
+                body
+            }
+            None => {
+                let body = self.fetch_text(url, token).await?;
+                if let Some(cache) = &self.cache {
+                    cache.put(url, body.as_deref());
+                }
+                body
+            }
+        })
+    }
+
+    /// The body of a JSON API response, `None` for a 404 response.
+    async fn fetch_text(&self, url: &str, token: Option<&str>) -> Result<Option<String>, String> {
+        let mut request = self.request(reqwest::Method::GET, url)?
+            .header("Accept", "application/json");
+        if let Some(token) = token {
+            request = request.header(reqwest::header::AUTHORIZATION, token);
+        } else if let Some(token) = self.credentials.for_url(url) {
+            request = request.bearer_auth(token);
+        }
+        let response = self.send(request).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(format!("{} returned HTTP {}", url, response.status()));
+        }
+        response.text().await
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Name of the researcher with ORCID iD `orcid` on their public record: the
+    /// credit name, or else the given and family names.
+    pub async fn orcid_name(&self, orcid: &str) -> Result<Option<String>, String> {
+        let id = orcid.trim_start_matches("https://orcid.org/");
+        let url = format!("https://pub.orcid.org/v3.0/{}/person", id);
+        let Some(name) = self.get_json::<OrcidPerson>(&url).await?.name else {
+            return Ok(None);
+        };
+        if let Some(credit) = name.credit_name {
+            return Ok(Some(credit.value));
+        }
+        let parts: Vec<String> = [name.given_names, name.family_name].into_iter().flatten().map(|v| v.value).collect();
+        Ok((!parts.is_empty()).then(|| parts.join(" ")))
+    }
+
+    /// BibTeX entry registered for `doi`, through doi.org content negotiation.
+    pub async fn doi_bibtex(&self, doi: &str) -> Result<String, String> {
+        let url = format!("https://doi.org/{}", doi);
+        let response = self.send(self.request(reqwest::Method::GET, &url)?
+            .header("Accept", "application/x-bibtex")).await?;
+        if !response.status().is_success() {
+            return Err(format!("{} returned HTTP {}", url, response.status()));
+        }
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if !text.trim_start().starts_with('@') {
+            return Err(format!("{} did not return a BibTeX entry", url));
+        }
+        Ok(text)
+    }
+
+    /// Send `body` as JSON to `url` with `token` as bearer token, returning the
+    /// JSON response. A null body sends none.
+    pub(crate) async fn send_json<T: serde::de::DeserializeOwned>(&self, method: reqwest::Method, url: &str, token: &str, body: &serde_json::Value) -> Result<T, String> {
+        let mut request = self.request(method, url)?
+            .bearer_auth(token);
+        if !body.is_null() {
+            request = request.json(body);
+        }
+        let response = self.send(request).await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(format!("{} returned HTTP {}: {}", url, status, text.trim()));
+        }
+        response.json::<T>().await
+            .map_err(|e| format!("unexpected response from {}: {}", url, e))
+    }
+
+    /// Upload `content` to `url` with a PUT request authorized by `token`.
+    pub(crate) async fn upload(&self, url: &str, token: &str, content: Vec<u8>) -> Result<(), String> {
+        let response = self.send(self.request(reqwest::Method::PUT, url)?
+            .header("Content-Type", "application/octet-stream")
+            .bearer_auth(token)
+            .body(content)).await?;
+        if !response.status().is_success() {
+            return Err(format!("upload to {} returned HTTP {}", url, response.status()));
+        }
+        Ok(())
+    }
+
+    /// Delete the resource at `url` with a request authorized by `token`.
+    pub(crate) async fn delete(&self, url: &str, token: &str) -> Result<(), String> {
+        let response = self.send(self.request(reqwest::Method::DELETE, url)?
+            .bearer_auth(token)).await?;
+        if !response.status().is_success() {
+            return Err(format!("{} returned HTTP {}", url, response.status()));
+        }
+        Ok(())
+    }
+
+    /// The found result of a lookup of `what` that only completes an entry,
+    /// warning about failures, which are expected offline.
+    fn lookup<T>(&self, what: &str, result: Result<Option<T>, String>) -> Option<T> {
+        result.unwrap_or_else(|e| {
+            if !self.offline {
+                diagnostics::warning(Code::FetchFailed, format!("Could not fetch {}: {}", what, e));
+            }
+            None
+        })
+    }
+
+    /// Issue a request to `url`, returning the HTTP status code.
+    pub async fn probe(&self, url: &str) -> Result<u16, String> {
+        self.request(reqwest::Method::GET, url)?
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map(|response| response.status().as_u16())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Metadata of `crate_name` from the web API of `registry`, crates.io
+    /// when `None`.
+    pub async fn crate_info(&self, registry: Option<&RegistryApi>, crate_name: &str) -> Result<CrateInfo, String> {
+        let registry = registry.unwrap_or(&self.crates_io);
+        let url = registry.api_url(&format!("crates/{}", crate_name))?;
+        let data = self.get_json_auth::<CratesIoResponse>(&url, registry.token.as_deref()).await?
+            .ok_or_else(|| format!("{} returned HTTP 404 Not Found", url))?;
+        // Versions are listed newest first
+        let license = data.versions.into_iter().find_map(|v| v.license);
+        Ok(CrateInfo { license, ..data.crate_info })
+    }
+
+    /// Every release of `crate_name` listed in the sparse index of
+    /// `registry`, oldest first, or `None` when no such crate is published.
+    pub async fn index_versions(&self, registry: Option<&RegistryApi>, crate_name: &str) -> Result<Option<Vec<IndexVersion>>, String> {
+        let registry = registry.unwrap_or(&self.crates_io);
+        let url = registry.index_url(crate_name)?;
+        let Some(text) = self.get_text_opt(&url, registry.token.as_deref()).await? else { return Ok(None) };
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<IndexVersion>(line).map_err(|e| format!("unexpected response from {}: {}", url, e)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some)
+    }
+
+    /// Every published version of `crate_name`, newest first.
+    pub async fn versions(&self, registry: Option<&RegistryApi>, crate_name: &str) -> Result<Vec<PublishedVersion>, String> {
+        let registry = registry.unwrap_or(&self.crates_io);
+        let url = registry.api_url(&format!("crates/{}/versions", crate_name))?;
+        self.get_json_auth::<VersionsResponse>(&url, registry.token.as_deref()).await?
+            .map(|response| response.versions)
+            .ok_or_else(|| format!("{} returned HTTP 404 Not Found", url))
+    }
+
+    /// Publication date of `version` of `crate_name` on `registry`. A version
+    /// requirement such as `1.2` is dated by the newest release matching it.
+    pub async fn release_date(&self, registry: Option<&RegistryApi>, crate_name: &str, version: &str) -> Option<NaiveDate> {
+        let req = VersionReq::parse(version).ok()?;
+        let exact = Version::parse(version).ok();
+        let registry = registry.unwrap_or(&self.crates_io);
+        // Registries without a web API do not record release dates
+        let url = registry.api_url(&format!("crates/{}/versions", crate_name)).ok()?;
+        // Unpublished crates have no release date
+        let response = self.lookup(&format!("the releases of {}", crate_name), self.get_json_auth::<VersionsResponse>(&url, registry.token.as_deref()).await)?;
+        response.versions
+            .iter()
+            .filter_map(|v| Some((Version::parse(&v.num).ok()?, v)))
+            .filter(|(num, v)| match &exact {
+                Some(exact) => num == exact,
+                None => !v.yanked && req.matches(num),
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .and_then(|(_, v)| parse_date(&v.created_at))
+    }
+
+    /// Names to cite as the authors of a crate that lists none: its owners
+    /// on `registry`, or else the publisher of `version`. Teams are left out.
+    pub async fn owner_authors(&self, registry: Option<&RegistryApi>, crate_name: &str, version: Option<&str>) -> Vec<String> {
+        let registry_api = registry.unwrap_or(&self.crates_io);
+        let Ok(url) = registry_api.api_url(&format!("crates/{}/owners", crate_name)) else { return Vec::new() };
+        let owners: Vec<String> = self.lookup(&format!("the owners of {}", crate_name), self.get_json_auth::<OwnersResponse>(&url, registry_api.token.as_deref()).await)
+            .map(|response| response.users)
+            .unwrap_or_default()
+            .iter()
+            .filter(|user| user.kind.as_deref() != Some("team"))
+            .map(|user| user.display_name().to_string())
+            .collect();
+        if !owners.is_empty() {
+            return owners;
+        }
+        let Some(version) = version.and_then(|v| Version::parse(v).ok()) else { return Vec::new() };
+        self.versions(registry, crate_name).await
+            .unwrap_or_default()
+            .iter()
+            .find(|v| Version::parse(&v.num).ok().as_ref() == Some(&version))
+            .and_then(|v| v.published_by.as_ref())
+            .map(|user| vec![user.display_name().to_string()])
+            .unwrap_or_default()
+    }
+
+    /// Creation date of a GitHub or GitLab repository.
+    pub async fn repository_created(&self, repository_url: &str) -> Option<NaiveDate> {
+        let (host, path) = repository_path(repository_url)?;
+        let api_url = match host {
+            "github.com" => {
+                // Only the owner/name part identifies the repository
+                let mut parts = path.splitn(3, '/');
+                let (owner, name) = (parts.next()?, parts.next()?);
+                format!("https://api.github.com/repos/{}/{}", owner, name)
+            }
+            "gitlab.com" => format!("https://gitlab.com/api/v4/projects/{}", path.replace('/', "%2F")),
+            _ => return None,
+        };
+        let response: RepositoryResponse = self.lookup(&format!("the creation date of {}", repository_url), self.get_json_opt(&api_url).await)?;
+        parse_date(response.created_at.as_deref()?)
+    }
+
+    /// Best available date for software that has no release date of its own:
+    /// the repository creation date, then the first crates.io release.
+    pub async fn fallback_date(&self, repository_url: Option<&str>, crate_info: Option<&CrateInfo>) -> Option<NaiveDate> {
+        if let Some(url) = repository_url {
+            if let Some(date) = self.repository_created(url).await {
+                return Some(date);
+            }
+        }
+        crate_info
+            .and_then(|info| info.created_at.as_deref())
+            .and_then(parse_date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn crates_io_api_requests_are_spaced() {
+        let fetcher = MetadataFetcher::new();
+        let start = tokio::time::Instant::now();
+        fetcher.crates_io_api_turn().await;
+        assert!(start.elapsed() < CRATES_IO_API_INTERVAL);
+        fetcher.crates_io_api_turn().await;
+        assert!(start.elapsed() >= CRATES_IO_API_INTERVAL);
+    }
+}
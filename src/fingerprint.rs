@@ -0,0 +1,38 @@
+//! Fingerprints of generated files: a comment on their last line holding a
+//! hash of the rest, which tells whether a file was edited by hand since
+//! cargo-cite wrote it.
+
+/// Text of the comment, followed by the hash.
+const MARKER: &str = "cargo-cite fingerprint:";
+
+/// FNV-1a hash of `text`, stable across Rust versions and platforms.
+pub(crate) fn hash(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// `text` without its fingerprint line, and the hash that line records.
+pub fn strip(text: &str) -> (&str, Option<&str>) {
+    let body = text.strip_suffix('\n').unwrap_or(text);
+    let start = body.rfind('\n').map_or(0, |i| i + 1);
+    let recorded = body[start..].split_once(MARKER)
+        .and_then(|(_, rest)| rest.split_whitespace().next());
+    match recorded {
+        Some(recorded) => (&text[..start], Some(recorded)),
+        None => (text, None),
+    }
+}
+
+/// The text of the fingerprint line of `content`, to write as a comment on
+/// the line after it.
+pub fn text(content: &str) -> String {
+    format!("{} {} (regenerating warns if this file is edited)", MARKER, hash(content))
+}
+
+/// Whether `text` has a fingerprint that its content no longer matches.
+pub fn edited(text: &str) -> bool {
+    match strip(text) {
+        (content, Some(recorded)) => recorded != hash(content),
+        (_, None) => false,
+    }
+}
@@ -0,0 +1,363 @@
+//! BibTeX, the default format.
+
+use super::{escape_latex, orcid_url, Formatter, PersonName};
+use crate::diagnostics::EntryKind;
+use crate::entry::CitationEntry;
+use crate::{today, DependencyKind};
+use chrono::{Datelike, NaiveDate};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the `month` field is written, selected with `--month-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MonthFormat {
+    /// `month = 3`
+    Numeric,
+    /// `month = mar`, the macros BibTeX styles define
+    #[default]
+    Macro,
+    /// `month = {March}`
+    Name,
+}
+
+impl FromStr for MonthFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "numeric" => Ok(MonthFormat::Numeric),
+            "macro" => Ok(MonthFormat::Macro),
+            "name" => Ok(MonthFormat::Name),
+            other => Err(format!("unknown month format {:?} (expected numeric, macro or name)", other)),
+        }
+    }
+}
+
+impl MonthFormat {
+    /// The value of the `month` field for `date`.
+    fn value(self, date: NaiveDate) -> String {
+        match self {
+            MonthFormat::Numeric => date.month().to_string(),
+            MonthFormat::Macro => date.format("%b").to_string().to_lowercase(),
+            MonthFormat::Name => format!("{{{}}}", date.format("%B")),
+        }
+    }
+}
+
+/// What fields are indented with, selected with `--indent`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Indent {
+    #[default]
+    Tab,
+    Spaces(usize),
+}
+
+impl FromStr for Indent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tab" => Ok(Indent::Tab),
+            spaces => spaces.parse().map(Indent::Spaces)
+                .map_err(|_| format!("invalid indent {:?} (expected tab or a number of spaces)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Indent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Indent::Tab => f.write_str("\t"),
+            Indent::Spaces(n) => write!(f, "{:n$}", ""),
+        }
+    }
+}
+
+/// How the fields of generated entries are laid out, so that they match
+/// the conventions of bibtool or a bibliography linter.
+#[derive(Debug, Clone, Default)]
+pub struct Layout {
+    pub indent: Indent,
+    /// Pad field names so that the `=` signs line up
+    pub align: bool,
+    /// Fields written first, in this order; the others follow in the
+    /// usual order
+    pub order: Vec<String>,
+    /// Leave out the comma after the last field
+    pub no_trailing_comma: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bibtex {
+    /// Type of the entries without a configured one, instead of `misc`
+    pub entry_type: Option<String>,
+    /// Follow biblatex's conventions: `@software` entries, a `date` field
+    /// instead of `year` and `month`, and the `urldate` of URLs
+    pub biblatex: bool,
+    /// Keep LaTeX's special characters in free text as they are, for biber
+    /// users writing UTF-8 who escape nothing
+    pub no_escape: bool,
+    pub month_format: MonthFormat,
+    pub layout: Layout,
+}
+
+/// A field value in braces.
+fn braced(value: &str) -> String {
+    format!("{{{}}}", value)
+}
+
+impl Bibtex {
+    /// The type of `entry`: its configured one, else `entry_type`, else the
+    /// default of the mode.
+    fn entry_type<'a>(&'a self, entry: &'a CitationEntry) -> &'a str {
+        entry.entry_type.as_deref()
+            .or(self.entry_type.as_deref())
+            .unwrap_or(if self.biblatex { "software" } else { "misc" })
+    }
+
+    /// Free text, with the characters LaTeX treats specially escaped.
+    /// BibTeX counts `\{` and `\}` as braces too, so a lone brace is
+    /// written as a command instead.
+    fn text(&self, text: &str) -> String {
+        match self.no_escape {
+            true => text.to_string(),
+            false => escape_latex(text)
+                .replace("\\{", "\\textbraceleft{}")
+                .replace("\\}", "\\textbraceright{}"),
+        }
+    }
+
+    /// People, each escaped, joined with `and`.
+    fn names(&self, people: &[String]) -> String {
+        people.iter().map(|person| self.text(person)).collect::<Vec<_>>().join(" and ")
+    }
+
+    /// The `url` field, and with biblatex the date it was accessed: the one
+    /// recorded for `entry` before, else today.
+    fn url(&self, fields: &mut Vec<(&'static str, String)>, url: &str, entry: &CitationEntry) {
+        fields.push(("url", braced(url)));
+        if self.biblatex {
+            let accessed = entry.accessed.unwrap_or_else(today);
+            fields.push(("urldate", braced(&accessed.to_string())));
+        }
+    }
+
+    /// The `date` field with biblatex, else `year` and `month` in `order`.
+    fn date(&self, fields: &mut Vec<(&'static str, String)>, date: NaiveDate, year_first: bool) {
+        if self.biblatex {
+            fields.push(("date", braced(&date.to_string())));
+            return;
+        }
+        let year = ("year", date.year().to_string());
+        let month = ("month", self.month_format.value(date));
+        fields.extend(if year_first { [year, month] } else { [month, year] });
+    }
+
+    /// The entry `@entry_type{key, ...}` with `fields`, laid out as
+    /// configured, after `comments`.
+    fn render(&self, comments: &str, entry_type: &str, key: &str, mut fields: Vec<(&'static str, String)>) -> String {
+        let order = &self.layout.order;
+        if !order.is_empty() {
+            // Stable, so the other fields keep their usual order
+            fields.sort_by_key(|(name, _)| order.iter().position(|o| o.eq_ignore_ascii_case(name)).unwrap_or(order.len()));
+        }
+        let width = match self.layout.align {
+            true => fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0),
+            false => 0,
+        };
+        let indent = self.layout.indent.to_string();
+        let mut body = fields.iter()
+            .map(|(name, value)| format!("{}{:width$} = {}", indent, name, value, width = width))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        if !self.layout.no_trailing_comma {
+            body.push(',');
+        }
+        format!("{}@{}{{{},\n{}\n}}\n", comments, entry_type, key, body)
+    }
+
+    fn package(&self, entry: &CitationEntry) -> String {
+        if let Some(preferred) = &entry.preferred_bibtex {
+            return format!("{}\n", preferred.trim_end());
+        }
+        let description_part = entry.description.as_ref()
+            .map(|s| format!(": {}", self.text(s)))
+            .unwrap_or_default();
+
+        // BibTeX has no field for ORCID iDs; comments outside entries are ignored
+        let orcid: String = entry.authors.iter()
+            .filter_map(|author| {
+                let name = PersonName::parse(author).full_name();
+                let id = entry.orcid.get(&name)?;
+                Some(format!("% ORCID iD of {}: {}\n", name, orcid_url(id)))
+            })
+            .collect();
+
+        let mut fields = vec![
+            ("title", format!("{{{}{}}}", self.text(&entry.name), description_part)),
+            ("author", braced(&self.names(&entry.authors))),
+            ("version", braced(entry.version.as_deref().unwrap_or_default())),
+        ];
+        self.date(&mut fields, entry.date.unwrap_or_else(today), false);
+        if !entry.maintainers.is_empty() {
+            fields.push(("note", format!("{{Maintained by {}}}", self.names(&entry.maintainers))));
+        }
+        if let Some(doi) = &entry.doi {
+            fields.push(("doi", braced(doi)));
+        }
+        if let Some(swhid) = &entry.swhid {
+            fields.push(("swhid", braced(swhid)));
+        }
+        if let Some(url) = &entry.repository {
+            self.url(&mut fields, url, entry);
+        }
+        if let Some(license) = entry.license_text() {
+            fields.push(("license", braced(&self.text(&license))));
+        }
+        if !entry.keywords.is_empty() {
+            fields.push(("keywords", braced(&self.text(&entry.keywords.join(", ")))));
+        }
+        self.render(&orcid, self.entry_type(entry), &entry.key, fields)
+    }
+
+    fn dependency(&self, entry: &CitationEntry) -> String {
+        if let Some(preferred) = &entry.preferred_bibtex {
+            return format!("{}\n\n", preferred.trim_end());
+        }
+
+        // Renamed, dev, build and target-specific dependencies say so at the end of the note
+        let qualifiers: Vec<String> = entry.alias.as_ref().map(|alias| format!("Imported as {}", alias)).into_iter()
+            .chain(entry.dependency_kind.and_then(DependencyKind::description).map(str::to_string))
+            .chain(entry.target.as_ref().map(|target| format!("Only for target {}", target)))
+            .chain((!entry.used_by.is_empty()).then(|| format!("Used by {}", entry.used_by.join(", "))))
+            .collect();
+        let note = |fields: &mut Vec<(&'static str, String)>, text: Option<&str>| {
+            let parts: Vec<&str> = match text {
+                Some(text) if !qualifiers.is_empty() => vec![text.trim_end().trim_end_matches('.')],
+                text => text.into_iter().collect(),
+            };
+            let parts: Vec<&str> = parts.into_iter().chain(qualifiers.iter().map(String::as_str)).collect();
+            if !parts.is_empty() {
+                fields.push(("note", braced(&self.text(&parts.join(". ")))));
+            }
+        };
+
+        let mut fields = vec![("title", braced(&self.text(&entry.name)))];
+        if let Some(path) = &entry.path {
+            note(&mut fields, Some(&format!("Local dependency from path: {}", path)));
+        } else if let Some(git) = &entry.git {
+            self.url(&mut fields, git, entry);
+            note(&mut fields, Some("Git dependency"));
+            if !entry.authors.is_empty() {
+                fields.push(("author", braced(&self.names(&entry.authors))));
+            }
+        } else if entry.metadata.is_some() {
+            // Regular crates.io dependency
+            note(&mut fields, entry.description.as_deref());
+
+            if !entry.authors.is_empty() {
+                fields.push(("author", braced(&self.names(&entry.authors))));
+            }
+
+            // Prefer repository URL, fallback to homepage
+            if let Some(url) = entry.repository.as_ref().or(entry.homepage.as_ref()) {
+                self.url(&mut fields, url, entry);
+            }
+        } else {
+            note(&mut fields, None);
+        }
+
+        if let Some(version) = &entry.version {
+            fields.push(("version", braced(version)));
+        }
+        if let Some(checksum) = &entry.checksum {
+            fields.push(("checksum", format!("{{sha256:{}}}", checksum)));
+        }
+        if let Some(source) = &entry.locked_source {
+            fields.push(("source", braced(source)));
+        }
+        if let Some(doi) = &entry.doi {
+            fields.push(("doi", braced(doi)));
+        }
+        if let Some(swhid) = &entry.swhid {
+            fields.push(("swhid", braced(swhid)));
+        }
+        if let Some(license) = entry.license_text() {
+            fields.push(("license", braced(&self.text(&license))));
+        }
+        if !entry.keywords.is_empty() {
+            fields.push(("keywords", braced(&self.text(&entry.keywords.join(", ")))));
+        }
+        self.date(&mut fields, entry.date.unwrap_or_else(today), true);
+
+        // Only add crates.io link for regular dependencies
+        if entry.is_registry() {
+            fields.push(("howpublished", format!("{{https://crates.io/crates/{}}}", entry.name)));
+        }
+
+        format!("{}\n", self.render("", self.entry_type(entry), &entry.key, fields))
+    }
+}
+
+impl Formatter for Bibtex {
+    fn extension(&self) -> &str {
+        "bib"
+    }
+
+    fn renders_bibtex(&self) -> bool {
+        true
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("% {}", text))
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        Some(match entry.kind {
+            EntryKind::Package => self.package(entry),
+            EntryKind::Dependency => self.dependency(entry),
+        })
+    }
+
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let bib = super::concat(self, package, dependencies);
+        // Read the output back, so that a stray brace in a description or a
+        // preferred entry fails here instead of in the user's LaTeX build
+        let problems = crate::bibtex::validate(&bib);
+        if !problems.is_empty() {
+            return Err(format!("the generated BibTeX is malformed: {}", problems.join("; ")));
+        }
+        Ok(bib)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn month_format_from_str() {
+        assert_eq!("numeric".parse(), Ok(MonthFormat::Numeric));
+        assert_eq!("Macro".parse(), Ok(MonthFormat::Macro));
+        assert_eq!("NAME".parse(), Ok(MonthFormat::Name));
+        assert!("roman".parse::<MonthFormat>().unwrap_err().contains("\"roman\""));
+    }
+
+    #[test]
+    fn month_format_values() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        assert_eq!(MonthFormat::Numeric.value(date), "3");
+        assert_eq!(MonthFormat::Macro.value(date), "mar");
+        assert_eq!(MonthFormat::Name.value(date), "{March}");
+    }
+
+    #[test]
+    fn indent_from_str() {
+        assert_eq!("tab".parse(), Ok(Indent::Tab));
+        assert_eq!("TAB".parse(), Ok(Indent::Tab));
+        assert_eq!("4".parse(), Ok(Indent::Spaces(4)));
+        assert!("-2".parse::<Indent>().is_err());
+        assert!("wide".parse::<Indent>().unwrap_err().contains("\"wide\""));
+        assert_eq!(Indent::Spaces(2).to_string(), "  ");
+    }
+}
@@ -0,0 +1,220 @@
+//! Citation File Format (CITATION.cff), as rendered by GitHub's
+//! "Cite this repository" button. See https://citation-file-format.github.io/.
+
+use super::{orcid_url, Formatter, PersonName};
+use crate::diagnostics::EntryKind;
+use crate::entry::CitationEntry;
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+const CFF_VERSION: &str = "1.2.0";
+const MESSAGE: &str = "If you use this software, please cite it using the metadata from this file.";
+
+/// YAML double-quoted scalar, with control characters escaped since YAML
+/// does not allow them unescaped.
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A `persons` list under `key`, indented by `indent` spaces, with the ORCID
+/// iDs in `orcid` keyed by name.
+fn persons(key: &str, people: &[String], orcid: &BTreeMap<String, String>, indent: usize) -> String {
+    if people.is_empty() {
+        return String::new();
+    }
+    let pad = " ".repeat(indent);
+    let mut text = format!("{}{}:\n", pad, key);
+    for person in people {
+        let name = PersonName::parse(person);
+        let orcid = orcid.get(&name.full_name()).map(|id| orcid_url(id));
+        let fields = [
+            ("name", name.entity),
+            ("given-names", name.given),
+            ("name-particle", name.particle),
+            ("family-names", name.family),
+            ("email", name.email),
+            ("orcid", orcid.as_deref()),
+        ];
+        let mut first = true;
+        for (field, value) in fields {
+            if let Some(value) = value {
+                text.push_str(&format!("{}  {} {}: {}\n", pad, if first { "-" } else { " " }, field, quote(value)));
+                first = false;
+            }
+        }
+    }
+    text
+}
+
+/// `key: "YYYY-MM-DD"` line indented by `indent` spaces, if there is a date.
+fn date(key: &str, date: Option<NaiveDate>, indent: usize) -> String {
+    field(key, date.map(|d| d.format("%Y-%m-%d").to_string()).as_deref(), indent)
+}
+
+/// `key: value` line indented by `indent` spaces, if there is a value.
+fn field(key: &str, value: Option<&str>, indent: usize) -> String {
+    value
+        .map(|v| format!("{}{}: {}\n", " ".repeat(indent), key, quote(v)))
+        .unwrap_or_default()
+}
+
+/// An `identifiers` list holding the Software Heritage identifier, if any.
+fn swhid(swhid: Option<&str>, indent: usize) -> String {
+    let Some(swhid) = swhid else { return String::new() };
+    let pad = " ".repeat(indent);
+    format!("{pad}identifiers:\n{pad}  - type: swh\n{pad}    value: {}\n", quote(swhid), pad = pad)
+}
+
+pub struct Cff;
+
+impl Cff {
+    /// A complete CITATION.cff for the package.
+    fn package(entry: &CitationEntry) -> String {
+        let mut text = format!("cff-version: {}\nmessage: {}\ntype: software\n", CFF_VERSION, quote(MESSAGE));
+        text.push_str(&field("title", Some(&entry.name), 0));
+        text.push_str(&field("abstract", entry.description.as_deref(), 0));
+        // `authors` is required, so an empty list is kept to be filled in by hand
+        match persons("authors", &entry.authors, &entry.orcid, 0) {
+            authors if authors.is_empty() => text.push_str("authors: []\n"),
+            authors => text.push_str(&authors),
+        }
+        text.push_str(&persons("contact", &entry.maintainers, &entry.orcid, 0));
+        text.push_str(&field("version", entry.version.as_deref(), 0));
+        text.push_str(&date("date-released", entry.date, 0));
+        text.push_str(&field("doi", entry.doi.as_deref(), 0));
+        text.push_str(&swhid(entry.swhid.as_deref(), 0));
+        text.push_str(&field("license", entry.license.as_deref(), 0));
+        text.push_str(&field("repository-code", entry.repository.as_deref(), 0));
+        if !entry.keywords.is_empty() {
+            text.push_str("keywords:\n");
+            for keyword in &entry.keywords {
+                text.push_str(&format!("  - {}\n", quote(keyword)));
+            }
+        }
+        text
+    }
+
+    /// One item of a CFF `references` list.
+    fn reference(entry: &CitationEntry) -> String {
+        let mut text = format!("  - type: software\n{}", field("title", Some(&entry.name), 4));
+        text.push_str(&field("abstract", entry.description.as_deref(), 4));
+        match persons("authors", &entry.authors, &entry.orcid, 4) {
+            authors if authors.is_empty() => text.push_str("    authors: []\n"),
+            authors => text.push_str(&authors),
+        }
+        text.push_str(&field("version", entry.version.as_deref(), 4));
+        text.push_str(&date("date-released", entry.date, 4));
+        text.push_str(&field("doi", entry.doi.as_deref(), 4));
+        text.push_str(&swhid(entry.swhid.as_deref(), 4));
+        text.push_str(&field("license", entry.license.as_deref(), 4));
+        text.push_str(&field("repository-code", entry.git.as_deref().or(entry.repository.as_deref()), 4));
+        if entry.is_registry() {
+            text.push_str(&field("url", Some(&format!("https://crates.io/crates/{}", entry.name)), 4));
+        } else {
+            text.push_str(&field("url", entry.homepage.as_deref(), 4));
+        }
+        text
+    }
+}
+
+impl Formatter for Cff {
+    fn extension(&self) -> &str {
+        "cff"
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("# {}", text))
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        Some(match entry.kind {
+            EntryKind::Package => Cff::package(entry),
+            EntryKind::Dependency => Cff::reference(entry),
+        })
+    }
+
+    /// The package's CITATION.cff, with the dependencies as its `references`.
+    /// Without a package, a bare `references` list to merge into one.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let mut text = package.map(Cff::package).unwrap_or_default();
+        if !dependencies.is_empty() {
+            text.push_str("references:\n");
+            for dep in dependencies {
+                text.push_str(&Cff::reference(dep));
+            }
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackageInfo;
+    use serde_yaml::Value;
+    use std::path::PathBuf;
+
+    fn entry(manifest: &str) -> CitationEntry {
+        let package: PackageInfo = toml::from_str(manifest).unwrap();
+        CitationEntry::from_package(&package, &package.name, PathBuf::from("Cargo.toml"), NaiveDate::from_ymd_opt(2024, 5, 1).unwrap())
+    }
+
+    fn parse(package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Value {
+        let text = Cff.document(package, dependencies).unwrap();
+        serde_yaml::from_str(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text))
+    }
+
+    #[test]
+    fn document_splits_author_names() {
+        let package = entry(r#"
+name = "demo"
+version = "1.0.0"
+authors = ["Jane Doe <jane@example.org>", "Guido van Rossum", "The Rust Project Developers"]
+"#);
+        let cff = parse(Some(&package), &[]);
+        let authors = cff["authors"].as_sequence().unwrap();
+        assert_eq!(authors[0]["given-names"], "Jane");
+        assert_eq!(authors[0]["family-names"], "Doe");
+        assert_eq!(authors[0]["email"], "jane@example.org");
+        assert_eq!(authors[1]["given-names"], "Guido");
+        assert_eq!(authors[1]["name-particle"], "van");
+        assert_eq!(authors[1]["family-names"], "Rossum");
+        assert_eq!(authors[2]["name"], "The Rust Project Developers");
+        assert!(authors[2].get("given-names").is_none() && authors[2].get("family-names").is_none());
+        assert_eq!(cff["version"], "1.0.0");
+        assert_eq!(cff["date-released"], "2024-05-01");
+    }
+
+    #[test]
+    fn document_keeps_empty_author_lists() {
+        let package = entry("name = \"demo\"\nversion = \"1.0.0\"");
+        let mut dependency = entry("name = \"serde\"\nversion = \"1.0.200\"");
+        dependency.kind = EntryKind::Dependency;
+        let cff = parse(Some(&package), &[dependency]);
+        assert_eq!(cff["authors"], Value::Sequence(Vec::new()));
+        assert_eq!(cff["references"][0]["title"], "serde");
+        assert_eq!(cff["references"][0]["authors"], Value::Sequence(Vec::new()));
+    }
+
+    #[test]
+    fn quote_escapes_control_characters() {
+        let text = "Tab\t, bell\u{7}, escape\u{1b}, \"quotes\" \\ and\r\nlines\u{7f}";
+        let mut package = entry("name = \"demo\"\nversion = \"1.0.0\"");
+        package.description = Some(text.to_string());
+        assert_eq!(parse(Some(&package), &[])["abstract"], text);
+        assert!(!quote(text).chars().any(char::is_control));
+    }
+}
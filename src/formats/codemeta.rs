@@ -0,0 +1,147 @@
+//! CodeMeta (codemeta.json), the JSON-LD software description ingested by
+//! institutional software registries. See https://codemeta.github.io/.
+
+use super::{orcid_url, Formatter, PersonName};
+use crate::entry::CitationEntry;
+use serde::Serialize;
+
+const CONTEXT: &str = "https://w3id.org/codemeta/3.0";
+const FILE_NAME: &str = "codemeta.json";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SoftwareSourceCode<'a> {
+    #[serde(rename = "@context", skip_serializing_if = "Option::is_none")]
+    context: Option<&'static str>,
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_published: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    author: Vec<Agent<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    maintainer: Vec<Agent<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_repository: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identifier: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    license: Vec<String>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    keywords: &'a [String],
+    programming_language: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    software_requirements: Vec<SoftwareSourceCode<'a>>,
+}
+
+/// A person or, for names that are not split, an organisation.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Agent<'a> {
+    #[serde(rename = "@type")]
+    kind: &'static str,
+    /// ORCID iD URL
+    #[serde(rename = "@id", skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    given_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    family_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<&'a str>,
+}
+
+fn agents<'a>(people: &'a [String], entry: &CitationEntry) -> Vec<Agent<'a>> {
+    people.iter()
+        .map(|person| {
+            let name = PersonName::parse(person);
+            let id = entry.orcid.get(&name.full_name()).map(|id| orcid_url(id));
+            match name.entity {
+                Some(entity) => Agent { kind: "Organization", id, given_name: None, family_name: None, name: Some(entity), email: name.email },
+                None => Agent {
+                    kind: "Person",
+                    id,
+                    given_name: name.given,
+                    family_name: Some([name.particle, name.family].into_iter().flatten().collect::<Vec<_>>().join(" ")),
+                    name: None,
+                    email: name.email,
+                },
+            }
+        })
+        .collect()
+}
+
+/// SPDX license URLs of the licenses in an SPDX expression.
+fn licenses(expression: &str) -> Vec<String> {
+    expression.split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '/'))
+        .filter(|part| !part.is_empty() && !matches!(*part, "OR" | "AND" | "WITH"))
+        .map(|id| format!("https://spdx.org/licenses/{}", id))
+        .collect()
+}
+
+impl<'a> SoftwareSourceCode<'a> {
+    fn new(entry: &'a CitationEntry) -> Self {
+        SoftwareSourceCode {
+            context: None,
+            kind: "SoftwareSourceCode",
+            name: &entry.name,
+            description: entry.description.as_deref(),
+            version: entry.version.as_deref(),
+            date_published: entry.date.map(|d| d.format("%Y-%m-%d").to_string()),
+            author: agents(&entry.authors, entry),
+            maintainer: agents(&entry.maintainers, entry),
+            code_repository: entry.git.as_deref().or(entry.repository.as_deref()),
+            url: entry.link(),
+            identifier: entry.doi.as_ref().map(|doi| format!("https://doi.org/{}", doi)),
+            license: entry.license.as_deref().map(licenses).unwrap_or_default(),
+            keywords: &entry.keywords,
+            programming_language: "Rust",
+            software_requirements: Vec::new(),
+        }
+    }
+}
+
+pub struct CodeMeta;
+
+impl Formatter for CodeMeta {
+    fn extension(&self) -> &str {
+        "codemeta.json"
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        Some(FILE_NAME)
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        serde_json::to_string_pretty(&SoftwareSourceCode::new(entry)).ok()
+    }
+
+    /// The package's codemeta.json, its dependencies listed as software
+    /// requirements. Without a package, an array of descriptions.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let mut json = match package {
+            Some(package) => {
+                let mut document = SoftwareSourceCode::new(package);
+                document.context = Some(CONTEXT);
+                document.software_requirements = dependencies.iter().map(SoftwareSourceCode::new).collect();
+                serde_json::to_string_pretty(&document)
+            }
+            None => serde_json::to_string_pretty(&dependencies.iter().map(SoftwareSourceCode::new).collect::<Vec<_>>()),
+        }.map_err(|e| e.to_string())?;
+        json.push('\n');
+        Ok(json)
+    }
+}
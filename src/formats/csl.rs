@@ -0,0 +1,151 @@
+//! CSL-JSON, the item format read by Zotero, Mendeley and pandoc's citeproc,
+//! and the same items as the `references` of a pandoc YAML metadata block.
+//! See https://citeproc-js.readthedocs.io/en/latest/csl-json/markup.html.
+
+use super::{Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Item<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    title: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    author: Vec<Name<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    issued: Option<Date>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(rename = "abstract", skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    #[serde(rename = "URL", skip_serializing_if = "Option::is_none")]
+    url: Option<&'a str>,
+    #[serde(rename = "DOI", skip_serializing_if = "Option::is_none")]
+    doi: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publisher: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyword: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Name<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    family: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    given: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    non_dropping_particle: Option<&'a str>,
+    /// Organisations, which are not split into name parts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    literal: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Date {
+    date_parts: [[i32; 3]; 1],
+}
+
+impl From<NaiveDate> for Date {
+    fn from(date: NaiveDate) -> Self {
+        Date { date_parts: [[date.year(), date.month() as i32, date.day() as i32]] }
+    }
+}
+
+fn names(people: &[String]) -> Vec<Name<'_>> {
+    people.iter()
+        .map(|person| {
+            let name = PersonName::parse(person);
+            Name {
+                family: name.family,
+                given: name.given,
+                non_dropping_particle: name.particle,
+                literal: name.entity,
+            }
+        })
+        .collect()
+}
+
+impl<'a> From<&'a CitationEntry> for Item<'a> {
+    fn from(entry: &'a CitationEntry) -> Self {
+        Item {
+            id: &entry.key,
+            kind: "software",
+            title: &entry.name,
+            author: names(&entry.authors),
+            issued: entry.date.map(Date::from),
+            version: entry.version.as_deref(),
+            summary: entry.description.as_deref(),
+            url: entry.url(),
+            doi: entry.doi.as_deref(),
+            publisher: entry.is_registry().then_some("crates.io"),
+            keyword: Some(entry.keywords.join(", ")).filter(|k| !k.is_empty()),
+        }
+    }
+}
+
+pub struct CslJson;
+
+impl Formatter for CslJson {
+    fn extension(&self) -> &str {
+        "csl.json"
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        serde_json::to_string_pretty(&Item::from(entry)).ok()
+    }
+
+    /// A CSL-JSON document: an array of items.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let items: Vec<Item> = super::entries(package, dependencies).map(Item::from).collect();
+        let mut json = serde_json::to_string_pretty(&items).map_err(|e| e.to_string())?;
+        json.push('\n');
+        Ok(json)
+    }
+}
+
+/// Pandoc metadata file citing the package and its dependencies
+const PANDOC_FILE_NAME: &str = "citations.yaml";
+
+#[derive(Debug, Serialize)]
+struct Metadata<'a> {
+    references: Vec<Item<'a>>,
+}
+
+pub struct PandocYaml;
+
+impl Formatter for PandocYaml {
+    fn extension(&self) -> &str {
+        "yaml"
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        Some(PANDOC_FILE_NAME)
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("# {}", text))
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        serde_yaml::to_string(&Item::from(entry)).ok()
+    }
+
+    /// A YAML metadata block with the items under `references`, for pandoc's
+    /// `--metadata-file` or to paste at the top of a Markdown document.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let references: Vec<Item> = super::entries(package, dependencies).map(Item::from).collect();
+        let yaml = serde_yaml::to_string(&Metadata { references }).map_err(|e| e.to_string())?;
+        Ok(format!("---\n{}...\n", yaml))
+    }
+}
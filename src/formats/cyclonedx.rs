@@ -0,0 +1,187 @@
+//! CycloneDX SBOM (bom.cdx.json) whose components carry references to how
+//! each crate is cited: its DOI, citation file and repository. See
+//! https://cyclonedx.org/docs/1.5/json/.
+
+use super::{citation_file_url, purl, Formatter};
+use crate::entry::CitationEntry;
+use crate::today;
+use serde::Serialize;
+
+const SPEC_VERSION: &str = "1.5";
+const FILE_NAME: &str = "bom.cdx.json";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom<'a> {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: Metadata<'a>,
+    components: Vec<Component<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<Dependency>,
+}
+
+#[derive(Debug, Serialize)]
+struct Metadata<'a> {
+    timestamp: String,
+    tools: Vec<Tool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<Component<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Component<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<License>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<Reference>,
+    /// The citation key, to match the component with the bibliography
+    properties: Vec<Property<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum License {
+    Expression { expression: String },
+    Named { license: NamedLicense },
+}
+
+#[derive(Debug, Serialize)]
+struct NamedLicense {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Reference {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct Property<'a> {
+    name: &'static str,
+    value: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Dependency {
+    #[serde(rename = "ref")]
+    reference: String,
+    depends_on: Vec<String>,
+}
+
+/// Identifier of `entry` within the BOM.
+fn bom_ref(entry: &CitationEntry) -> String {
+    purl(entry).unwrap_or_else(|| format!("{}@{}", entry.name, entry.version.as_deref().unwrap_or("*")))
+}
+
+impl<'a> Component<'a> {
+    fn new(entry: &'a CitationEntry, kind: &'static str) -> Self {
+        let mut references = Vec::new();
+        // CycloneDX has no reference type for citations; "other" is commented
+        if let Some(doi) = &entry.doi {
+            references.push(Reference { kind: "other", url: format!("https://doi.org/{}", doi), comment: Some("citation: DOI") });
+        }
+        if let Some(url) = citation_file_url(entry) {
+            references.push(Reference { kind: "other", url, comment: Some("citation: citation file") });
+        }
+        if let Some(swhid) = &entry.swhid {
+            references.push(Reference { kind: "other", url: format!("https://archive.softwareheritage.org/{}", swhid), comment: Some("citation: Software Heritage archive") });
+        }
+        if let Some(url) = entry.git.as_ref().or(entry.repository.as_ref()) {
+            references.push(Reference { kind: "vcs", url: url.clone(), comment: None });
+        }
+        if let Some(url) = &entry.homepage {
+            references.push(Reference { kind: "website", url: url.clone(), comment: None });
+        }
+        if entry.is_registry() {
+            references.push(Reference { kind: "distribution", url: format!("https://crates.io/crates/{}", entry.name), comment: None });
+        }
+        let licenses = match (&entry.license, entry.license_text()) {
+            (Some(expression), _) => vec![License::Expression { expression: expression.clone() }],
+            (None, Some(name)) => vec![License::Named { license: NamedLicense { name } }],
+            (None, None) => Vec::new(),
+        };
+        Component {
+            kind,
+            bom_ref: bom_ref(entry),
+            name: &entry.name,
+            version: entry.version.as_deref(),
+            description: entry.description.as_deref(),
+            author: Some(entry.authors.join(", ")).filter(|a| !a.is_empty()),
+            licenses,
+            purl: purl(entry),
+            external_references: references,
+            properties: vec![Property { name: "cargo-cite:citation-key", value: &entry.key }],
+        }
+    }
+}
+
+pub struct CycloneDx;
+
+impl Formatter for CycloneDx {
+    fn extension(&self) -> &str {
+        "cdx.json"
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        Some(FILE_NAME)
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        serde_json::to_string_pretty(&Component::new(entry, "library")).ok()
+    }
+
+    /// A BOM of the package, when given, and its dependencies. It is dated
+    /// with the package so that regenerating it leaves it unchanged.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let date = package.and_then(|p| p.date).unwrap_or_else(today);
+        let bom = Bom {
+            bom_format: "CycloneDX",
+            spec_version: SPEC_VERSION,
+            version: 1,
+            metadata: Metadata {
+                timestamp: format!("{}T00:00:00Z", date.format("%Y-%m-%d")),
+                tools: vec![Tool { name: env!("CARGO_PKG_NAME"), version: env!("CARGO_PKG_VERSION") }],
+                component: package.map(|p| Component::new(p, "application")),
+            },
+            components: dependencies.iter().map(|d| Component::new(d, "library")).collect(),
+            dependencies: package.map(|p| Dependency {
+                reference: bom_ref(p),
+                depends_on: dependencies.iter().map(bom_ref).collect(),
+            }).into_iter().collect(),
+        };
+        let mut json = serde_json::to_string_pretty(&bom).map_err(|e| e.to_string())?;
+        json.push('\n');
+        Ok(json)
+    }
+}
@@ -0,0 +1,108 @@
+//! EndNote XML, the library format EndNote imports and some journals require.
+//! Software is cited with the "Computer Program" reference type.
+
+use super::{escape_xml as escape, Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::NaiveDate;
+
+/// EndNote's reference type number for "Computer Program".
+const COMPUTER_PROGRAM: u32 = 9;
+
+/// The fields of one `<record>`, in the order of the EndNote schema.
+struct Record<'a> {
+    key: &'a str,
+    authors: &'a [String],
+    title: &'a str,
+    date: Option<NaiveDate>,
+    version: Option<&'a str>,
+    publisher: Option<&'a str>,
+    summary: Option<&'a str>,
+    doi: Option<&'a str>,
+    url: Option<&'a str>,
+    keywords: &'a [String],
+}
+
+impl Record<'_> {
+    fn render(&self) -> String {
+        let element = |name: &str, value: Option<&str>| {
+            value.map(|v| format!("      <{0}>{1}</{0}>\n", name, escape(v))).unwrap_or_default()
+        };
+
+        let mut xml = String::from("    <record>\n");
+        xml.push_str("      <source-app name=\"cargo-cite\">cargo-cite</source-app>\n");
+        xml.push_str(&format!("      <ref-type name=\"Computer Program\">{}</ref-type>\n", COMPUTER_PROGRAM));
+        if !self.authors.is_empty() {
+            xml.push_str("      <contributors>\n        <authors>\n");
+            for author in self.authors {
+                xml.push_str(&format!("          <author>{}</author>\n", escape(&PersonName::parse(author).inverted())));
+            }
+            xml.push_str("        </authors>\n      </contributors>\n");
+        }
+        xml.push_str(&format!("      <titles>\n        <title>{}</title>\n      </titles>\n", escape(self.title)));
+        if !self.keywords.is_empty() {
+            xml.push_str("      <keywords>\n");
+            for keyword in self.keywords {
+                xml.push_str(&format!("        <keyword>{}</keyword>\n", escape(keyword)));
+            }
+            xml.push_str("      </keywords>\n");
+        }
+        if let Some(date) = self.date {
+            xml.push_str(&format!(
+                "      <dates>\n        <year>{}</year>\n        <pub-dates>\n          <date>{}</date>\n        </pub-dates>\n      </dates>\n",
+                date.format("%Y"),
+                date.format("%Y-%m-%d"),
+            ));
+        }
+        xml.push_str(&element("publisher", self.publisher));
+        xml.push_str(&element("edition", self.version));
+        xml.push_str(&element("electronic-resource-num", self.doi));
+        xml.push_str(&element("abstract", self.summary));
+        xml.push_str(&element("label", Some(self.key)));
+        if let Some(url) = self.url {
+            xml.push_str(&format!("      <urls>\n        <related-urls>\n          <url>{}</url>\n        </related-urls>\n      </urls>\n", escape(url)));
+        }
+        xml.push_str("    </record>\n");
+        xml
+    }
+}
+
+impl<'a> From<&'a CitationEntry> for Record<'a> {
+    fn from(entry: &'a CitationEntry) -> Self {
+        Record {
+            key: &entry.key,
+            authors: &entry.authors,
+            title: &entry.name,
+            date: entry.date,
+            version: entry.version.as_deref(),
+            publisher: entry.is_registry().then_some("crates.io"),
+            summary: entry.description.as_deref(),
+            doi: entry.doi.as_deref(),
+            url: entry.url(),
+            keywords: &entry.keywords,
+        }
+    }
+}
+
+pub struct EndnoteXml;
+
+impl Formatter for EndnoteXml {
+    fn extension(&self) -> &str {
+        "xml"
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("<!-- {} -->", text))
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        Some(Record::from(entry).render())
+    }
+
+    /// A complete EndNote XML library.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        Ok(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xml>\n  <records>\n{}  </records>\n</xml>\n",
+            super::concat(self, package, dependencies)
+        ))
+    }
+}
@@ -0,0 +1,146 @@
+//! Standalone HTML bibliography page, e.g. for GitHub Pages.
+
+use super::bibtex::Bibtex;
+use super::{escape_xml as escape, Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::{Datelike, NaiveDate};
+
+/// HTML page citing the package and its dependencies
+const FILE_NAME: &str = "citations.html";
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 50em; margin: 2em auto; padding: 0 1em; line-height: 1.5; }
+li { margin-bottom: 1em; }
+pre { background: #f6f8fa; padding: 0.5em; overflow-x: auto; }";
+
+/// The fields of one list entry.
+struct Entry<'a> {
+    key: &'a str,
+    name: &'a str,
+    link: Option<String>,
+    authors: &'a [String],
+    version: Option<&'a str>,
+    date: Option<NaiveDate>,
+    description: Option<&'a str>,
+    /// Repository, shown in addition to a crates.io link
+    repository: Option<&'a str>,
+    doi: Option<&'a str>,
+    bibtex: Option<String>,
+}
+
+impl Entry<'_> {
+    fn render(&self) -> String {
+        let mut html = format!("  <li id=\"{}\">\n    ", escape(self.key));
+        match &self.link {
+            Some(url) => html.push_str(&format!("<a href=\"{}\"><strong>{}</strong></a>", escape(url), escape(self.name))),
+            None => html.push_str(&format!("<strong>{}</strong>", escape(self.name))),
+        }
+        let authors: Vec<String> = self.authors.iter().map(|a| PersonName::parse(a).full_name()).collect();
+        if !authors.is_empty() {
+            html.push_str(&format!(" by {}", escape(&authors.join(", "))));
+        }
+        let details: Vec<String> = [
+            self.version.map(|v| format!("version {}", escape(v))),
+            self.date.map(|d| d.year().to_string()),
+        ].into_iter().flatten().collect();
+        if !details.is_empty() {
+            html.push_str(&format!(" ({})", details.join(", ")));
+        }
+        if let Some(description) = self.description {
+            html.push_str(&format!(". {}.", escape(description.trim_end().trim_end_matches('.'))));
+        }
+        if let Some(url) = self.repository.filter(|url| self.link.as_deref() != Some(url)) {
+            html.push_str(&format!(" <a href=\"{0}\">{0}</a>", escape(url)));
+        }
+        if let Some(doi) = self.doi {
+            html.push_str(&format!(" doi:<a href=\"https://doi.org/{0}\">{0}</a>", escape(doi)));
+        }
+        html.push_str(&format!(" <a href=\"#{}\" title=\"Link to this entry\">#</a>\n", escape(self.key)));
+        if let Some(bibtex) = &self.bibtex {
+            html.push_str(&format!("    <pre><code class=\"language-bibtex\">{}</code></pre>\n", escape(bibtex.trim_end())));
+        }
+        html.push_str("  </li>\n");
+        html
+    }
+}
+
+impl<'a> Entry<'a> {
+    fn new(entry: &'a CitationEntry, bibtex: Option<String>) -> Self {
+        Entry {
+            key: &entry.key,
+            name: &entry.name,
+            link: entry.link(),
+            authors: &entry.authors,
+            version: entry.version.as_deref(),
+            date: entry.date,
+            description: entry.description.as_deref(),
+            repository: entry.repository.as_deref(),
+            doi: entry.doi.as_deref(),
+            bibtex,
+        }
+    }
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape(title),
+    )
+}
+
+pub struct Html {
+    /// Include each BibTeX entry, rendered so, in a `<pre>` block
+    pub embed_bibtex: Option<Bibtex>,
+}
+
+impl Html {
+    fn list(&self, entries: &[CitationEntry]) -> String {
+        let items: String = entries.iter().enumerate().filter_map(|(i, e)| self.entry(e, i + 1)).collect();
+        format!("<ul>\n{}</ul>\n", items)
+    }
+}
+
+impl Formatter for Html {
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        Some(FILE_NAME)
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn renders_bibtex(&self) -> bool {
+        self.embed_bibtex.is_some()
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("<!-- {} -->", text))
+    }
+
+    fn entry(&self, entry: &CitationEntry, number: usize) -> Option<String> {
+        let bibtex = self.embed_bibtex.as_ref().and_then(|bibtex| bibtex.entry(entry, number));
+        Some(Entry::new(entry, bibtex).render())
+    }
+
+    /// citations.html for the package followed by its dependencies, or a
+    /// bibliography page of dependencies only.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let Some(package) = package else {
+            return Ok(page(super::list_title(dependencies), &self.list(dependencies)));
+        };
+
+        let mut body = format!(
+            "<p>If you use {} in your work, please cite it as:</p>\n{}",
+            escape(&package.name),
+            self.list(std::slice::from_ref(package))
+        );
+        if !dependencies.is_empty() {
+            body.push_str(&format!("<h2 id=\"dependencies\">Dependencies</h2>\n<p>{} builds on the following crates:</p>\n", escape(&package.name)));
+            body.push_str(&self.list(dependencies));
+        }
+        Ok(page(&format!("Citing {}", package.name), &body))
+    }
+}
@@ -0,0 +1,35 @@
+//! The resolved citation model as JSON, for dashboards and reporting scripts.
+
+use super::Formatter;
+use crate::entry::CitationEntry;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+struct Document<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<&'a CitationEntry>,
+    dependencies: &'a [CitationEntry],
+}
+
+pub struct Json;
+
+impl Formatter for Json {
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        serde_json::to_string_pretty(entry).ok()
+    }
+
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let mut json = serde_json::to_string_pretty(&Document { package, dependencies })
+            .map_err(|e| e.to_string())?;
+        json.push('\n');
+        Ok(json)
+    }
+}
@@ -0,0 +1,82 @@
+//! LaTeX `thebibliography` environment, for document classes that require
+//! inline bibliographies instead of BibTeX.
+
+use super::{escape_latex as escape, Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::Datelike;
+
+/// A URL for `\url`, which takes most characters verbatim but not `%` and `#`
+/// in the argument of another command.
+fn escape_url(url: &str) -> String {
+    url.replace('%', "\\%").replace('#', "\\#")
+}
+
+/// Authors as written, without email addresses, joined with "and".
+fn authors(people: &[String]) -> Option<String> {
+    let names: Vec<String> = people.iter().map(|p| escape(&PersonName::parse(p).full_name())).collect();
+    match names.as_slice() {
+        [] => None,
+        [one] => Some(one.clone()),
+        [rest @ .., last] => Some(format!("{} and {}", rest.join(", "), last)),
+    }
+}
+
+pub struct Latex;
+
+impl Formatter for Latex {
+    fn extension(&self) -> &str {
+        "tex"
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("% {}", text))
+    }
+
+    /// A `\bibitem`: authors, title and description, version and year, link
+    /// and DOI.
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        let mut text = format!("\\bibitem{{{}}}\n", entry.key);
+        if let Some(authors) = authors(&entry.authors) {
+            text.push_str(&format!("{}.\n", authors.trim_end_matches('.')));
+        }
+        let title = match &entry.description {
+            Some(description) => format!("\\emph{{{}}}: {}", escape(&entry.name), escape(&description.split_whitespace().collect::<Vec<_>>().join(" "))),
+            None => format!("\\emph{{{}}}", escape(&entry.name)),
+        };
+        text.push_str(&format!("{}.\n", title.trim_end_matches('.')));
+        let details: Vec<String> = [
+            entry.version.as_ref().map(|v| format!("Version {}", escape(v))),
+            entry.date.map(|d| d.year().to_string()),
+        ].into_iter().flatten().collect();
+        if !details.is_empty() {
+            text.push_str(&format!("{}.\n", details.join(", ")));
+        }
+        if let Some(url) = entry.link() {
+            text.push_str(&format!("\\url{{{}}}.\n", escape_url(&url)));
+        }
+        if let Some(doi) = &entry.doi {
+            text.push_str(&format!("doi:\\href{{https://doi.org/{}}}{{{}}}.\n", escape_url(doi), escape(doi)));
+        }
+        Some(text)
+    }
+
+    /// A `thebibliography` environment with the package followed by its
+    /// dependencies, to paste into the document or `\input`.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let items: Vec<String> = super::entries(package, dependencies)
+            .enumerate()
+            .filter_map(|(i, entry)| self.entry(entry, i + 1))
+            .collect();
+        // The widest label, as many digits as the number of items has
+        let widest = "9".repeat(items.len().max(1).to_string().len());
+        Ok(format!(
+            "% \\url and \\href need the hyperref package.\n\\begin{{thebibliography}}{{{}}}\n\n{}\n\\end{{thebibliography}}\n",
+            widest,
+            items.join("\n"),
+        ))
+    }
+}
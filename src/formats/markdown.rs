@@ -0,0 +1,83 @@
+//! Markdown bibliography for project documentation and websites.
+
+use super::{Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::Datelike;
+
+/// Markdown bibliography of the package and its dependencies
+const FILE_NAME: &str = "CITATIONS.md";
+
+/// Authors as written, without email addresses, joined with "and".
+fn authors(people: &[String]) -> Option<String> {
+    let names: Vec<String> = people.iter().map(|p| PersonName::parse(p).full_name()).collect();
+    match names.as_slice() {
+        [] => None,
+        [one] => Some(one.clone()),
+        [rest @ .., last] => Some(format!("{} and {}", rest.join(", "), last)),
+    }
+}
+
+pub struct Markdown;
+
+impl Formatter for Markdown {
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        Some(FILE_NAME)
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn comment(&self, text: &str) -> Option<String> {
+        Some(format!("<!-- {} -->", text))
+    }
+
+    /// One list item: linked name, authors, version, year and description.
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        let mut text = match entry.link() {
+            Some(url) => format!("- [**{}**]({})", entry.name, url),
+            None => format!("- **{}**", entry.name),
+        };
+        if let Some(authors) = authors(&entry.authors) {
+            text.push_str(&format!(" by {}", authors));
+        }
+        let details: Vec<String> = [
+            entry.version.as_ref().map(|v| format!("version {}", v)),
+            entry.date.map(|d| d.year().to_string()),
+        ].into_iter().flatten().collect();
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join(", ")));
+        }
+        if let Some(description) = &entry.description {
+            text.push_str(&format!(": {}", description.split_whitespace().collect::<Vec<_>>().join(" ")));
+        }
+        text.push('\n');
+        Some(text)
+    }
+
+    /// CITATIONS.md for the package followed by its dependencies, or a
+    /// bibliography of dependencies only.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let list = |entries: &[CitationEntry]| -> String {
+            entries.iter().enumerate().filter_map(|(i, e)| self.entry(e, i + 1)).collect()
+        };
+        let Some(package) = package else {
+            return Ok(format!("# {}\n\n{}", super::list_title(dependencies), list(dependencies)));
+        };
+
+        let mut text = format!("# Citations\n\nIf you use {} in your work, please cite it as:\n\n", package.name);
+        text.push_str(&self.entry(package, 1).unwrap_or_default());
+        if let Some(doi) = &package.doi {
+            text.push_str(&format!("\nDOI: [{0}](https://doi.org/{0})\n", doi));
+        }
+        if !dependencies.is_empty() {
+            text.push_str(&format!("\n## Dependencies\n\n{} builds on the following crates:\n\n", package.name));
+            text.push_str(&list(dependencies));
+        }
+        Ok(text)
+    }
+}
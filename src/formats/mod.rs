@@ -0,0 +1,275 @@
+//! Citation formats selected with `--format`, behind the `Formatter` trait.
+
+pub mod bibtex;
+pub mod cff;
+pub mod codemeta;
+pub mod csl;
+pub mod cyclonedx;
+pub mod endnote;
+pub mod html;
+pub mod json;
+pub mod latex;
+pub mod markdown;
+pub mod plugin;
+pub mod ris;
+pub mod spdx;
+pub mod style;
+pub mod template;
+
+use crate::diagnostics::EntryKind;
+use crate::entry::CitationEntry;
+use crate::registry;
+use style::Style;
+
+/// Renders citation entries in one format.
+pub trait Formatter {
+    /// Extension of the default output files, e.g. `bib` for CITATION.bib.
+    fn extension(&self) -> &str;
+
+    /// Name of the package citation file, when it is not `CITATION.<extension>`.
+    fn file_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the package citation also lists the package's dependencies.
+    fn includes_dependencies(&self) -> bool {
+        false
+    }
+
+    /// Whether entries are rendered as BibTeX, so that entries fetched from
+    /// doi.org can be used verbatim.
+    fn renders_bibtex(&self) -> bool {
+        false
+    }
+
+    /// `text` as a comment line of the format, without the line break.
+    /// `None` for formats without comments.
+    fn comment(&self, _text: &str) -> Option<String> {
+        None
+    }
+
+    /// A single entry, `number` being its 1-based position in the document.
+    /// `None` for formatters that only render whole documents.
+    fn entry(&self, entry: &CitationEntry, number: usize) -> Option<String>;
+
+    /// The complete output: the package citation, if any, followed by the
+    /// dependencies.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String>;
+}
+
+/// The formatter for `--format name`, `--style` or the default BibTeX, the
+/// BibTeX rendered as configured in `bibtex`. Names without a built-in
+/// formatter are delegated to plugins.
+pub fn select(name: Option<&str>, style: Option<Style>, bibtex: bibtex::Bibtex, embed_bibtex: bool) -> Result<Box<dyn Formatter>, String> {
+    if let Some(style) = style {
+        if name.is_some() {
+            return Err(String::from("--style and --format cannot be used together."));
+        }
+        return Ok(Box::new(style::Styled(style)));
+    }
+    Ok(match name.unwrap_or("bibtex") {
+        "" => return Err(String::from("format name cannot be empty")),
+        "bibtex" => Box::new(bibtex),
+        "cff" => Box::new(cff::Cff),
+        "codemeta" => Box::new(codemeta::CodeMeta),
+        "csl-json" => Box::new(csl::CslJson),
+        "ris" => Box::new(ris::Ris),
+        "endnote-xml" => Box::new(endnote::EndnoteXml),
+        "markdown" => Box::new(markdown::Markdown),
+        "html" => Box::new(html::Html { embed_bibtex: embed_bibtex.then_some(bibtex) }),
+        "json" => Box::new(json::Json),
+        "latex" => Box::new(latex::Latex),
+        "pandoc" => Box::new(csl::PandocYaml),
+        "cyclonedx" => Box::new(cyclonedx::CycloneDx),
+        "spdx" => Box::new(spdx::Spdx),
+        other => Box::new(plugin::Plugin(other.to_string())),
+    })
+}
+
+/// Heading of a document without a package: the workspace's packages or the
+/// dependencies.
+fn list_title(entries: &[CitationEntry]) -> &'static str {
+    if entries.iter().all(|e| e.kind == EntryKind::Package) {
+        "Packages"
+    } else {
+        "Dependencies"
+    }
+}
+
+/// Every entry in order, for formats whose documents are plain lists.
+fn entries<'a>(package: Option<&'a CitationEntry>, dependencies: &'a [CitationEntry]) -> impl Iterator<Item = &'a CitationEntry> {
+    package.into_iter().chain(dependencies)
+}
+
+/// The concatenated entries, for formats without a document structure.
+fn concat(formatter: &dyn Formatter, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> String {
+    entries(package, dependencies)
+        .enumerate()
+        .filter_map(|(i, entry)| formatter.entry(entry, i + 1))
+        .collect()
+}
+
+/// Escape text for XML and HTML content and attribute values.
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escape the characters LaTeX treats specially in running text.
+pub fn escape_latex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A person's name split into the parts citation formats ask for.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PersonName<'a> {
+    pub given: Option<&'a str>,
+    /// Lowercase particle such as "van" or "de"
+    pub particle: Option<&'a str>,
+    pub family: Option<&'a str>,
+    /// Organisations and groups, which have no given or family name
+    pub entity: Option<&'a str>,
+    pub email: Option<&'a str>,
+}
+
+/// Package URL of a crates.io crate, identifying it in SBOMs.
+fn purl(entry: &CitationEntry) -> Option<String> {
+    if !entry.is_registry() && entry.kind != EntryKind::Package {
+        return None;
+    }
+    Some(match &entry.version {
+        Some(version) => format!("pkg:cargo/{}@{}", entry.name, version),
+        None => format!("pkg:cargo/{}", entry.name),
+    })
+}
+
+/// Where the CITATION.cff or CITATION.bib a dependency ships can be read:
+/// its packaged source on docs.rs.
+fn citation_file_url(entry: &CitationEntry) -> Option<String> {
+    let version = entry.version.as_deref().filter(|_| entry.is_registry())?;
+    let file = registry::citation_files(entry.local_source.as_deref()?).into_iter().next()?;
+    Some(format!("https://docs.rs/crate/{}/{}/source/{}", entry.name, version, file.file_name()?.to_string_lossy()))
+}
+
+/// An ORCID iD as the URL it resolves to.
+fn orcid_url(id: &str) -> String {
+    if id.starts_with("http") {
+        id.to_string()
+    } else {
+        format!("https://orcid.org/{}", id)
+    }
+}
+
+/// Words that mark an author as a group rather than a person.
+const ENTITY_WORDS: &[&str] = &["Contributors", "Developers", "Team", "Project", "Foundation", "Authors"];
+
+impl<'a> PersonName<'a> {
+    /// Split a Cargo-style `Given Family <email>` or `Family, Given` entry.
+    pub fn parse(person: &'a str) -> Self {
+        let (name, email) = match person.split_once('<') {
+            Some((name, rest)) => (name.trim(), rest.split('>').next().map(str::trim).filter(|e| !e.is_empty())),
+            None => (person.trim(), None),
+        };
+        if name.split_whitespace().count() < 2 || name.split_whitespace().any(|w| ENTITY_WORDS.contains(&w)) {
+            return PersonName { entity: Some(name), email, ..Default::default() };
+        }
+
+        let (given, rest) = match name.split_once(',') {
+            Some((family, given)) => (given.trim(), family.trim()),
+            None => {
+                // The family name starts at the first lowercase particle, or is the last word
+                let words: Vec<&str> = name.split_whitespace().collect();
+                let family_start = words.iter()
+                    .position(|w| w.starts_with(|c: char| c.is_lowercase()))
+                    .filter(|&i| i > 0 && i < words.len() - 1)
+                    .unwrap_or(words.len() - 1);
+                let split = name.find(words[family_start]).unwrap_or(0);
+                (name[..split].trim(), &name[split..])
+            }
+        };
+        let (particle, family) = match rest.rfind(|c: char| c.is_whitespace()) {
+            Some(i) if rest.starts_with(|c: char| c.is_lowercase()) => (Some(rest[..i].trim()), rest[i..].trim()),
+            _ => (None, rest),
+        };
+        PersonName {
+            given: Some(given).filter(|g| !g.is_empty()),
+            particle,
+            family: Some(family),
+            entity: None,
+            email,
+        }
+    }
+
+    /// The name as written, without the email address.
+    pub fn full_name(&self) -> String {
+        match self.entity {
+            Some(entity) => entity.to_string(),
+            None => [self.given, self.particle, self.family].into_iter().flatten().collect::<Vec<_>>().join(" "),
+        }
+    }
+
+    /// `particle family, given`, the order reference lists sort by.
+    pub fn inverted(&self) -> String {
+        if let Some(entity) = self.entity {
+            return entity.to_string();
+        }
+        let family = [self.particle, self.family].into_iter().flatten().collect::<Vec<_>>().join(" ");
+        match self.given {
+            Some(given) => format!("{}, {}", family, given),
+            None => family,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_latex_escapes_special_characters() {
+        assert_eq!(escape_latex("50% of C&C_2 {x} #1 $5"), "50\\% of C\\&C\\_2 \\{x\\} \\#1 \\$5");
+        assert_eq!(escape_latex("a\\b ~c^d"), "a\\textbackslash{}b \\textasciitilde{}c\\textasciicircum{}d");
+        assert_eq!(escape_latex("Zoë"), "Zoë");
+    }
+
+    #[test]
+    fn person_name_parses_given_and_family() {
+        let name = PersonName::parse("Jane Doe <jane@example.org>");
+        assert_eq!(name, PersonName { given: Some("Jane"), family: Some("Doe"), email: Some("jane@example.org"), ..Default::default() });
+        assert_eq!(name.inverted(), "Doe, Jane");
+        assert_eq!(PersonName::parse("Mary Ann Smith").given, Some("Mary Ann"));
+    }
+
+    #[test]
+    fn person_name_parses_particles() {
+        let name = PersonName::parse("Guido van Rossum");
+        assert_eq!(name, PersonName { given: Some("Guido"), particle: Some("van"), family: Some("Rossum"), ..Default::default() });
+        assert_eq!(name.inverted(), "van Rossum, Guido");
+        assert_eq!(PersonName::parse("van Rossum, Guido"), name);
+        assert_eq!(name.full_name(), "Guido van Rossum");
+    }
+
+    #[test]
+    fn person_name_keeps_groups_whole() {
+        let name = PersonName::parse("The Rust Project Developers");
+        assert_eq!(name.entity, Some("The Rust Project Developers"));
+        assert_eq!(name.inverted(), "The Rust Project Developers");
+        assert_eq!(PersonName::parse("dtolnay").entity, Some("dtolnay"));
+    }
+}
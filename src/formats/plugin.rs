@@ -0,0 +1,90 @@
+//! External formatter plugins.
+//!
+//! Selecting a format without a built-in formatter, e.g. `--format foo`, runs
+//! the `cargo-cite-format-foo` executable found on PATH. The collected metadata
+//! is written to its stdin as a single JSON document, and whatever it prints on
+//! stdout is used as the generated citation file.
+
+use super::Formatter;
+use crate::entry::CitationEntry;
+use serde::Serialize;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const PLUGIN_PREFIX: &str = "cargo-cite-format-";
+
+/// The JSON document a plugin reads from stdin.
+#[derive(Debug, Serialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+enum PluginInput<'a> {
+    Package {
+        key: &'a str,
+        manifest_path: &'a Path,
+        package: &'a CitationEntry,
+    },
+    Dependencies {
+        dependencies: &'a [CitationEntry],
+    },
+}
+
+/// A formatter backed by the `cargo-cite-format-<name>` executable.
+pub struct Plugin(pub String);
+
+impl Formatter for Plugin {
+    fn extension(&self) -> &str {
+        &self.0
+    }
+
+    fn entry(&self, _entry: &CitationEntry, _number: usize) -> Option<String> {
+        None
+    }
+
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let input = match package {
+            Some(package) => PluginInput::Package {
+                key: &package.key,
+                manifest_path: &package.manifest_path,
+                package,
+            },
+            None => PluginInput::Dependencies { dependencies },
+        };
+        run(&self.0, &input).map_err(|e| e.to_string())
+    }
+}
+
+pub fn executable_name(format: &str) -> String {
+    format!("{}{}", PLUGIN_PREFIX, format)
+}
+
+/// Run the plugin for `format`, feeding it `input` as JSON and returning its output.
+pub fn run<T: Serialize>(format: &str, input: &T) -> Result<String, Box<dyn std::error::Error>> {
+    let executable = executable_name(format);
+    let json = serde_json::to_vec(input)?;
+
+    let mut child = match Command::new(&executable)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Err(format!(
+                    "unknown format {:?}: no built-in formatter and no `{}` executable found on PATH",
+                    format, executable
+                ).into());
+            }
+            Err(e) => return Err(format!("could not run `{}`: {}", executable, e).into()),
+        };
+
+    // Dropping stdin after writing closes the pipe so the plugin sees EOF
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&json)?;
+    }
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!("`{}` failed with {}", executable, output.status).into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
@@ -0,0 +1,72 @@
+//! RIS, the tagged format imported by EndNote, RefWorks and most reference
+//! managers. Software is cited with the `COMP` (computer program) type.
+
+use super::{Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::NaiveDate;
+
+/// Builder for one `TY` ... `ER` record.
+struct Record(String);
+
+impl Record {
+    fn new() -> Self {
+        Record(String::from("TY  - COMP\n"))
+    }
+
+    fn tag(&mut self, tag: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            // Tags hold a single line
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            self.0.push_str(&format!("{}  - {}\n", tag, value));
+        }
+    }
+
+    fn authors(&mut self, people: &[String]) {
+        for person in people {
+            self.tag("AU", Some(&PersonName::parse(person).inverted()));
+        }
+    }
+
+    fn date(&mut self, date: Option<NaiveDate>) {
+        if let Some(date) = date {
+            self.tag("PY", Some(&date.format("%Y").to_string()));
+            self.tag("DA", Some(&date.format("%Y/%m/%d").to_string()));
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.0.push_str("ER  - \n\n");
+        self.0
+    }
+}
+
+pub struct Ris;
+
+impl Formatter for Ris {
+    fn extension(&self) -> &str {
+        "ris"
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        let mut record = Record::new();
+        record.tag("ID", Some(&entry.key));
+        record.tag("TI", Some(&entry.name));
+        record.authors(&entry.authors);
+        record.date(entry.date);
+        record.tag("ET", entry.version.as_deref());
+        record.tag("AB", entry.description.as_deref());
+        record.tag("UR", entry.url());
+        if entry.is_registry() {
+            record.tag("PB", Some("crates.io"));
+        }
+        record.tag("DO", entry.doi.as_deref());
+        for keyword in &entry.keywords {
+            record.tag("KW", Some(keyword));
+        }
+        Some(record.finish())
+    }
+
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        Ok(super::concat(self, package, dependencies))
+    }
+}
@@ -0,0 +1,192 @@
+//! SPDX 2.3 SBOM (bom.spdx.json) whose packages carry references to how each
+//! crate is cited: its DOI, citation file and Software Heritage archive. See
+//! https://spdx.github.io/spdx-spec/v2.3/.
+
+use super::{citation_file_url, purl, Formatter};
+use crate::entry::CitationEntry;
+use crate::{fingerprint, today};
+use serde::Serialize;
+
+const SPDX_VERSION: &str = "SPDX-2.3";
+const FILE_NAME: &str = "bom.spdx.json";
+const NOASSERTION: &str = "NOASSERTION";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Document<'a> {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: CreationInfo,
+    packages: Vec<Package<'a>>,
+    relationships: Vec<Relationship>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Package<'a> {
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_info: Option<&'a str>,
+    download_location: String,
+    files_analyzed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<&'a str>,
+    license_concluded: &'static str,
+    license_declared: String,
+    copyright_text: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_date: Option<String>,
+    /// The citation key, to match the package with the bibliography
+    comment: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_refs: Vec<ExternalRef>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Relationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+/// SPDX identifier of `entry`; identifiers only allow letters, digits, `.`
+/// and `-`.
+fn spdx_id(entry: &CitationEntry) -> String {
+    let id = format!("{}-{}", entry.name, entry.version.as_deref().unwrap_or_default());
+    let id: String = id.trim_end_matches('-').chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect();
+    format!("SPDXRef-Package-{}", id)
+}
+
+impl<'a> Package<'a> {
+    fn new(entry: &'a CitationEntry) -> Self {
+        let mut refs = Vec::new();
+        if let Some(purl) = purl(entry) {
+            refs.push(ExternalRef { reference_category: "PACKAGE-MANAGER", reference_type: "purl", reference_locator: purl, comment: None });
+        }
+        if let Some(doi) = &entry.doi {
+            refs.push(ExternalRef { reference_category: "OTHER", reference_type: "doi", reference_locator: doi.clone(), comment: Some("Cite the DOI") });
+        }
+        if let Some(url) = citation_file_url(entry) {
+            refs.push(ExternalRef { reference_category: "OTHER", reference_type: "citation-file", reference_locator: url, comment: Some("How the authors ask to be cited") });
+        }
+        if let Some(swhid) = &entry.swhid {
+            refs.push(ExternalRef { reference_category: "PERSISTENT-ID", reference_type: "swh", reference_locator: swhid.clone(), comment: None });
+        }
+        let download_location = match (&entry.git, &entry.version) {
+            (Some(git), _) => format!("git+{}", git),
+            (None, Some(version)) if entry.is_registry() => format!("https://crates.io/api/v1/crates/{}/{}/download", entry.name, version),
+            _ => NOASSERTION.to_string(),
+        };
+        Package {
+            spdx_id: spdx_id(entry),
+            name: &entry.name,
+            version_info: entry.version.as_deref(),
+            download_location,
+            files_analyzed: false,
+            homepage: entry.homepage.as_deref().or(entry.repository.as_deref()),
+            license_concluded: NOASSERTION,
+            license_declared: entry.license.clone().unwrap_or_else(|| NOASSERTION.to_string()),
+            copyright_text: NOASSERTION,
+            description: entry.description.as_deref(),
+            release_date: entry.date.map(|d| format!("{}T00:00:00Z", d.format("%Y-%m-%d"))),
+            comment: format!("Citation key: {}", entry.key),
+            external_refs: refs,
+        }
+    }
+}
+
+pub struct Spdx;
+
+impl Formatter for Spdx {
+    fn extension(&self) -> &str {
+        "spdx.json"
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        Some(FILE_NAME)
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn entry(&self, entry: &CitationEntry, _number: usize) -> Option<String> {
+        serde_json::to_string_pretty(&Package::new(entry)).ok()
+    }
+
+    /// A document describing the package, when given, which depends on the
+    /// dependencies; else describing each dependency. It is dated with the
+    /// package, and its namespace derived from the packages listed, so that
+    /// regenerating it leaves it unchanged.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let date = package.and_then(|p| p.date).unwrap_or_else(today);
+        let name = match package {
+            Some(p) => format!("{}-{}", p.name, p.version.as_deref().unwrap_or_default()),
+            None => String::from("dependencies"),
+        };
+        let ids: Vec<String> = package.into_iter().chain(dependencies).map(spdx_id).collect();
+        let relationships = match package {
+            Some(p) => std::iter::once(Relationship {
+                spdx_element_id: String::from("SPDXRef-DOCUMENT"),
+                relationship_type: "DESCRIBES",
+                related_spdx_element: spdx_id(p),
+            })
+            .chain(dependencies.iter().map(|d| Relationship {
+                spdx_element_id: spdx_id(p),
+                relationship_type: "DEPENDS_ON",
+                related_spdx_element: spdx_id(d),
+            }))
+            .collect(),
+            None => dependencies.iter()
+                .map(|d| Relationship {
+                    spdx_element_id: String::from("SPDXRef-DOCUMENT"),
+                    relationship_type: "DESCRIBES",
+                    related_spdx_element: spdx_id(d),
+                })
+                .collect(),
+        };
+        let document = Document {
+            spdx_version: SPDX_VERSION,
+            data_license: "CC0-1.0",
+            spdx_id: "SPDXRef-DOCUMENT",
+            document_namespace: format!("https://spdx.org/spdxdocs/{}-{}", name, fingerprint::hash(&ids.join(" "))),
+            name,
+            creation_info: CreationInfo {
+                created: format!("{}T00:00:00Z", date.format("%Y-%m-%d")),
+                creators: vec![format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+            },
+            packages: package.into_iter().chain(dependencies).map(Package::new).collect(),
+            relationships,
+        };
+        let mut json = serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?;
+        json.push('\n');
+        Ok(json)
+    }
+}
@@ -0,0 +1,188 @@
+//! Plain-text references rendered in common citation styles, ready to paste
+//! into a paper or grant report.
+
+use super::{Formatter, PersonName};
+use crate::entry::CitationEntry;
+use chrono::{Datelike, NaiveDate};
+use std::str::FromStr;
+
+/// Citation style selected with `--style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Apa,
+    Ieee,
+    Chicago,
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "apa" => Ok(Style::Apa),
+            "ieee" => Ok(Style::Ieee),
+            "chicago" => Ok(Style::Chicago),
+            other => Err(format!("unknown style {:?} (expected apa, ieee or chicago)", other)),
+        }
+    }
+}
+
+/// The fields a software reference is rendered from.
+struct Reference<'a> {
+    authors: &'a [String],
+    title: &'a str,
+    version: Option<&'a str>,
+    date: Option<NaiveDate>,
+    publisher: Option<&'a str>,
+    url: Option<&'a str>,
+    doi: Option<&'a str>,
+}
+
+/// `A, B, and C`, with `conjunction` before the last item.
+fn join_list(items: &[String], conjunction: &str, serial_comma: bool) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [first, second] => format!("{} {} {}", first, conjunction, second),
+        [rest @ .., last] => format!("{}{} {} {}", rest.join(", "), if serial_comma { "," } else { "" }, conjunction, last),
+    }
+}
+
+/// `J. Q.` for `Jane Q.`.
+fn initials(given: &str) -> String {
+    given.split_whitespace()
+        .filter_map(|word| word.chars().next())
+        .map(|c| format!("{}.", c))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `particle family` of a person, or the whole name of an organisation.
+fn family_name(name: &PersonName) -> String {
+    name.entity
+        .map(str::to_string)
+        .unwrap_or_else(|| [name.particle, name.family].into_iter().flatten().collect::<Vec<_>>().join(" "))
+}
+
+impl<'a> From<&'a CitationEntry> for Reference<'a> {
+    fn from(entry: &'a CitationEntry) -> Self {
+        Reference {
+            authors: &entry.authors,
+            title: &entry.name,
+            version: entry.version.as_deref(),
+            date: entry.date,
+            publisher: entry.is_registry().then_some("crates.io"),
+            url: entry.url(),
+            doi: entry.doi.as_deref(),
+        }
+    }
+}
+
+impl Reference<'_> {
+    /// The reference in `style`; `number` labels IEEE references.
+    fn render(&self, style: Style, number: usize) -> String {
+        let names: Vec<PersonName> = self.authors.iter().map(|a| PersonName::parse(a)).collect();
+        let year = self.date.map(|d| d.year().to_string());
+        let link = self.doi.map(|doi| format!("https://doi.org/{}", doi)).or(self.url.map(str::to_string));
+
+        let mut parts = Vec::new();
+        match style {
+            Style::Apa => {
+                // Family, G. (Year). Title (Version x) [Computer software]. Publisher. URL
+                let authors: Vec<String> = names.iter()
+                    .map(|n| match (n.entity, n.given) {
+                        (None, Some(given)) => format!("{}, {}", family_name(n), initials(given)),
+                        _ => family_name(n),
+                    })
+                    .collect();
+                let title = match self.version {
+                    Some(version) => format!("{} (Version {}) [Computer software].", self.title, version),
+                    None => format!("{} [Computer software].", self.title),
+                };
+                let date = format!("({}).", year.as_deref().unwrap_or("n.d."));
+                if authors.is_empty() {
+                    parts.push(title);
+                    parts.push(date);
+                } else {
+                    parts.push(join_list(&authors, "&", true));
+                    parts.push(date);
+                    parts.push(title);
+                }
+                parts.extend(self.publisher.map(|p| format!("{}.", p)));
+                parts.extend(link);
+            }
+            Style::Ieee => {
+                // [n] G. Family, "Title," Version x, Publisher, Year. [Online]. Available: URL
+                let authors: Vec<String> = names.iter()
+                    .map(|n| match (n.entity, n.given) {
+                        (None, Some(given)) => format!("{} {}", initials(given), family_name(n)),
+                        _ => family_name(n),
+                    })
+                    .collect();
+                parts.push(format!("[{}]", number));
+                if !authors.is_empty() {
+                    parts.push(format!("{},", join_list(&authors, "and", authors.len() > 2)));
+                }
+                let details: Vec<String> = [
+                    self.version.map(|v| format!("Version {}", v)),
+                    self.publisher.map(str::to_string),
+                    year,
+                ].into_iter().flatten().collect();
+                if details.is_empty() {
+                    parts.push(format!("\"{}.\"", self.title));
+                } else {
+                    parts.push(format!("\"{},\"", self.title));
+                    parts.push(format!("{}.", details.join(", ")));
+                }
+                if let Some(doi) = self.doi {
+                    parts.push(format!("doi: {}.", doi));
+                } else if let Some(url) = self.url {
+                    parts.push(format!("[Online]. Available: {}", url));
+                }
+            }
+            Style::Chicago => {
+                // Family, Given, and Given Family. Title. Version x. Publisher, Year. URL.
+                let authors: Vec<String> = names.iter()
+                    .enumerate()
+                    .map(|(i, n)| match (n.entity, n.given) {
+                        (None, Some(given)) if i == 0 => format!("{}, {}", family_name(n), given),
+                        (None, Some(given)) => format!("{} {}", given, family_name(n)),
+                        _ => family_name(n),
+                    })
+                    .collect();
+                if !authors.is_empty() {
+                    parts.push(format!("{}.", join_list(&authors, "and", true).trim_end_matches('.')));
+                }
+                parts.push(format!("{}.", self.title));
+                parts.extend(self.version.map(|v| format!("Version {}.", v)));
+                let published: Vec<&str> = [self.publisher, year.as_deref()].into_iter().flatten().collect();
+                if !published.is_empty() {
+                    parts.push(format!("{}.", published.join(", ")));
+                }
+                parts.extend(link.map(|l| format!("{}.", l)));
+            }
+        }
+        parts.join(" ")
+    }
+}
+
+/// Plain-text references in a citation style, selected with `--style`.
+pub struct Styled(pub Style);
+
+impl Formatter for Styled {
+    fn extension(&self) -> &str {
+        "txt"
+    }
+
+    fn entry(&self, entry: &CitationEntry, number: usize) -> Option<String> {
+        Some(Reference::from(entry).render(self.0, number))
+    }
+
+    /// A reference list, one paragraph per reference.
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        Ok(super::entries(package, dependencies)
+            .enumerate()
+            .map(|(i, entry)| format!("{}\n\n", Reference::from(entry).render(self.0, i + 1)))
+            .collect())
+    }
+}
@@ -0,0 +1,85 @@
+//! User-defined formats: a Tera template rendered with the citation model,
+//! selected with `--template`.
+//!
+//! The template sees `package`, the package's entry (absent when citing
+//! dependencies only), `dependencies`, and `entries`, both in order. Each
+//! entry has the fields of the JSON output. See https://keats.github.io/tera/.
+
+use super::Formatter;
+use crate::entry::CitationEntry;
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use tera::{Context, Tera};
+
+const TEMPLATE_EXTENSION: &str = "tera";
+/// Name the template is registered under.
+const NAME: &str = "citation";
+
+#[derive(Debug, Serialize)]
+struct Model<'a> {
+    package: Option<&'a CitationEntry>,
+    dependencies: &'a [CitationEntry],
+    entries: Vec<&'a CitationEntry>,
+}
+
+pub struct Template {
+    tera: Tera,
+    /// The template's file name without `.tera`, e.g. `citations.org`
+    file_name: Option<String>,
+    extension: String,
+}
+
+/// `error` and its causes, which Tera reports separately.
+fn describe(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+impl Template {
+    /// Read and parse the template at `path`. Its name without `.tera` names
+    /// the package citation file, and its extension the other files.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Could not read template {:?}: {}", path, e))?;
+        let mut tera = Tera::default();
+        tera.add_raw_template(NAME, &content)
+            .map_err(|e| format!("Invalid template {:?}: {}", path, describe(&e)))?;
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let name = name.strip_suffix(&format!(".{}", TEMPLATE_EXTENSION)).unwrap_or(name);
+        let (file_name, extension) = match Path::new(name).extension().and_then(|e| e.to_str()) {
+            Some(extension) => (Some(name.to_string()), extension.to_string()),
+            None => (None, String::from("txt")),
+        };
+        Ok(Template { tera, file_name, extension })
+    }
+}
+
+impl Formatter for Template {
+    fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    fn includes_dependencies(&self) -> bool {
+        true
+    }
+
+    fn entry(&self, _entry: &CitationEntry, _number: usize) -> Option<String> {
+        None
+    }
+
+    fn document(&self, package: Option<&CitationEntry>, dependencies: &[CitationEntry]) -> Result<String, String> {
+        let model = Model { package, dependencies, entries: super::entries(package, dependencies).collect() };
+        let context = Context::from_serialize(&model).map_err(|e| describe(&e))?;
+        self.tera.render(NAME, &context).map_err(|e| describe(&e))
+    }
+}
@@ -0,0 +1,61 @@
+//! `cargo cite hook install`: a git pre-commit hook running `--check`, so
+//! that commits bumping the version in Cargo.toml without regenerating the
+//! citation files are refused.
+
+use crate::codes::Code;
+use crate::history::git;
+use crate::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marks the hooks written by `install`, which it may replace.
+const MARKER: &str = "Installed by `cargo cite hook install`";
+
+/// `arg` quoted for a POSIX shell when needed.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// The hook script, running `cargo cite --check` with `args`.
+fn script(args: &[String]) -> String {
+    let args: String = args.iter().map(|arg| format!(" {}", shell_quote(arg))).collect();
+    format!(
+        "#!/bin/sh\n\
+         # {}: refuses commits whose citation files are out of date.\n\
+         # Regenerate them with `cargo cite --overwrite{}`.\n\
+         exec cargo cite --check --quiet{}\n",
+        MARKER, args, args,
+    )
+}
+
+/// Install the pre-commit hook of the git repository containing `dir`,
+/// checking the citation files generated with `args`. Another pre-commit
+/// hook is only replaced with `overwrite`. Returns the hook's path.
+pub fn install(dir: &Path, args: &[String], overwrite: bool) -> Result<PathBuf, Error> {
+    let hooks = git(dir, &["rev-parse", "--git-path", "hooks"])
+        .map_err(|e| Error::new(Code::PathAccess, format!("{:?} is not in a git repository: {}", dir, e)))?;
+    // Relative to `dir`, unless core.hooksPath is absolute
+    let hooks = dir.join(hooks);
+    let path = hooks.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&path) {
+        if !existing.contains(MARKER) && !overwrite {
+            return Err(Error::new(Code::PathAccess, format!("A pre-commit hook already exists at {:?}.\nUse --overwrite to replace it, or add `cargo cite --check` to it.", path)));
+        }
+    }
+    let write = |path: &Path| -> std::io::Result<()> {
+        fs::create_dir_all(&hooks)?;
+        fs::write(path, script(args))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    };
+    write(&path).map_err(|e| Error::new(Code::PathAccess, format!("Could not write {:?}: {}", path, e)))?;
+    Ok(path)
+}
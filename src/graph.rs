@@ -0,0 +1,243 @@
+//! The resolved dependency graph reported by `cargo metadata`, for citing
+//! transitive dependencies.
+
+use crate::diagnostics;
+use crate::lockfile::CRATES_IO_SOURCE;
+use crate::registries;
+use crate::{DependencyInfo, DependencyKind};
+use semver::Version;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    packages: Vec<Package>,
+    resolve: Option<Resolve>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+    /// `registry+<index>` or `git+<url>#<commit>`; absent for path packages
+    source: Option<String>,
+    manifest_path: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct Resolve {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    id: String,
+    #[serde(default)]
+    deps: Vec<NodeDep>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NodeDep {
+    pkg: String,
+    #[serde(default)]
+    dep_kinds: Vec<DepKind>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DepKind {
+    /// `dev` or `build`; `None` for normal dependencies
+    kind: Option<String>,
+    /// `cfg(...)` expression or triple the dependency is limited to
+    target: Option<String>,
+}
+
+impl NodeDep {
+    fn kinds(&self) -> impl Iterator<Item = (DependencyKind, Option<&str>)> + '_ {
+        self.dep_kinds.iter().map(|k| {
+            let kind = match k.kind.as_deref() {
+                Some("dev") => DependencyKind::Dev,
+                Some("build") => DependencyKind::Build,
+                _ => DependencyKind::Normal,
+            };
+            (kind, k.target.as_deref())
+        })
+    }
+}
+
+/// A package of the dependency graph, cited as a dependency.
+#[derive(Debug)]
+pub struct GraphDependency {
+    pub kind: DependencyKind,
+    /// Target of the crate's own dependency this package is only needed through
+    pub target: Option<String>,
+    pub info: DependencyInfo,
+}
+
+/// The feature flags cargo resolves the dependency graph with.
+#[derive(Debug, Default)]
+pub struct Features<'a> {
+    pub features: &'a [String],
+    pub all_features: bool,
+    pub no_default_features: bool,
+}
+
+impl Features<'_> {
+    /// Whether any flag differs from cargo's defaults.
+    pub fn is_selected(&self) -> bool {
+        !self.features.is_empty() || self.all_features || self.no_default_features
+    }
+
+    fn args(&self) -> Vec<&str> {
+        let mut args = Vec::new();
+        for features in self.features {
+            args.extend(["--features", features.as_str()]);
+        }
+        if self.all_features {
+            args.push("--all-features");
+        }
+        if self.no_default_features {
+            args.push("--no-default-features");
+        }
+        args
+    }
+}
+
+/// Run `cargo metadata` on the manifest at `manifest_path`.
+fn cargo_metadata(manifest_path: &Path, features: &Features) -> Result<Metadata, String> {
+    // Cargo sets $CARGO for the subcommands it runs
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    diagnostics::verbose(format!("Running `cargo metadata` for {:?}", manifest_path));
+    let output = Command::new(cargo)
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(manifest_path)
+        .args(features.args())
+        .output()
+        .map_err(|e| format!("could not run `cargo metadata`: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("`cargo metadata` failed with {}:\n{}", output.status, stderr.trim_end()));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("unexpected `cargo metadata` output: {}", e))
+}
+
+/// The dependency a package in the graph is cited as: an exact registry
+/// version, a git repository or a directory relative to `base`.
+fn dependency_info(package: &Package, base: &Path) -> DependencyInfo {
+    let source = package.source.as_deref();
+    let git = source
+        .and_then(|s| s.strip_prefix("git+"))
+        .map(|url| url.split(['?', '#']).next().unwrap_or(url).to_string());
+    let path = match source {
+        Some(_) => None,
+        None => package.manifest_path.parent().map(|dir| {
+            dir.strip_prefix(base).unwrap_or(dir).display().to_string()
+        }),
+    };
+    // Alternative registries are named as configured, or else by their index
+    let registry = source
+        .filter(|s| !s.starts_with("git+") && *s != CRATES_IO_SOURCE)
+        .map(|s| registries::by_source(s, base).map_or_else(|| s.to_string(), |r| r.name));
+    DependencyInfo::Detailed {
+        version: Some(format!("={}", package.version)),
+        path,
+        git,
+        package: None,
+        registry,
+    }
+}
+
+/// Names of the crates the crate at `manifest_path` depends on directly once
+/// cargo has resolved `features`; optional dependencies of disabled features
+/// are left out.
+pub fn enabled_dependencies(manifest_path: &Path, features: &Features) -> Result<HashSet<String>, String> {
+    let metadata = cargo_metadata(manifest_path, features)?;
+    let root = root_package(&metadata, manifest_path)?;
+    let resolve = metadata.resolve.as_ref().ok_or("`cargo metadata` did not resolve the dependencies")?;
+    let node = resolve.nodes.iter()
+        .find(|n| n.id == root.id)
+        .ok_or("`cargo metadata` did not resolve the package")?;
+    Ok(node.deps.iter()
+        .filter_map(|dep| metadata.packages.iter().find(|p| p.id == dep.pkg))
+        .map(|p| p.name.clone())
+        .collect())
+}
+
+/// The package of `metadata` whose manifest is at `manifest_path`.
+fn root_package<'a>(metadata: &'a Metadata, manifest_path: &Path) -> Result<&'a Package, String> {
+    let manifest_path = fs::canonicalize(manifest_path).map_err(|e| format!("could not resolve {:?}: {}", manifest_path, e))?;
+    metadata.packages.iter()
+        .find(|p| p.manifest_path == manifest_path)
+        .ok_or_else(|| format!("{:?} is not a package of its workspace", manifest_path))
+}
+
+/// Every package the crate at `manifest_path` depends on, directly or not,
+/// through the dependency `kinds` given, in order of preference. Each is
+/// listed under the first kind it is reached through: the build dependencies
+/// of a normal dependency are build dependencies, and everything only needed
+/// by the crate's own dev-dependencies is a dev dependency. Packages only
+/// needed through a target-specific dependency of the crate carry its target.
+/// A crate resolved at several versions is listed once, at the newest.
+/// Only the dependencies enabled by `features` are followed.
+pub fn transitive_dependencies(manifest_path: &Path, kinds: &[DependencyKind], features: &Features) -> Result<BTreeMap<String, GraphDependency>, String> {
+    let metadata = cargo_metadata(manifest_path, features)?;
+    let root = root_package(&metadata, manifest_path)?;
+    let base = root.manifest_path.parent().unwrap_or(&root.manifest_path);
+    let resolve = metadata.resolve.as_ref().ok_or("`cargo metadata` did not resolve the dependencies")?;
+    let nodes: HashMap<&str, &Node> = resolve.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+    let packages: HashMap<&str, &Package> = metadata.packages.iter().map(|p| (p.id.as_str(), p)).collect();
+
+    // Breadth-first from the root package and everything reached so far,
+    // once per kind, first without then with the root's target-specific
+    // dependencies. Only the root's own dev-dependencies are resolved.
+    let mut reached: HashMap<&str, (DependencyKind, Option<&str>)> = HashMap::from([(root.id.as_str(), (DependencyKind::Normal, None))]);
+    for &kind in kinds {
+        for with_targets in [false, true] {
+            let follows = |edge: DependencyKind, target: Option<&str>, from_root: bool| {
+                let kind_followed = match edge {
+                    DependencyKind::Normal => true,
+                    DependencyKind::Dev => kind == DependencyKind::Dev && from_root,
+                    DependencyKind::Build => kind != DependencyKind::Normal && kinds.contains(&DependencyKind::Build),
+                };
+                kind_followed && (with_targets || !from_root || target.is_none())
+            };
+            let mut queue: VecDeque<&str> = reached.keys().copied().collect();
+            while let Some(id) = queue.pop_front() {
+                let from_root = id == root.id;
+                for dep in nodes.get(id).map(|n| n.deps.as_slice()).unwrap_or_default() {
+                    if reached.contains_key(dep.pkg.as_str()) {
+                        continue;
+                    }
+                    if let Some((_, edge_target)) = dep.kinds().find(|&(k, t)| follows(k, t, from_root)) {
+                        let target = if from_root { edge_target } else { reached[id].1 };
+                        reached.insert(&dep.pkg, (kind, target));
+                        queue.push_back(&dep.pkg);
+                    }
+                }
+            }
+        }
+    }
+    reached.remove(root.id.as_str());
+
+    let mut newest: BTreeMap<&str, (&Package, DependencyKind, Option<&str>)> = BTreeMap::new();
+    for (package, kind, target) in reached.iter().filter_map(|(id, (kind, target))| Some((*packages.get(id)?, *kind, *target))) {
+        let version = Version::parse(&package.version).ok();
+        match newest.get(package.name.as_str()) {
+            Some((other, _, _)) if Version::parse(&other.version).ok() >= version => {}
+            _ => {
+                newest.insert(&package.name, (package, kind, target));
+            }
+        }
+    }
+    Ok(newest.into_iter()
+        .map(|(name, (package, kind, target))| (name.to_string(), GraphDependency {
+            kind,
+            target: target.map(str::to_string),
+            info: dependency_info(package, base),
+        }))
+        .collect())
+}
@@ -0,0 +1,151 @@
+//! `--as-of`: citations as they were at an earlier git revision or date.
+
+use crate::codes::Code;
+use crate::diagnostics;
+use crate::fetch::{parse_date, MetadataFetcher};
+use crate::ResolvedDependency;
+use chrono::NaiveDate;
+use semver::{Version, VersionReq};
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+
+/// Point in time selected with `--as-of`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsOf {
+    Date(NaiveDate),
+    /// A tag, branch or commit
+    Revision(String),
+}
+
+impl FromStr for AsOf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(String::from("expected a date (YYYY-MM-DD) or a git revision"));
+        }
+        Ok(match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            Ok(date) => AsOf::Date(date),
+            Err(_) => AsOf::Revision(s.to_string()),
+        })
+    }
+}
+
+/// The commit that `--as-of` resolved to.
+#[derive(Debug)]
+pub struct Snapshot {
+    pub commit: String,
+    /// The requested date, or the commit date of the requested revision
+    pub date: NaiveDate,
+}
+
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Commit date of the tag of `version` in the repository containing `dir`,
+/// named `v1.2.3`, `1.2.3`, `name-v1.2.3` or `name-1.2.3`.
+pub fn tag_date(dir: &Path, name: &str, version: &str) -> Option<NaiveDate> {
+    let tags = [
+        format!("v{}", version),
+        version.to_string(),
+        format!("{}-v{}", name, version),
+        format!("{}-{}", name, version),
+    ];
+    tags.iter().find_map(|tag| {
+        let spec = format!("refs/tags/{}^{{commit}}", tag);
+        let date = git(dir, &["log", "-1", "--format=%cs", &spec]).ok()?;
+        NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()
+    })
+}
+
+/// Commit date of the last commit changing `dir`, if it is in a git
+/// repository.
+pub fn last_commit_date(dir: &Path) -> Option<NaiveDate> {
+    let date = git(dir, &["log", "-1", "--format=%cs", "--", "."]).ok()?;
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()
+}
+
+impl Snapshot {
+    /// Resolve `as_of` in the repository containing `dir`. A date selects the
+    /// last commit made on or before that day.
+    pub fn resolve(dir: &Path, as_of: &AsOf) -> Result<Snapshot, String> {
+        match as_of {
+            AsOf::Revision(rev) => {
+                let spec = format!("{}^{{commit}}", rev);
+                let commit = git(dir, &["rev-parse", "--verify", "--quiet", &spec])
+                    .map_err(|_| format!("revision {:?} not found in the repository at {:?}", rev, dir))?;
+                let date = git(dir, &["log", "-1", "--format=%cs", &commit])?;
+                let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|e| format!("unexpected commit date {:?}: {}", date, e))?;
+                Ok(Snapshot { commit, date })
+            }
+            AsOf::Date(date) => {
+                let before = format!("--before={} 23:59:59", date);
+                let commit = git(dir, &["rev-list", "-n", "1", &before, "HEAD"])?;
+                if commit.is_empty() {
+                    return Err(format!("no commit on or before {} in the repository at {:?}", date, dir));
+                }
+                Ok(Snapshot { commit, date: *date })
+            }
+        }
+    }
+
+    /// Content of `path` at this commit, or `None` if it did not exist.
+    pub fn read(&self, path: &Path) -> Option<String> {
+        let dir = path.parent()?;
+        let spec = format!("{}:./{}", self.commit, path.file_name()?.to_str()?);
+        git(dir, &["show", &spec]).ok()
+    }
+
+    /// Pin `dependencies` to the versions used at this commit: those recorded
+    /// in the lockfile next to their manifest when it was committed, otherwise
+    /// the newest release matching the requirement published by the date.
+    /// Entries are dated by the release of the pinned version.
+    pub async fn pin_dependencies(&self, fetcher: &MetadataFetcher, dependencies: &mut [ResolvedDependency]) {
+        for dep in dependencies.iter_mut().filter(|d| d.path.is_none() && d.git.is_none()) {
+            // Already pinned to the lockfile committed next to the manifest
+            let locked = dep.lock.as_ref().and_then(|lock| Version::parse(&lock.version).ok());
+            let req = match dep.version.as_deref().map(VersionReq::parse) {
+                Some(Ok(req)) => req,
+                Some(Err(_)) => continue,
+                None => VersionReq::STAR,
+            };
+            // Replaced by the release date once the version is known
+            dep.date = Some(self.date);
+
+            let history = match fetcher.versions(dep.registry_api.as_ref(), &dep.name).await {
+                Ok(history) => history,
+                Err(e) => {
+                    diagnostics::warning(Code::FetchFailed, format!("Could not fetch the version history of {}: {}", dep.name, e));
+                    continue;
+                }
+            };
+            let released = history.iter().filter_map(|v| Some((Version::parse(&v.num).ok()?, parse_date(&v.created_at)?, v.yanked)));
+            let pinned = match &locked {
+                Some(version) => released.filter(|(v, _, _)| v == version).max_by(|a, b| a.0.cmp(&b.0)),
+                None => released
+                    .filter(|(v, date, yanked)| !yanked && *date <= self.date && req.matches(v))
+                    .max_by(|a, b| a.0.cmp(&b.0)),
+            };
+            match pinned {
+                Some((version, date, _)) => {
+                    dep.version = Some(version.to_string());
+                    dep.date = Some(date);
+                }
+                None if locked.is_some() => {}
+                None => diagnostics::warning(Code::RevisionNotFound, format!("No release of {} matching {:?} was published by {}; citing it unpinned.", dep.name, req.to_string(), self.date)),
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Commands run after citation files have been generated.
+
+use crate::codes::Code;
+use crate::diagnostics::{self, MessageFormat};
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Environment variable listing the written files, joined like `PATH`.
+const OUTPUT_FILES_VAR: &str = "CARGO_CITE_OUTPUT_FILES";
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.args(["/C", command]);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.args(["-c", command]);
+        c
+    }
+}
+
+/// Run each `post-generate` hook from `dir` through the platform shell.
+/// Failures are reported as warnings and do not stop the remaining hooks.
+pub fn run_post_generate(commands: &[String], dir: &Path, written: &[PathBuf]) {
+    let output_files = env::join_paths(written).unwrap_or_default();
+    for command in commands {
+        diagnostics::info(format!("Running post-generate hook: {}", command));
+        let mut cmd = shell_command(command);
+        cmd.current_dir(dir).env(OUTPUT_FILES_VAR, &output_files);
+        // Keep stdout clean for JSON message consumers
+        if diagnostics::message_format() == MessageFormat::Json {
+            cmd.stdout(Stdio::from(io::stderr()));
+        }
+        let status = cmd.status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => diagnostics::warning(Code::HookFailed, format!("Post-generate hook `{}` failed with {}", command, status)),
+            Err(e) => diagnostics::warning(Code::HookFailed, format!("Could not run post-generate hook `{}`: {}", command, e)),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Prompts of `--interactive`, written to stderr and answered on stdin.
+//! At the end of the input every prompt takes its default answer.
+
+use crate::progress;
+use std::io::{self, BufRead, Write};
+
+/// Separates the authors when editing them.
+const LIST_SEPARATOR: char = ';';
+
+/// Print `prompt` and read the answer, trimmed. `None` at the end of input.
+fn ask(prompt: &str) -> Option<String> {
+    progress::suspend(|| {
+        eprint!("{} ", prompt);
+        let _ = io::stderr().flush();
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    })
+}
+
+/// Ask a yes or no `question`, `default` answering an empty line.
+pub fn confirm(question: &str, default: bool) -> bool {
+    let choices = if default { "Y/n" } else { "y/N" };
+    loop {
+        let Some(answer) = ask(&format!("{} [{}]", question, choices)) else {
+            return default;
+        };
+        match answer.to_lowercase().as_str() {
+            "" => return default,
+            "y" | "yes" => return true,
+            "n" | "no" => return false,
+            _ => progress::suspend(|| eprintln!("Please answer y or n.")),
+        }
+    }
+}
+
+/// Ask for a new value of `field`, keeping `current` on an empty answer.
+pub fn edit(field: &str, current: &str) -> String {
+    match ask(&format!("{} [{}]:", field, current)) {
+        Some(answer) if !answer.is_empty() => answer,
+        _ => current.to_string(),
+    }
+}
+
+/// Ask for new `items` of `field`, separated by semicolons.
+pub fn edit_list(field: &str, items: &[String]) -> Vec<String> {
+    let separator = format!("{} ", LIST_SEPARATOR);
+    let answer = edit(&format!("{} (separated by {})", field, LIST_SEPARATOR), &items.join(&separator));
+    answer.split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
@@ -0,0 +1,56 @@
+//! `cargo cite joss`: the start of a Journal of Open Source Software paper,
+//! following https://joss.readthedocs.io/en/latest/submitting.html.
+
+use crate::entry::CitationEntry;
+use crate::formats::PersonName;
+use crate::today;
+
+/// Directory JOSS expects the paper in.
+pub const PAPER_DIR: &str = "paper";
+pub const PAPER_MD: &str = "paper.md";
+pub const PAPER_BIB: &str = "paper.bib";
+
+/// YAML single-quoted scalar.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// The `paper.md` skeleton for the package cited by `package`, citing
+/// `dependencies` by their keys in `paper.bib`.
+pub fn paper(package: &CitationEntry, dependencies: &[CitationEntry]) -> String {
+    let title = match &package.description {
+        Some(description) => format!("{}: {}", package.name, description.trim_end_matches('.')),
+        None => package.name.clone(),
+    };
+    let mut text = format!("---\ntitle: {}\ntags:\n  - Rust\n", quote(&title));
+    for keyword in &package.keywords {
+        text.push_str(&format!("  - {}\n", quote(keyword)));
+    }
+
+    text.push_str("authors:\n");
+    for author in &package.authors {
+        let name = PersonName::parse(author).full_name();
+        text.push_str(&format!("  - name: {}\n", quote(&name)));
+        if let Some(orcid) = package.orcid.get(&name) {
+            text.push_str(&format!("    orcid: {}\n", orcid.trim_start_matches("https://orcid.org/")));
+        }
+        text.push_str("    affiliation: 1\n");
+    }
+    text.push_str("affiliations:\n  - name: Institution Name, Country\n    index: 1\n");
+    text.push_str(&format!("date: {}\n", today().format("%-d %B %Y")));
+    text.push_str(&format!("bibliography: {}\n---\n\n", PAPER_BIB));
+
+    text.push_str("# Summary\n\n");
+    let summary = package.description.as_deref().unwrap_or("A summary describing the high-level functionality and purpose of the software for a diverse, non-specialist audience");
+    text.push_str(&format!("{} [@{}].\n\n", summary.trim_end().trim_end_matches('.'), package.key));
+
+    text.push_str("# Statement of need\n\n");
+    text.push_str("Why the software is needed, who it is for, and how it compares to other software in the field.\n");
+    if !dependencies.is_empty() {
+        let keys: Vec<String> = dependencies.iter().map(|d| format!("@{}", d.key)).collect();
+        text.push_str(&format!("\n`{}` is written in Rust and builds on [{}].\n", package.name, keys.join("; ")));
+    }
+
+    text.push_str("\n# Acknowledgements\n\nFinancial support and contributions from others during the project.\n\n# References\n");
+    text
+}
@@ -0,0 +1,2247 @@
+//! Produce citable BibTeX, and other citation formats, from Cargo manifests.
+//!
+//! The `cargo-cite` binary is a thin wrapper around this library, which build
+//! scripts, CI bots and GUIs can embed instead of shelling out:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), cargo_cite::Error> {
+//! use cargo_cite::{generate_citations, CitationOptions};
+//! use std::path::Path;
+//!
+//! let options = CitationOptions { dependencies: true, ..Default::default() };
+//! let generation = generate_citations(Path::new("."), &options).await?;
+//! println!("Wrote {:?}", generation.written);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod archive;
+mod bibtex;
+pub mod cache;
+mod citation_file;
+pub mod credentials;
+pub mod codes;
+pub mod config;
+pub mod coverage;
+pub mod diagnostics;
+pub mod doctor;
+pub mod enrich;
+pub mod entry;
+pub mod fetch;
+mod fingerprint;
+pub mod formats;
+pub mod history;
+mod git_hook;
+mod graph;
+mod hooks;
+mod interactive;
+mod joss;
+pub mod licenses;
+pub mod lint;
+pub mod notice;
+mod lockfile;
+mod mdbook;
+pub mod output;
+pub mod progress;
+mod readme;
+pub mod recommend;
+mod registries;
+mod registry;
+mod rustdoc;
+pub mod stats;
+pub mod watch;
+mod workspace;
+pub mod zenodo;
+
+use archive::{ArchivalInfo, ArchivalRecord};
+use cache::Cache;
+use codes::Code;
+use credentials::Credentials;
+use config::{Config, DependenciesConfig, DependencyOverride, PackageConfig};
+use diagnostics::{EntryKind, MessageFormat, Verbosity};
+use enrich::{Enrichment, Host};
+use fetch::{CrateInfo, RegistryApi};
+use formats::style::Style;
+use formats::bibtex::{Bibtex, Indent, Layout, MonthFormat};
+use entry::{GroupBy, SortOrder};
+use formats::Formatter;
+use history::{AsOf, Snapshot};
+use lockfile::{LockedPackage, Lockfile};
+use output::Outcome;
+use progress::Progress;
+use registries::AltRegistry;
+use zenodo::ZenodoMetadata;
+use futures::stream::{self, StreamExt};
+use gumdrop::Options;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use walkdir::WalkDir;
+use chrono::{Datelike, NaiveDate};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+pub use entry::CitationEntry;
+pub use fetch::MetadataFetcher;
+
+pub const CARGO_FILE: &str = "Cargo.toml";
+/// Exit status when the run failed, or had warnings with `--strict`.
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit status for invalid arguments.
+pub const EXIT_USAGE: u8 = 2;
+const CITATION_FILE: &str = "CITATION";
+const DEPENDENCIES_FILE: &str = "DEPENDENCIES";
+/// Seconds after which requests are abandoned, unless `--timeout` is given.
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+/// Dependencies whose metadata is fetched at the same time.
+const CONCURRENT_FETCHES: usize = 8;
+
+/// The dependencies cited, by glob patterns of their crate names.
+#[derive(Debug, Default)]
+pub struct DependencyFilter {
+    exclude: Vec<glob::Pattern>,
+    /// Empty to cite every crate not excluded
+    only: Vec<glob::Pattern>,
+}
+
+impl DependencyFilter {
+    pub fn includes(&self, crate_name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|p| p.matches(crate_name)))
+            && !self.exclude.iter().any(|p| p.matches(crate_name))
+    }
+}
+
+/// The parts of a Cargo.toml that citations are built from.
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    pub package: PackageInfo,
+    pub dependencies: Option<BTreeMap<String, DependencyInfo>>,
+    #[serde(rename = "dev-dependencies")]
+    pub dev_dependencies: Option<BTreeMap<String, DependencyInfo>>,
+    #[serde(rename = "build-dependencies")]
+    pub build_dependencies: Option<BTreeMap<String, DependencyInfo>>,
+    /// `[target.'cfg(...)'.dependencies]` and the like, keyed by target
+    #[serde(default)]
+    pub target: BTreeMap<String, TargetDependencies>,
+}
+
+/// The dependency tables under one `[target.<cfg or triple>]`.
+#[derive(Debug, Deserialize)]
+pub struct TargetDependencies {
+    pub dependencies: Option<BTreeMap<String, DependencyInfo>>,
+    #[serde(rename = "dev-dependencies")]
+    pub dev_dependencies: Option<BTreeMap<String, DependencyInfo>>,
+    #[serde(rename = "build-dependencies")]
+    pub build_dependencies: Option<BTreeMap<String, DependencyInfo>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub keywords: Option<Vec<String>>,
+    pub license: Option<String>,
+    /// File with the text of a license that has no SPDX identifier
+    #[serde(rename = "license-file")]
+    pub license_file: Option<String>,
+    /// Whether, or to which registries, the package may be published
+    #[serde(default, skip_serializing)]
+    pub publish: Option<Publish>,
+    /// `[package.metadata]`, of which only the `cite` table is read
+    #[serde(default, skip_serializing)]
+    pub metadata: PackageMetadata,
+    /// Declared in the cargo-cite configuration or `[package.metadata.cite]`
+    #[serde(skip_deserializing)]
+    pub maintainers: Vec<String>,
+    #[serde(skip_deserializing)]
+    pub doi: Option<String>,
+    #[serde(skip_deserializing)]
+    pub orcid: BTreeMap<String, String>,
+    #[serde(skip_deserializing)]
+    pub entry_type: Option<String>,
+    #[serde(skip_deserializing)]
+    pub key: Option<String>,
+}
+
+/// `publish` in Cargo.toml: `false`, or the names of the registries the
+/// package may be published to.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Publish {
+    Allowed(bool),
+    Registries(Vec<String>),
+}
+
+/// Tables under `[package.metadata]`; those of other tools are ignored.
+#[derive(Debug, Default, Deserialize)]
+pub struct PackageMetadata {
+    /// Citation overrides, merged under those of cargo-cite.toml
+    #[serde(default, deserialize_with = "config::embedded_package_config")]
+    pub cite: PackageConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum DependencyInfo {
+    Simple(String),
+    Detailed {
+        version: Option<String>,
+        path: Option<String>,
+        git: Option<String>,
+        /// Name of the crate when the dependency is renamed
+        package: Option<String>,
+        /// Alternative registry the crate is published on
+        registry: Option<String>,
+    },
+}
+
+#[derive(Debug, Default, Options)]
+pub struct CitationOptions {
+    #[options(free, help = "Command to run, generating the citation files by default")]
+    pub command: Vec<String>,
+
+    #[options(help = "print help message", short = "h")]
+    pub help: bool,
+
+    #[options(help = "Generate CITATION.bib file", short = "g")]
+    pub generate: bool,
+
+    #[options(help = "Over-write existing CITATION.bib file", short = "o")]
+    pub overwrite: bool,
+
+    #[options(help = "Overwrite citation files even when they were edited by hand since cargo-cite generated them", no_short)]
+    pub force: bool,
+
+    #[options(help = "Merge into existing BibTeX files: update the entries with the keys cargo-cite generates and keep the others, such as hand-written ones", no_short)]
+    pub merge: bool,
+
+    #[options(help = "Update only the version fields of the entries already in existing BibTeX files, leaving the rest as it is", no_short)]
+    pub update: bool,
+
+    #[options(help = "With --update, also update the year, month and date fields", no_short)]
+    pub update_dates: bool,
+
+    #[options(help = "Add a \"Citing\" section to README.md or README.rst, or update the one added before", short = "r")]
+    pub readme_append: bool,
+
+    #[options(help = "Add a DOI badge, or a \"Cite\" badge linking to the citation file, to the badge row of README.md or README.rst", no_short)]
+    pub badge: bool,
+
+    #[options(help = "Add a \"Citing\" section to the crate-level documentation of src/lib.rs, or to the file it includes as its documentation", no_short)]
+    pub rustdoc: bool,
+
+    #[options(help = "File holding the template of the README \"Citing\" section, overriding the configured one", no_short, meta = "FILE")]
+    pub readme_template: Option<String>,
+
+    #[options(help = "Path to the crate, default to current directory. If not specified, will use current directory and recursively search all subdirectories for Cargo.toml files", short = "p")]
+    pub path: Option<String>,
+
+    #[options(help = "Path to Cargo.toml, as in cargo. Only this manifest is processed and relative output paths are resolved against its directory", no_short, meta = "PATH")]
+    pub manifest_path: Option<String>,
+
+    #[options(help = "Citation file to add, default to CITATION.bib (recommended). \"STDOUT\" for outputing to standard output.", short = "f")]
+    pub filename: Option<String>,
+
+    #[options(help = "Generate BibTeX entries for all explicit dependencies", short = "d")]
+    pub dependencies: bool,
+
+    #[options(help = "Also cite the [dev-dependencies]", no_short)]
+    pub dev_deps: bool,
+
+    #[options(help = "Also cite the [build-dependencies]", no_short)]
+    pub build_deps: bool,
+
+    #[options(help = "Only cite the dependencies enabled with these features, as resolved by cargo. Space or comma separated, may be repeated", no_short, meta = "FEATURES")]
+    pub features: Vec<String>,
+
+    #[options(help = "Only cite the dependencies enabled with every feature", no_short)]
+    pub all_features: bool,
+
+    #[options(help = "Only cite the dependencies enabled without the default features", no_short)]
+    pub no_default_features: bool,
+
+    #[options(help = "Do not credit dependencies that list no authors to their crates.io owners", no_short)]
+    pub no_owners: bool,
+
+    #[options(help = "Add the contributors, latest release and description of dependencies from their repository host's API: github, gitlab, codeberg or bitbucket. May be repeated. Tokens for github, gitlab and codeberg raise the API rate limits", no_short, meta = "HOST")]
+    pub enrich: Vec<Host>,
+
+    #[options(help = "Leave out the dependencies whose crate name matches this glob, e.g. \"*-sys\". May be repeated", no_short, meta = "GLOB")]
+    pub exclude: Vec<String>,
+
+    #[options(help = "Only cite the dependencies whose crate name matches this glob. May be repeated", no_short, meta = "GLOB")]
+    pub only: Vec<String>,
+
+    #[options(help = "Also cite path dependencies in the same repository, such as the other members of a workspace", no_short)]
+    pub include_internal: bool,
+
+    #[options(help = "Cite every crate in the resolved dependency graph, as listed by `cargo metadata`, rather than only the direct dependencies", no_short)]
+    pub transitive: bool,
+
+    #[options(help = "For a workspace, also write one citation file at its root citing every member package", no_short)]
+    pub workspace_citation: bool,
+
+    #[options(help = "Only cite dependencies from this kind of source: registry, git, path or all (default)", no_short, meta = "KIND")]
+    pub source: Option<SourceKind>,
+
+    #[options(help = "Maximum depth for recursive search (default: unlimited). 0 means only current directory, -1 means unlimited depth.", short = "m")]
+    pub max_depth: Option<i32>,
+
+    #[options(help = "Namespace used to scope all generated citation keys, e.g. \"myproj\" produces \"myproj:serde\"", no_short, meta = "NS")]
+    pub key_namespace: Option<String>,
+
+    #[options(help = "Template of the citation keys, with {name}, {version} and {year} placeholders, e.g. \"{name}_{version}_{year}\". Characters BibTeX tools reject become underscores", no_short, meta = "TEMPLATE")]
+    pub key_template: Option<String>,
+
+    #[options(help = "Citation format: \"bibtex\" (default), \"cff\", \"codemeta\", \"csl-json\", \"ris\", \"endnote-xml\", \"markdown\", \"html\", \"json\", \"latex\", \"pandoc\", \"cyclonedx\" or \"spdx\". Any other name runs the cargo-cite-format-<name> executable found on PATH with the collected metadata as JSON on stdin", no_short, meta = "FORMAT")]
+    pub format: Option<String>,
+
+    #[options(help = "Render the citations with this Tera template instead of a format. Its name without .tera names the package citation file, e.g. citations.org.tera", no_short, meta = "FILE")]
+    pub template: Option<String>,
+
+    #[options(help = "With --format html, include each BibTeX entry in a <pre> block", no_short)]
+    pub embed_bibtex: bool,
+
+    #[options(help = "BibTeX entry type of the entries without a configured one, e.g. \"software\" or \"online\", instead of \"misc\"", no_short, meta = "TYPE")]
+    pub entry_type: Option<String>,
+
+    #[options(help = "Write biblatex entries: @software, with a date field instead of year and month, and the urldate of their URLs", no_short)]
+    pub biblatex: bool,
+
+    #[options(help = "Order of the cited dependencies: \"none\" (default, as declared), \"name\" (by citation key) or \"kind\" (normal, dev, then build dependencies)", no_short, meta = "ORDER")]
+    pub sort: Option<SortOrder>,
+
+    #[options(help = "Group the cited dependencies into sections of the dependencies file, headed by a BibTeX comment: \"kind\" (runtime, dev and build dependencies) or \"category\" (first crates.io category)", no_short, meta = "GROUPING")]
+    pub group_by: Option<GroupBy>,
+
+    #[options(help = "With --group-by, write each group to its own file, e.g. DEPENDENCIES-runtime.bib, in any format", no_short)]
+    pub split: bool,
+
+    #[options(help = "How BibTeX month fields are written: \"macro\" (default, e.g. mar), \"numeric\" (3) or \"name\" ({March})", no_short, meta = "FORMAT")]
+    pub month_format: Option<MonthFormat>,
+
+    #[options(help = "Indent BibTeX fields with a tab (default) or this many spaces", no_short, meta = "tab|N")]
+    pub indent: Option<Indent>,
+
+    #[options(help = "Pad BibTeX field names so that the = signs line up", no_short)]
+    pub align_fields: bool,
+
+    #[options(help = "BibTeX fields to write first, in this order, comma separated, e.g. \"author,title,year\"; the others follow", no_short, meta = "FIELDS")]
+    pub field_order: Option<String>,
+
+    #[options(help = "Leave out the comma after the last field of BibTeX entries", no_short)]
+    pub no_trailing_comma: bool,
+
+    #[options(help = "Leave LaTeX special characters such as & and _ unescaped in BibTeX text fields, e.g. for biber", no_short)]
+    pub no_latex_escape: bool,
+
+    #[options(help = "Write plain-text references in a citation style instead: apa, ieee or chicago", no_short, meta = "STYLE")]
+    pub style: Option<Style>,
+
+    #[options(help = "Configuration file, default to cargo-cite.toml in the search directory when present", no_short, meta = "PATH")]
+    pub config: Option<String>,
+
+    #[options(help = "Generate the citations as they were at a git tag, commit or date (YYYY-MM-DD), pinning dependencies to the versions in use then", no_short, meta = "DATE|REV")]
+    pub as_of: Option<AsOf>,
+
+    #[options(help = "List Zenodo DOIs, Software Heritage archives and CITATION files of dependencies not yet used in the dependencies bibliography", no_short)]
+    pub recommend: bool,
+
+    #[options(help = "After generating, print a table of the license of each cited crate", no_short)]
+    pub license_summary: bool,
+
+    #[options(help = "With --dependencies, cite dependencies through their DOI, Software Heritage archive or own CITATION.bib when available", no_short)]
+    pub adopt_recommended: bool,
+
+    #[options(help = "With --dependencies, search Zenodo and DataCite for a DOI archiving each dependency and cite it with the archived record's authors and date", no_short)]
+    pub lookup_doi: bool,
+
+    #[options(help = "Synthesize BibTeX entries for packages and dependencies with a DOI rather than using the entry doi.org returns for it", no_short)]
+    pub no_doi_bibtex: bool,
+
+    #[options(help = "Do not cite dependencies with the entry from the CITATION.cff or CITATION.bib shipped in their downloaded source", no_short)]
+    pub no_citation_files: bool,
+
+    #[options(help = "Cite authors with an ORCID iD under the name on their public ORCID record", no_short)]
+    pub resolve_orcid: bool,
+
+    #[options(help = "Look up the Software Heritage archive of the package's and dependencies' repositories and cite its SWHID", no_short)]
+    pub swhid: bool,
+
+    #[options(help = "DOI of the package, replacing the configured one", no_short, meta = "DOI")]
+    pub doi: Option<String>,
+
+    #[options(help = "With deposit, deposit the package on Zenodo, authenticated by the configured zenodo token", no_short)]
+    pub zenodo: bool,
+
+    #[options(help = "With deposit --zenodo, use the Zenodo sandbox to try out depositing", no_short)]
+    pub zenodo_sandbox: bool,
+
+    #[options(help = "With deposit, publish the deposition rather than leaving it as a draft. Published records cannot be deleted", no_short)]
+    pub publish: bool,
+
+    #[options(help = "Also write a .zenodo.json next to each Cargo.toml, describing the package to Zenodo's GitHub integration", no_short)]
+    pub zenodo_json: bool,
+
+    #[options(help = "Also write a citations.md chapter citing the crate and its dependencies into the mdBook in this directory, linked from its SUMMARY.md", no_short, meta = "BOOK_DIR")]
+    pub mdbook: Option<String>,
+
+    #[options(help = "Never access the network: read dependency metadata from the sources cargo downloaded to ~/.cargo/registry and from cached responses", no_short)]
+    pub offline: bool,
+
+    #[options(help = "Give up on requests after this many seconds (default 30)", no_short, meta = "SECS")]
+    pub timeout: Option<u64>,
+
+    #[options(help = "Send requests through this proxy rather than the one set by HTTPS_PROXY, HTTP_PROXY or ALL_PROXY", no_short, meta = "URL")]
+    pub proxy: Option<String>,
+
+    #[options(help = "Base URL of the crates.io web API, for mirrors (default https://crates.io/api/v1)", no_short, meta = "URL")]
+    pub registry_api_url: Option<String>,
+
+    #[options(help = "Fetch metadata from the network on every run rather than reusing responses cached in ~/.cache/cargo-cite", no_short)]
+    pub no_cache: bool,
+
+    #[options(help = "Fetch metadata again, replacing the cached responses", no_short)]
+    pub refresh: bool,
+
+    #[options(help = "Reuse cached responses for this many hours (default 24)", no_short, meta = "HOURS")]
+    pub cache_ttl: Option<u64>,
+
+    #[options(help = "Explain a warning or error code, e.g. CITE002", no_short, meta = "CODE")]
+    pub explain: Option<Code>,
+
+    #[options(help = "Format of diagnostics and generated entries on stdout: \"human\" (default), \"json\" for one JSON message per line, or \"gha\" for GitHub Actions annotations", no_short, meta = "FMT")]
+    pub message_format: Option<MessageFormat>,
+
+    #[options(help = "Ask for each manifest whether to cite it, overwrite its citation file and append to its README, and edit its authors and description", no_short)]
+    pub interactive: bool,
+
+    #[options(help = "Check that the citation files are up to date instead of writing them, printing a diff of those that are not", no_short)]
+    pub check: bool,
+
+    #[options(help = "Report the files that would be created, overwritten or appended to, with a diff, without writing them", no_short)]
+    pub dry_run: bool,
+
+    #[options(help = "Keep running, regenerating the citation files whenever a manifest, lockfile or configuration file changes. Implies --overwrite", no_short)]
+    pub watch: bool,
+
+    #[options(help = "Exit with a failure status when a file is skipped or a warning is reported", no_short)]
+    pub strict: bool,
+
+    #[options(help = "Only report errors", short = "q")]
+    pub quiet: bool,
+
+    #[options(help = "Also report the requests made; twice for details of the files read", short = "v", count)]
+    pub verbose: u32,
+}
+
+/// Kind of source a dependency comes from, as selected with `--source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Registry,
+    Git,
+    Path,
+    All,
+}
+
+impl FromStr for SourceKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "registry" => Ok(SourceKind::Registry),
+            "git" => Ok(SourceKind::Git),
+            "path" => Ok(SourceKind::Path),
+            "all" => Ok(SourceKind::All),
+            other => Err(format!("unknown source kind {:?} (expected registry, git, path or all)", other)),
+        }
+    }
+}
+
+impl SourceKind {
+    fn includes(self, kind: SourceKind) -> bool {
+        self == SourceKind::All || self == kind
+    }
+}
+
+/// Dependency table a dependency is declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DependencyKind {
+    /// How citations note the kind; `None` for normal dependencies.
+    pub fn description(self) -> Option<&'static str> {
+        match self {
+            DependencyKind::Normal => None,
+            DependencyKind::Dev => Some("Development dependency"),
+            DependencyKind::Build => Some("Build dependency"),
+        }
+    }
+}
+
+/// A dependency together with the metadata fetched for it.
+#[derive(Debug)]
+pub struct ResolvedDependency {
+    key: String,
+    name: String,
+    /// Name the dependency is imported as, when renamed with `package`
+    alias: Option<String>,
+    kind: DependencyKind,
+    /// Target the dependency is limited to, e.g. `cfg(windows)`
+    target: Option<String>,
+    /// Alternative registry the crate is published on, by name
+    registry: Option<String>,
+    /// Where that registry serves its index and web API, when configured
+    registry_api: Option<RegistryApi>,
+    manifest_path: PathBuf,
+    version: Option<String>,
+    path: Option<String>,
+    git: Option<String>,
+    metadata: Option<CrateInfo>,
+    date: Option<NaiveDate>,
+    /// Downloaded source in the local cargo registry or git checkouts, when
+    /// available
+    local_source: Option<PathBuf>,
+    /// The package in Cargo.lock, whose version replaces the requirement
+    lock: Option<LockedPackage>,
+    /// crates.io owners, cited as the authors of crates that list none
+    owners: Vec<String>,
+    /// Repository details from `--enrich`
+    enrichment: Option<Enrichment>,
+    /// Zenodo or DataCite record whose metadata the entry prefers
+    archival: Option<ArchivalRecord>,
+    doi: Option<String>,
+    swhid: Option<String>,
+    /// Entry from the dependency's own citation file or registered for its DOI
+    preferred_entry: Option<String>,
+    /// Corrections from the configuration, applied over everything fetched
+    overrides: Option<DependencyOverride>,
+}
+
+fn today() -> NaiveDate {
+    chrono::prelude::Local::now().date_naive()
+}
+
+/// Placeholders of `--key-template`.
+const KEY_TEMPLATE_VARIABLES: &[&str] = &["name", "version", "year"];
+
+/// Build the citation key of `name` at `version`, released in `year`: the
+/// key template rendered, or `default_prefix` followed by the name. A
+/// namespace scopes the key as `ns:key`.
+fn citation_key(name: &str, version: Option<&str>, year: Option<i32>, options: &CitationOptions, default_prefix: &str) -> String {
+    let key = match &options.key_template {
+        Some(template) => sanitize_key(&template
+            .replace("{name}", name)
+            .replace("{version}", version.unwrap_or_default())
+            .replace("{year}", &year.map(|year| year.to_string()).unwrap_or_default())),
+        None => format!("{}{}", default_prefix, name),
+    };
+    match options.key_namespace.as_deref() {
+        Some(ns) => format!("{}:{}", ns, key),
+        None => key,
+    }
+}
+
+/// `key` with the characters some BibTeX tools reject replaced by underscores.
+fn sanitize_key(key: &str) -> String {
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_:.".contains(c) { c } else { '_' })
+        .collect()
+}
+
+/// Check that `template` only uses the placeholders of `--key-template`.
+fn check_key_template(template: &str) -> Result<(), Error> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').map(|end| start + end)
+            .ok_or_else(|| Error::new(Code::InvalidCommand, format!("Unterminated {{ in key template {:?}.", template)))?;
+        let name = &rest[start + 1..end];
+        if !KEY_TEMPLATE_VARIABLES.contains(&name) {
+            return Err(Error::new(Code::InvalidCommand, format!("Unknown placeholder {{{}}} in key template {:?} (expected {{name}}, {{version}} or {{year}}).", name, template)));
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(())
+}
+
+/// The name part of a Cargo-style `Name <email>` person entry.
+fn person_name(person: &str) -> &str {
+    person.split('<').next().unwrap_or(person).trim()
+}
+
+impl PackageInfo {
+    /// Whether the package may be published to crates.io, so that its
+    /// releases can be looked up there.
+    fn publishes_to_crates_io(&self) -> bool {
+        match &self.publish {
+            None => true,
+            Some(Publish::Allowed(allowed)) => *allowed,
+            Some(Publish::Registries(registries)) => registries.iter().any(|r| r == "crates-io"),
+        }
+    }
+
+    /// Apply the configured overrides, those of cargo-cite.toml taking
+    /// precedence over `[package.metadata.cite]`, and separate maintainers
+    /// from authors.
+    fn apply_config(&mut self, config: &PackageConfig) {
+        let config = config.or(&self.metadata.cite);
+        if let Some(authors) = config.authors {
+            self.authors = authors;
+        }
+        self.maintainers = config.maintainers;
+        self.doi = config.doi;
+        self.orcid = config.orcid;
+        self.entry_type = config.entry_type;
+        self.key = config.key;
+        let maintainer_names: Vec<&str> = self.maintainers.iter().map(|m| person_name(m)).collect();
+        self.authors.retain(|a| !maintainer_names.contains(&person_name(a)));
+    }
+
+    /// The configured citation key, or the one `options` give the release
+    /// of `date`.
+    fn citation_key(&self, options: &CitationOptions, date: NaiveDate) -> String {
+        self.key.clone().unwrap_or_else(|| citation_key(&self.name, Some(&self.version), Some(date.year()), options, ""))
+    }
+}
+
+impl DependencyInfo {
+    fn get_version(&self) -> Option<String> {
+        match self {
+            DependencyInfo::Simple(v) => Some(v.clone()),
+            DependencyInfo::Detailed { version, .. } => version.clone(),
+        }
+    }
+
+    fn source_kind(&self) -> SourceKind {
+        match self.get_source_info() {
+            (Some(_), _) => SourceKind::Path,
+            (None, Some(_)) => SourceKind::Git,
+            (None, None) => SourceKind::Registry,
+        }
+    }
+
+    fn get_source_info(&self) -> (Option<String>, Option<String>) {
+        match self {
+            DependencyInfo::Simple(_) => (None, None),
+            DependencyInfo::Detailed { path, git, .. } => (path.clone(), git.clone()),
+        }
+    }
+
+    /// The crate's own name, when the dependency renames it.
+    fn package(&self) -> Option<&str> {
+        match self {
+            DependencyInfo::Simple(_) => None,
+            DependencyInfo::Detailed { package, .. } => package.as_deref(),
+        }
+    }
+
+    /// The alternative registry the crate comes from, when not crates.io.
+    fn registry(&self) -> Option<&str> {
+        match self {
+            DependencyInfo::Simple(_) => None,
+            DependencyInfo::Detailed { path: None, git: None, registry, .. } => registry.as_deref(),
+            DependencyInfo::Detailed { .. } => None,
+        }
+    }
+}
+
+/// A dependency to cite, as declared in a manifest or found in the
+/// dependency graph.
+struct Declared<'a> {
+    name: &'a str,
+    kind: DependencyKind,
+    target: Option<&'a str>,
+    info: &'a DependencyInfo,
+}
+
+impl Declared<'_> {
+    /// The crate's own name, which `name` differs from when renamed.
+    fn crate_name(&self) -> &str {
+        self.info.package().unwrap_or(self.name)
+    }
+}
+
+impl TargetDependencies {
+    fn table(&self, kind: DependencyKind) -> Option<&BTreeMap<String, DependencyInfo>> {
+        match kind {
+            DependencyKind::Normal => self.dependencies.as_ref(),
+            DependencyKind::Dev => self.dev_dependencies.as_ref(),
+            DependencyKind::Build => self.build_dependencies.as_ref(),
+        }
+    }
+}
+
+impl Manifest {
+    fn table(&self, kind: DependencyKind) -> Option<&BTreeMap<String, DependencyInfo>> {
+        match kind {
+            DependencyKind::Normal => self.dependencies.as_ref(),
+            DependencyKind::Dev => self.dev_dependencies.as_ref(),
+            DependencyKind::Build => self.build_dependencies.as_ref(),
+        }
+    }
+
+    /// The dependencies declared in the tables of `kinds`, including the
+    /// target-specific ones. Each crate is listed once, under the first of
+    /// its kinds, even when imported under several names.
+    fn declared(&self, kinds: &[DependencyKind]) -> Vec<Declared<'_>> {
+        let mut declared: Vec<Declared> = Vec::new();
+        for &kind in kinds {
+            let tables = std::iter::once((None, self.table(kind)))
+                .chain(self.target.iter().map(|(target, deps)| (Some(target.as_str()), deps.table(kind))));
+            for (target, table) in tables {
+                for (name, info) in table.into_iter().flatten() {
+                    let dep = Declared { name, kind, target, info };
+                    if !declared.iter().any(|d| d.crate_name() == dep.crate_name()) {
+                        declared.push(dep);
+                    }
+                }
+            }
+        }
+        declared
+    }
+}
+
+/// Collect the dependencies in `deps` selected by `--source`, fetching
+/// crates.io metadata for regular (non-path, non-git) ones and repository
+/// details from the `--enrich` hosts.
+/// Versions are those locked in `lockfile` when it has the dependency.
+/// Crates listing no authors are credited to their repository's contributors
+/// or crates.io owners, unless `--no-owners` is given.
+/// Crates from alternative registries are looked up in the registry cargo's
+/// configuration names, authenticated with its token.
+/// Dependencies are resolved `CONCURRENT_FETCHES` at a time, shown on a
+/// progress bar, and listed in the order of `deps`; the fetcher still sends
+/// the crates.io web API one request a second.
+async fn resolve_dependencies(deps: &[Declared<'_>], fetcher: &MetadataFetcher, manifest_path: &Path, lockfile: Option<&Lockfile>, opt: &CitationOptions) -> Vec<ResolvedDependency> {
+    let sources = opt.source.unwrap_or(SourceKind::All);
+    let deps: Vec<&Declared> = deps.iter().filter(|dep| sources.includes(dep.info.source_kind())).collect();
+
+    let dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let mut registries = BTreeMap::new();
+    for name in deps.iter().filter_map(|dep| dep.info.registry()) {
+        if registries.contains_key(name) {
+            continue;
+        }
+        match registries::find(name, dir) {
+            Some(registry) => {
+                let api = fetcher.alt_registry(&registry).await;
+                registries.insert(name, (registry, api));
+            }
+            None => diagnostics::warning_in(Code::FetchFailed, manifest_path, format!("Registry {} is not configured in .cargo/config.toml; its crates are only described from their downloaded sources.", name)),
+        }
+    }
+
+    let progress = Progress::new(deps.len(), "dependencies");
+    stream::iter(deps)
+        .map(|dep| async {
+            progress.start();
+            let resolved = resolve_dependency(dep, fetcher, manifest_path, lockfile, &registries, opt).await;
+            progress.finish();
+            resolved
+        })
+        .buffered(CONCURRENT_FETCHES)
+        .collect()
+        .await
+}
+
+/// Resolve one dependency for `resolve_dependencies`, with the configured
+/// alternative `registries`.
+async fn resolve_dependency(&Declared { name, kind, target, info }: &Declared<'_>, fetcher: &MetadataFetcher, manifest_path: &Path, lockfile: Option<&Lockfile>, registries: &BTreeMap<&str, (AltRegistry, RegistryApi)>, opt: &CitationOptions) -> ResolvedDependency {
+    // Renamed dependencies are cited and looked up under the crate's own name
+    let (name, alias) = match info.package() {
+        Some(package) => (package, Some(name.to_string())),
+        None => (name, None),
+    };
+    let (path, git) = info.get_source_info();
+    let req = info.get_version().and_then(|v| VersionReq::parse(&v).ok()).unwrap_or(VersionReq::STAR);
+    let registry = info.registry();
+    let registry_config = registry.and_then(|r| registries.get(r));
+    let registry_api = registry_config.map(|(_, api)| api);
+    let lock = lockfile.and_then(|l| l.locked(name, &req, git.as_deref(), path.is_some(), registry_config.map(|(r, _)| r))).cloned();
+    let mut version = match &lock {
+        Some(lock) => Some(lock.version.clone()),
+        None => info.get_version(),
+    };
+    // Without a lockfile, registry dependencies are cited at the newest
+    // release the sparse index lists for their requirement, unless `--as-of`
+    // pins them to the releases of the time instead
+    // Registries that are not configured cannot be queried
+    let mut published = registry.is_none() || registry_api.is_some();
+    if path.is_none() && git.is_none() && lock.is_none() && opt.as_of.is_none() && published {
+        match fetcher.index_versions(registry_api, name).await {
+            Ok(Some(versions)) => {
+                if let Some(newest) = fetch::newest_release(&versions, &req) {
+                    version = Some(newest.to_string());
+                }
+            }
+            Ok(None) => {
+                diagnostics::warning_in(Code::FetchFailed, manifest_path, format!("{} is not published on {}.", name, registry.unwrap_or("crates.io")));
+                published = false;
+            }
+            // The crates.io lookups below report the failure
+            Err(_) => {}
+        }
+    }
+    let local_source = match (&path, &git) {
+        (None, None) => {
+            let exact = lock.as_ref().map(|l| format!("={}", l.version));
+            registry::find_source(name, exact.as_deref().or(version.as_deref()))
+        }
+        (None, Some(git)) => registry::find_checkout(git, lock.as_ref().and_then(LockedPackage::git_commit)),
+        (Some(_), _) => None,
+    };
+    let local_metadata = local_source.as_deref().and_then(registry::read_metadata);
+
+    // Registry dependencies are dated by the release of the cited version
+    let release_date = match (&path, &git, &version) {
+        (None, None, Some(version)) if published => fetcher.release_date(registry_api, name, version).await,
+        _ => None,
+    };
+    let (metadata, date) = if let Some(git) = &git {
+        (None, fetcher.repository_created(git).await)
+    } else if local_metadata.is_some() {
+        // Downloaded sources need no network access for their metadata
+        (local_metadata, release_date)
+    } else if path.is_none() && published {
+        let metadata = match fetcher.crate_info(registry_api, name).await {
+            Ok(metadata) => Some(metadata),
+            Err(_) if fetcher.is_offline() => {
+                diagnostics::warning_in(Code::FetchFailed, manifest_path, format!("No downloaded source of {} to read its metadata from offline. Run `cargo fetch` first.", name));
+                None
+            }
+            Err(e) => {
+                diagnostics::warning_in(Code::FetchFailed, manifest_path, format!("Could not fetch {} metadata for {}: {}", registry.unwrap_or("crates.io"), name, e));
+                None
+            }
+        };
+        let date = match release_date {
+            Some(date) => Some(date),
+            None => {
+                let repository = metadata.as_ref().and_then(|m| m.repository.as_deref());
+                fetcher.fallback_date(repository, metadata.as_ref()).await
+            }
+        };
+        (metadata, date)
+    } else {
+        (None, None)
+    };
+    let repository = git.as_deref().or(metadata.as_ref().and_then(|m| m.repository.as_deref()));
+    let enrichment = match repository {
+        Some(url) if !opt.enrich.is_empty() => enrich::enrich(fetcher, &opt.enrich, url).await,
+        _ => None,
+    };
+    // The repository's release of the cited version, when crates.io has none
+    let date = match (&enrichment, &version) {
+        (Some(enrichment), Some(version)) if release_date.is_none() => enrichment.release_date(version).or(date),
+        _ => date,
+    };
+
+    let has_authors = metadata.as_ref().and_then(|m| m.authors.as_ref()).is_some_and(|a| !a.is_empty())
+        || enrichment.as_ref().is_some_and(|e| !e.contributors.is_empty());
+    let owners = if !opt.no_owners && path.is_none() && git.is_none() && !has_authors {
+        fetcher.owner_authors(registry_api, name, version.as_deref()).await
+    } else {
+        Vec::new()
+    };
+    let key = citation_key(name, version.as_deref(), date.map(|d| d.year()), opt, "rust-");
+    // How the authors ask to be cited beats what can be pieced together
+    let preferred_entry = match &local_source {
+        Some(dir) if !opt.no_citation_files => citation_file::shipped_entry(dir, &key),
+        _ => None,
+    };
+    ResolvedDependency {
+        key,
+        name: name.to_string(),
+        alias,
+        kind,
+        target: target.map(str::to_string),
+        registry: registry.map(str::to_string),
+        registry_api: registry_api.cloned(),
+        manifest_path: manifest_path.to_path_buf(),
+        version,
+        path,
+        git,
+        metadata,
+        date,
+        local_source,
+        lock,
+        owners,
+        enrichment,
+        archival: None,
+        doi: None,
+        swhid: None,
+        preferred_entry,
+        overrides: None,
+    }
+}
+
+impl ResolvedDependency {
+    /// Cite the dependency through its archival records from now on.
+    fn adopt(&mut self, info: ArchivalInfo) {
+        self.preferred_entry = info.citation_entry(&self.key).or(self.preferred_entry.take());
+        self.doi = info.doi().map(str::to_string);
+        self.swhid = info.swhid;
+        self.archival = info.record;
+    }
+
+    /// Cite the dependency as `overrides` corrects it. The BibTeX entry given
+    /// replaces the one the crate ships; with a DOI or corrected fields, the
+    /// entry is generated instead.
+    fn apply_override(&mut self, overrides: DependencyOverride) {
+        if let Some(doi) = &overrides.doi {
+            self.doi = Some(doi.clone());
+        }
+        if overrides.bibtex.is_some() || overrides.doi.is_some() || overrides.edits_fields() {
+            self.preferred_entry = overrides.bibtex.as_deref().map(|entry| entry.trim().to_string());
+        }
+        match (&overrides.key, self.preferred_entry.as_deref().and_then(bibtex::entry_key)) {
+            (Some(key), _) => {
+                self.preferred_entry = self.preferred_entry.as_deref().map(|entry| bibtex::rekey(entry, key));
+                self.key = key.clone();
+            }
+            (None, Some(key)) => self.key = key.to_string(),
+            (None, None) => {}
+        }
+        self.overrides = Some(overrides);
+    }
+
+    /// Whether the DOI or BibTeX entry is given by an override, which
+    /// archive lookups leave alone.
+    fn overridden(&self) -> bool {
+        self.overrides.as_ref().is_some_and(|o| o.doi.is_some() || o.bibtex.is_some())
+    }
+
+    /// Cite the dependency through its DOI record from now on.
+    fn adopt_record(&mut self, record: ArchivalRecord) {
+        self.doi = Some(record.doi.clone());
+        self.archival = Some(record);
+    }
+
+    /// The git source, or the repository from the crate's metadata.
+    fn repository(&self) -> Option<&str> {
+        self.git.as_deref().or(self.metadata.as_ref().and_then(|m| m.repository.as_deref()))
+    }
+
+    fn source_kind(&self) -> SourceKind {
+        match (&self.path, &self.git) {
+            (Some(_), _) => SourceKind::Path,
+            (None, Some(_)) => SourceKind::Git,
+            (None, None) => SourceKind::Registry,
+        }
+    }
+}
+
+fn find_cargo_files(start_dir: &Path, max_depth: Option<i32>) -> Vec<PathBuf> {
+    let walker = WalkDir::new(start_dir).follow_links(true);
+    
+    // Apply max depth if specified, otherwise unlimited
+    let walker = match max_depth {
+        Some(depth) if depth >= 0 => walker.max_depth(depth as usize),
+        Some(_) => walker, // negative means unlimited
+        None => walker, // default to unlimited
+    };
+
+    walker
+        .into_iter()
+        .filter_map(|e| match e {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                diagnostics::warning(Code::PathAccess, format!("Error accessing path: {}", err));
+                None
+            }
+        })
+        .filter(|e| e.file_type().is_file() && e.file_name() == CARGO_FILE)
+        .map(|e| {
+            diagnostics::info(format!("Found Cargo.toml at: {:?}", e.path()));
+            e.path().to_path_buf()
+        })
+        .collect()
+}
+
+/// Read and parse a manifest, from the working tree or as it was in `snapshot`.
+fn read_manifest(cargo_path: &Path, snapshot: Option<&Snapshot>) -> Result<Manifest, String> {
+    let cargo_content = match snapshot {
+        Some(snapshot) => snapshot.read(cargo_path)
+            .ok_or_else(|| format!("{:?} did not exist at commit {}", cargo_path, snapshot.commit))?,
+        None => fs::read_to_string(cargo_path)
+            .map_err(|e| format!("Could not read {:?}: {}", cargo_path, e))?,
+    };
+    let invalid = |e: String| format!("Invalid Cargo.toml at {:?}:\n{}", cargo_path, e.trim_end());
+    let mut table: toml::Table = toml::from_str(&cargo_content).map_err(|e| invalid(e.to_string()))?;
+    let read = |path: &Path| match snapshot {
+        Some(snapshot) => snapshot.read(path),
+        None => fs::read_to_string(path).ok(),
+    };
+    let manifest: Manifest = if workspace::inherit(&mut table, cargo_path, &read).map_err(invalid)? {
+        diagnostics::debug(format!("Resolved the fields {:?} inherits from its workspace", cargo_path));
+        toml::Value::Table(table).try_into().map_err(|e: toml::de::Error| invalid(e.to_string()))?
+    } else {
+        // Parsed again from the text so that errors point at their line
+        toml::from_str(&cargo_content).map_err(|e| invalid(e.to_string()))?
+    };
+    for key in &manifest.package.metadata.cite.ignored {
+        diagnostics::warning_in(Code::InvalidConfig, cargo_path, format!("Ignoring unknown key `{}` in [package.metadata.cite].", key));
+    }
+    Ok(manifest)
+}
+
+impl Manifest {
+    /// Read and parse the Cargo.toml at `path`.
+    pub fn from_path(path: &Path) -> Result<Manifest, String> {
+        read_manifest(path, None)
+    }
+}
+
+fn citation_file_name(opt: &CitationOptions, formatter: &dyn Formatter) -> String {
+    match (&opt.filename, formatter.file_name()) {
+        (Some(o), _) => o.clone(),
+        (None, Some(name)) => name.to_string(),
+        (None, None) => format!("{}.{}", CITATION_FILE, formatter.extension()),
+    }
+}
+
+/// Release date, and with biblatex URL access date, that a citation file
+/// records for an entry.
+struct RecordedDate {
+    /// Citation key, absent in a CITATION.cff, which only records the
+    /// package
+    key: Option<String>,
+    version: Option<String>,
+    date: NaiveDate,
+    accessed: Option<NaiveDate>,
+}
+
+/// The dates recorded in the citation file at `path`, if it exists.
+fn recorded_dates(path: &Path) -> Vec<RecordedDate> {
+    let Ok(existing) = fs::read_to_string(path) else { return Vec::new() };
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("cff") => {
+            let Ok(cff) = serde_yaml::from_str::<serde_yaml::Value>(&existing) else { return Vec::new() };
+            let version = match cff.get("version") {
+                Some(serde_yaml::Value::String(v)) => Some(v.clone()),
+                Some(serde_yaml::Value::Number(v)) => Some(v.to_string()),
+                _ => None,
+            };
+            let date = cff.get("date-released").and_then(|d| d.as_str()).and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            date.map(|date| RecordedDate { key: None, version, date, accessed: None }).into_iter().collect()
+        }
+        _ => bibtex::parse_all(&existing).0.iter()
+            .filter_map(|entry| Some(RecordedDate {
+                key: Some(entry.key.to_string()),
+                version: entry.field("version").map(str::to_string),
+                date: bibtex::entry_date(entry)?,
+                accessed: entry.field("urldate").and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok()),
+            }))
+            .collect(),
+    }
+}
+
+/// Keep the dates the citation file at `path` records for the entries whose
+/// version has not changed, so that regenerating it neither moves them to
+/// today nor misdates the release, along with the date their URL was
+/// accessed. Entries are matched by key in a BibTeX file; a CITATION.cff
+/// records the package's release date.
+fn keep_dates(path: &Path, package: Option<&mut CitationEntry>, dependencies: &mut [CitationEntry]) {
+    let recorded = recorded_dates(path);
+    if recorded.is_empty() {
+        return;
+    }
+    let is_package = package.is_some();
+    for (i, entry) in package.into_iter().chain(dependencies.iter_mut()).enumerate() {
+        let recorded = recorded.iter().find(|recorded| {
+            let same_entry = match &recorded.key {
+                Some(key) => key.eq_ignore_ascii_case(&entry.key),
+                None => is_package && i == 0,
+            };
+            same_entry && recorded.version.as_deref() == entry.version.as_deref()
+        });
+        if let Some(recorded) = recorded {
+            diagnostics::debug(format!("Keeping the date {} recorded for {} in {:?}", recorded.date, entry.key, path));
+            entry.date = Some(recorded.date);
+            entry.accessed = recorded.accessed;
+        }
+    }
+}
+
+/// Date the citation file at `path` records for the current version of
+/// `package`, if any: that of its first entry of this version, since the
+/// key may depend on the date.
+fn recorded_package_date(path: &Path, package: &PackageInfo) -> Option<NaiveDate> {
+    recorded_dates(path).into_iter()
+        .find(|recorded| recorded.version.as_deref() == Some(package.version.as_str()))
+        .map(|recorded| recorded.date)
+}
+
+/// The content to write to the citation file at `path` instead of the
+/// generated `content`: merged into the existing file with `--merge`, or
+/// that file with its version fields updated with `--update`, followed by a
+/// fingerprint when the format has comments. A file edited by hand since
+/// cargo-cite generated it is only overwritten with `--force`, and one that
+/// cannot be parsed is not merged into, so that no entry is lost.
+fn citation_content(path: &Path, content: String, formatter: &dyn Formatter, opt: &CitationOptions) -> Result<String, Error> {
+    let content = match fs::read_to_string(path) {
+        Ok(existing) if fingerprint::edited(&existing) && opt.overwrite && !(opt.force || opt.merge || opt.update) => {
+            return Err(Error::new(Code::EditedByHand, format!("{:?} was edited by hand since cargo-cite generated it.\nUse --force to overwrite the edits, or --merge to keep them.", path)));
+        }
+        Ok(existing) if opt.merge || opt.update => merge_into(path, fingerprint::strip(&existing).0, &content, opt)
+            .map_err(|e| Error::new(Code::ProcessingFailed, e))?,
+        _ => content,
+    };
+    Ok(match formatter.comment(&fingerprint::text(&content)) {
+        Some(line) => format!("{}{}\n", content, line),
+        None => content,
+    })
+}
+
+/// `content` merged into `existing`, the BibTeX file at `path`, whose
+/// entries cargo-cite does not generate are kept with `--merge`; with
+/// `--update`, `existing` with only the version fields of its entries (and
+/// the dates, with `--update-dates`) taken from `content`.
+fn merge_into(path: &Path, existing: &str, content: &str, opt: &CitationOptions) -> Result<String, String> {
+    let malformed = |e| format!("Could not {} {:?}, which is malformed at {}", if opt.merge { "merge into" } else { "update" }, path, e);
+    if opt.merge {
+        return bibtex::merge(existing, content).map_err(malformed);
+    }
+    let fields: &[&str] = match opt.update_dates {
+        true => &["version", "year", "month", "date"],
+        false => &["version"],
+    };
+    let (updated, changed) = bibtex::update(existing, content, fields).map_err(malformed)?;
+    diagnostics::verbose(format!("Updated {} field{} in {:?}", changed, if changed == 1 { "" } else { "s" }, path));
+    Ok(updated)
+}
+
+/// The crate a subcommand cites, and the configuration it is cited with.
+struct Subject {
+    config: Config,
+    manifest: Manifest,
+    cargo_path: PathBuf,
+    package: CitationEntry,
+}
+
+/// The citation of the crate in `crate_dir`, configured and dated as in its
+/// citation file: the release date that file records for the version, else
+/// the one `package_date` finds.
+async fn package_citation(crate_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<Subject, Error> {
+    let config = load_config(crate_dir, options)?;
+    let cargo_path = crate_dir.join(CARGO_FILE);
+    let mut manifest = read_manifest(&cargo_path, None)
+        .map_err(|e| Error::new(Code::InvalidManifest, e))?;
+    manifest.package.apply_config(&config.package);
+    let citation_path = crate_dir.join(citation_file_name(options, options.formatter()?.as_ref()));
+    let date = match recorded_package_date(&citation_path, &manifest.package) {
+        Some(date) => date,
+        None => package_date(fetcher, &manifest.package, &cargo_path, None).await,
+    };
+    let key = manifest.package.citation_key(options, date);
+    let package = CitationEntry::from_package(&manifest.package, &key, cargo_path.clone(), date);
+    Ok(Subject { config, manifest, cargo_path, package })
+}
+
+/// The citation of the crate in `crate_dir`, as `package_citation` gives
+/// it, and those of its dependencies, keyed uniquely and sorted as in the
+/// dependencies file.
+async fn package_and_dependencies(crate_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<(Subject, Vec<CitationEntry>), Error> {
+    let subject = package_citation(crate_dir, options, fetcher).await?;
+    let filter = options.dependency_filter(&subject.config.dependencies)?;
+    let mut dependencies: Vec<CitationEntry> = dependencies_of(&subject.manifest, &subject.cargo_path, options, &filter, &subject.config.overrides, fetcher, None).await
+        .iter()
+        .map(CitationEntry::from_dependency)
+        .collect();
+    entry::unique_keys(Some(&subject.package), &mut dependencies);
+    entry::sort(&mut dependencies, options.sort.unwrap_or_default());
+    Ok((subject, dependencies))
+}
+
+/// Check that the README section of the crate in `crate_dir` matches its
+/// citation file. Returns whether they agree.
+pub async fn verify_readme(crate_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<bool, Error> {
+    let formatter = options.formatter()?;
+    let Subject { config, package, .. } = package_citation(crate_dir, options, fetcher).await?;
+
+    let readme_path = readme::find_readme(crate_dir)
+        .ok_or_else(|| Error::new(Code::ReadmeDrift, format!("No README found in {:?}.", crate_dir)))?;
+    let readme_content = fs::read_to_string(&readme_path)
+        .map_err(|e| Error::new(Code::ReadmeDrift, format!("Could not read {:?}: {}", readme_path, e)))?;
+    let citation_path = crate_dir.join(citation_file_name(options, formatter.as_ref()));
+    let citation = fs::read_to_string(&citation_path).ok()
+        .map(|citation| fingerprint::strip(&citation).0.to_string());
+
+    let expected = readme::section(&package, config.readme.template.as_deref(), &readme_path, &citation_file_name(options, formatter.as_ref()), citation.as_deref())
+        .map_err(|e| Error::new(Code::ReadmeDrift, e))?;
+    let problems = readme::verify(&readme_content, &expected, citation.as_deref());
+    if problems.is_empty() {
+        diagnostics::info(format!("README {:?} is consistent with {:?}", readme_path, citation_path));
+        return Ok(true);
+    }
+    diagnostics::error(Code::ReadmeDrift, format!("README {:?} has drifted from {:?}:\n- {}", readme_path, citation_path, problems.join("\n- ")));
+    Ok(false)
+}
+
+/// Check the citation files of the crate in `crate_dir` for problems: the
+/// file given with `--filename`, else whichever of CITATION.bib,
+/// CITATION.cff and DEPENDENCIES.bib exist.
+pub fn lint_citations(crate_dir: &Path, options: &CitationOptions) -> Result<lint::LintReport, Error> {
+    let config = load_config(crate_dir, options)?;
+    let cargo_path = crate_dir.join(CARGO_FILE);
+    let mut manifest = read_manifest(&cargo_path, None)
+        .map_err(|e| Error::new(Code::InvalidManifest, e))?;
+    manifest.package.apply_config(&config.package);
+
+    let files: Vec<PathBuf> = match &options.filename {
+        Some(name) => vec![crate_dir.join(name)],
+        None => [format!("{}.bib", CITATION_FILE), format!("{}.cff", CITATION_FILE), format!("{}.bib", DEPENDENCIES_FILE)]
+            .iter()
+            .map(|name| crate_dir.join(name))
+            .filter(|path| path.is_file())
+            .collect(),
+    };
+    if files.is_empty() {
+        return Err(Error::new(Code::LintFailed, format!("No citation file found in {:?}.", crate_dir)));
+    }
+    let requirements = manifest.declared(&[DependencyKind::Normal, DependencyKind::Dev, DependencyKind::Build])
+        .iter()
+        .filter_map(|dep| {
+            let req = VersionReq::parse(&dep.info.get_version()?).ok()?;
+            Some((dep.crate_name().to_string(), req))
+        })
+        .collect();
+    let expected = lint::Expected {
+        package: manifest.package.name.clone(),
+        version: manifest.package.version.clone(),
+        requirements,
+    };
+    Ok(lint::LintReport::collect(&files, &expected))
+}
+
+/// Install a git pre-commit hook in the repository containing `crate_dir`
+/// that runs `cargo cite --check` with `args`, the options the citation
+/// files are generated with. Returns the hook's path.
+pub fn install_hook(crate_dir: &Path, args: &[String], options: &CitationOptions) -> Result<PathBuf, Error> {
+    git_hook::install(crate_dir, args, options.overwrite)
+}
+
+/// Deposit the current release of the crate in `crate_dir` on the archive
+/// selected in `options`, returning the DOI minted or reserved for it.
+pub async fn deposit(crate_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<String, Error> {
+    if !options.zenodo {
+        return Err(Error::new(Code::InvalidCommand, "deposit needs an archive to deposit to: --zenodo."));
+    }
+    let subject = package_citation(crate_dir, options, fetcher).await?;
+    zenodo::deposit(fetcher, crate_dir, &subject.package, options).await
+}
+
+/// Write the start of a JOSS paper for the crate in `crate_dir` to its
+/// `paper/` directory: `paper.bib` citing the crate and its dependencies, and
+/// a `paper.md` skeleton citing them. Existing files are kept unless
+/// `--overwrite` is given. Returns the files written.
+pub async fn joss(crate_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<Vec<PathBuf>, Error> {
+    let (Subject { mut package, .. }, dependencies) = package_and_dependencies(crate_dir, options, fetcher).await?;
+    if let Some(doi) = package.doi.as_deref().filter(|_| !options.no_doi_bibtex && !options.offline) {
+        package.preferred_bibtex = archive::doi_entry(fetcher, doi, &package.key).await;
+    }
+
+    let dir = crate_dir.join(joss::PAPER_DIR);
+    if !options.dry_run && !options.check {
+        fs::create_dir_all(&dir)
+            .map_err(|e| Error::new(Code::ProcessingFailed, format!("Could not create {:?}: {}", dir, e)))?;
+    }
+    let bib = options.bibtex()?.document(Some(&package), &dependencies)
+        .map_err(|e| Error::new(Code::FormatterFailed, e))?;
+    let mut written = Vec::new();
+    for (name, content) in [(joss::PAPER_BIB, bib), (joss::PAPER_MD, joss::paper(&package, &dependencies))] {
+        let path = dir.join(name);
+        let outcome = output::write(&path, &content, name, options.overwrite, options)
+            .map_err(|e| Error::new(Code::ProcessingFailed, format!("Could not write {:?}: {}", path, e)))?;
+        if outcome == Outcome::Written {
+            diagnostics::info(format!("Created {:?}", path));
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+/// The citation of the crates.io crate `spec`, `name` or `name@version`,
+/// in the selected format, without a manifest to declare it. The newest
+/// release matching the version is cited, as with a dependency; the
+/// configuration in `dir` provides its overrides.
+pub async fn cite_crate(spec: &str, dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<String, Error> {
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (spec, None),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(Error::new(Code::InvalidCommand, format!("Invalid crate name {:?}: expected <name> or <name>@<version>.", name)));
+    }
+    // An exact version is cited as given, a requirement at its newest release
+    let requirement = match version {
+        None => String::from("*"),
+        Some(version) if Version::parse(version).is_ok() => format!("={}", version),
+        Some(version) if VersionReq::parse(version).is_ok() => version.to_string(),
+        Some(version) => return Err(Error::new(Code::InvalidCommand, format!("Invalid version {:?} of {}.", version, name))),
+    };
+    let config = load_config(dir, options)?;
+    let info = DependencyInfo::Simple(requirement.clone());
+    let declared = Declared { name, kind: DependencyKind::Normal, target: None, info: &info };
+    let mut dep = resolve_dependency(&declared, fetcher, Path::new(CARGO_FILE), None, &BTreeMap::new(), options).await;
+    if dep.metadata.is_none() || dep.version.as_deref().is_none_or(|v| Version::parse(v).is_err()) {
+        return Err(Error::new(Code::FetchFailed, format!("Could not find a release of {} matching {} on crates.io.", name, requirement)));
+    }
+    if let Some(correction) = config.overrides.get(name) {
+        dep.apply_override(correction.clone());
+    }
+    let formatter = options.formatter()?;
+    if formatter.renders_bibtex() && !options.no_doi_bibtex && !options.offline && dep.preferred_entry.is_none() {
+        if let Some(doi) = dep.doi.clone() {
+            dep.preferred_entry = archive::doi_entry(fetcher, &doi, &dep.key).await;
+        }
+    }
+    formatter.document(None, &[CitationEntry::from_dependency(&dep)])
+        .map_err(|e| Error::new(Code::FormatterFailed, e))
+}
+
+/// Write a NOTICE file crediting the dependencies of the crate in
+/// `crate_dir`, named by `--filename` if given. Returns the files written.
+pub async fn notice(crate_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<Vec<PathBuf>, Error> {
+    let (Subject { package, .. }, dependencies) = package_and_dependencies(crate_dir, options, fetcher).await?;
+
+    let path = crate_dir.join(options.filename.as_deref().unwrap_or(notice::NOTICE_FILE));
+    let content = notice::document(&package, &dependencies, options.style.unwrap_or(Style::Apa));
+    let outcome = output::write(&path, &content, "NOTICE file", options.overwrite, options)
+        .map_err(|e| Error::new(Code::ProcessingFailed, format!("Could not write {:?}: {}", path, e)))?;
+    if outcome == Outcome::Written {
+        diagnostics::info(format!("Created {:?}", path));
+        return Ok(vec![path]);
+    }
+    Ok(Vec::new())
+}
+
+/// Write a chapter citing the crate in `crate_dir` and its dependencies into
+/// the mdBook in `book_dir`, and link it from the book's SUMMARY.md.
+/// Returns the files written.
+pub async fn mdbook(crate_dir: &Path, book_dir: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<Vec<PathBuf>, Error> {
+    let src = mdbook::src_dir(book_dir).map_err(|e| Error::new(Code::InvalidCommand, e))?;
+    let (Subject { package, .. }, dependencies) = package_and_dependencies(crate_dir, options, fetcher).await?;
+
+    let chapter = formats::markdown::Markdown.document(Some(&package), &dependencies)
+        .map_err(|e| Error::new(Code::FormatterFailed, e))?;
+    let summary_path = src.join(mdbook::SUMMARY);
+    let summary = fs::read_to_string(&summary_path)
+        .map_err(|e| Error::new(Code::InvalidCommand, format!("Could not read {:?}: {}", summary_path, e)))?;
+    let mut files = vec![(src.join(mdbook::CHAPTER), chapter, "mdBook chapter", options.overwrite)];
+    if let Some(summary) = mdbook::link_chapter(&summary) {
+        files.push((summary_path, summary, "mdBook summary", true));
+    }
+    let mut written = Vec::new();
+    for (path, content, what, replace) in files {
+        let outcome = output::write(&path, &content, what, replace, options)
+            .map_err(|e| Error::new(Code::ProcessingFailed, format!("Could not write {:?}: {}", path, e)))?;
+        if outcome == Outcome::Written {
+            diagnostics::info(format!("Wrote the {} at {:?}", what, path));
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+/// Replace the names of `entry`'s authors that have an ORCID iD by the name
+/// on their ORCID record, keeping their email addresses.
+async fn resolve_orcid_names(fetcher: &MetadataFetcher, entry: &mut CitationEntry) {
+    for author in entry.authors.iter_mut() {
+        let name = person_name(author).to_string();
+        let Some(orcid) = entry.orcid.get(&name).cloned() else { continue };
+        match fetcher.orcid_name(&orcid).await {
+            Ok(Some(canonical)) if canonical != name => {
+                *author = author.replacen(&name, &canonical, 1);
+                entry.orcid.remove(&name);
+                entry.orcid.insert(canonical, orcid);
+            }
+            Ok(_) => {}
+            Err(e) => diagnostics::warning(Code::FetchFailed, format!("Could not fetch the ORCID record of {}: {}", name, e)),
+        }
+    }
+}
+
+/// Date of the package citation: that of the `--as-of` snapshot, otherwise
+/// the release of its version on crates.io or its git tag. Unreleased
+/// versions fall back to the repository creation, then to the last commit
+/// or change of the crate. Crates not published to crates.io are not looked
+/// up there, where another crate may have the name.
+async fn package_date(fetcher: &MetadataFetcher, package: &PackageInfo, cargo_path: &Path, snapshot: Option<&Snapshot>) -> NaiveDate {
+    if let Some(snapshot) = snapshot {
+        return snapshot.date;
+    }
+    if package.publishes_to_crates_io() {
+        if let Some(date) = fetcher.release_date(None, &package.name, &package.version).await {
+            return date;
+        }
+    }
+    let dir = start_dir(cargo_path);
+    if let Some(date) = history::tag_date(&dir, &package.name, &package.version) {
+        return date;
+    }
+    if let Some(date) = fetcher.fallback_date(package.repository.as_deref(), None).await {
+        return date;
+    }
+    history::last_commit_date(&dir)
+        .or_else(|| {
+            let modified = fs::metadata(cargo_path).and_then(|m| m.modified()).ok()?;
+            Some(chrono::DateTime::<chrono::Local>::from(modified).date_naive())
+        })
+        .unwrap_or_else(today)
+}
+
+/// The dependencies of `manifest` selected by `--source`, `--dev-deps`,
+/// `--build-deps`, `--include-internal` and `filter`, pinned to the
+/// `--as-of` snapshot if there is one. With `--transitive`, those of the
+/// whole dependency graph. With feature flags, only those cargo's resolver
+/// enables.
+async fn dependencies_of(manifest: &Manifest, cargo_path: &Path, opt: &CitationOptions, filter: &DependencyFilter, overrides: &BTreeMap<String, DependencyOverride>, fetcher: &MetadataFetcher, snapshot: Option<&Snapshot>) -> Vec<ResolvedDependency> {
+    let kinds = opt.dependency_kinds();
+    let features = opt.features();
+    let transitive = if opt.transitive {
+        match graph::transitive_dependencies(cargo_path, &kinds, &features) {
+            Ok(deps) => Some(deps),
+            Err(e) => {
+                diagnostics::warning(Code::DependencyGraph, format!("Could not resolve the dependency graph of {:?}: {}\nCiting its direct dependencies only.", cargo_path, e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let enabled = if features.is_selected() && !opt.transitive {
+        match graph::enabled_dependencies(cargo_path, &features) {
+            Ok(names) => Some(names),
+            Err(e) => {
+                diagnostics::warning(Code::DependencyGraph, format!("Could not resolve the features of {:?}: {}\nCiting every declared dependency.", cargo_path, e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // The lockfile is read after `cargo metadata`, which creates it when missing
+    let read = |path: &Path| match snapshot {
+        Some(snapshot) => snapshot.read(path),
+        None => fs::read_to_string(path).ok(),
+    };
+    let lockfile = Lockfile::find(cargo_path, &read);
+    diagnostics::debug(match &lockfile {
+        Some(lockfile) => format!("Read {} locked packages from Cargo.lock", lockfile.package.len()),
+        None => String::from("No Cargo.lock; citing the newest releases matching the requirements"),
+    });
+    let mut declared: Vec<Declared> = match &transitive {
+        Some(deps) => deps.iter()
+            .map(|(name, dep)| Declared { name, kind: dep.kind, target: dep.target.as_deref(), info: &dep.info })
+            .collect(),
+        None => {
+            let mut declared = manifest.declared(&kinds);
+            if let Some(enabled) = &enabled {
+                declared.retain(|d| enabled.contains(d.crate_name()));
+            }
+            declared
+        }
+    };
+    // Path dependencies in the same repository are the project's own crates
+    if !opt.include_internal && opt.source != Some(SourceKind::Path) {
+        let dir = start_dir(cargo_path);
+        // Outside a repository, the workspace is the project, else the crate alone
+        let root = history::git(&dir, &["rev-parse", "--show-toplevel"]).ok().map(PathBuf::from)
+            .or_else(|| workspace::root_dir(cargo_path))
+            .unwrap_or_else(|| dir.clone());
+        let before = declared.len();
+        declared.retain(|d| match d.info.get_source_info().0 {
+            Some(path) => !is_internal(&dir.join(path), &root),
+            None => true,
+        });
+        if declared.len() < before {
+            let skipped = before - declared.len();
+            diagnostics::note(format!("Left out {} internal path dependenc{}; pass --include-internal to cite {}.",
+                skipped, if skipped == 1 { "y" } else { "ies" }, if skipped == 1 { "it" } else { "them" }));
+        }
+    }
+    declared.retain(|d| {
+        let included = filter.includes(d.crate_name());
+        if !included {
+            diagnostics::verbose(format!("Leaving out {}, filtered by --exclude or --only", d.crate_name()));
+        }
+        included
+    });
+    let mut deps = resolve_dependencies(&declared, fetcher, cargo_path, lockfile.as_ref(), opt).await;
+    if let Some(snapshot) = snapshot {
+        snapshot.pin_dependencies(fetcher, &mut deps).await;
+    }
+    // Those of cargo-cite.toml win over those of Cargo.toml
+    for dep in &mut deps {
+        if let Some(correction) = overrides.get(&dep.name).or(manifest.package.metadata.cite.overrides.get(&dep.name)) {
+            diagnostics::verbose(format!("Applying the overrides of {}", dep.name));
+            dep.apply_override(correction.clone());
+        }
+    }
+    deps
+}
+
+/// Whether the path dependency in `dir` belongs to the project: it lies
+/// under `root`, the directory of its repository, workspace or crate.
+fn is_internal(dir: &Path, root: &Path) -> bool {
+    // A missing directory is placed by its path alone
+    let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| lexical_absolute(path));
+    absolute(dir).starts_with(absolute(root))
+}
+
+/// `path` made absolute, with `.` and `..` resolved without following
+/// symbolic links, so that it need not exist.
+fn lexical_absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()).components()
+        .fold(PathBuf::new(), |mut path, component| {
+            match component {
+                std::path::Component::ParentDir => { path.pop(); }
+                std::path::Component::CurDir => {}
+                other => path.push(other),
+            }
+            path
+        })
+}
+
+/// The outcome of processing one manifest.
+enum Processed {
+    /// Skipped after a warning or note
+    Skipped,
+    /// Dependencies collected with `--dependencies`, and the name of the
+    /// package declaring them
+    Dependencies(String, Vec<ResolvedDependency>),
+    /// The package citation that was written
+    Package(Box<CitationEntry>),
+    /// Left out when prompted with `--interactive`
+    Declined,
+}
+
+async fn process_cargo_file(cargo_path: &Path, opt: &CitationOptions, formatter: &dyn Formatter, config: &Config, fetcher: &MetadataFetcher, snapshot: Option<&Snapshot>, written: &mut Vec<PathBuf>) -> Result<Processed, Box<dyn std::error::Error>> {
+    diagnostics::info(format!("\nProcessing {:?}", cargo_path));
+    
+    let mut manifest = match read_manifest(cargo_path, snapshot) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            diagnostics::warning_in(Code::InvalidManifest, cargo_path, format!("{}\nSkipping this file.", e));
+            return Ok(Processed::Skipped);
+        }
+    };
+
+    if opt.interactive {
+        let question = match opt.dependencies {
+            true => format!("Cite the dependencies of {}?", manifest.package.name),
+            false => format!("Generate a citation for {} {}?", manifest.package.name, manifest.package.version),
+        };
+        if !interactive::confirm(&question, true) {
+            return Ok(Processed::Declined);
+        }
+    }
+
+    if opt.dependencies {
+        let deps = dependencies_of(&manifest, cargo_path, opt, &opt.dependency_filter(&config.dependencies)?, &config.overrides, fetcher, snapshot).await;
+        return Ok(Processed::Dependencies(manifest.package.name, deps));
+    }
+
+    manifest.package.apply_config(&package_config(config, cargo_path)?);
+    if opt.interactive {
+        manifest.package.authors = interactive::edit_list("Authors", &manifest.package.authors);
+        let description = interactive::edit("Description, cited in the title", manifest.package.description.as_deref().unwrap_or_default());
+        manifest.package.description = Some(description).filter(|d| !d.is_empty());
+    }
+    if manifest.package.authors.is_empty() {
+        diagnostics::warning_in(Code::MissingAuthors, cargo_path, format!("Package {} has no authors; its citation will have an empty author field.", manifest.package.name));
+    }
+
+    let date = package_date(fetcher, &manifest.package, cargo_path, snapshot).await;
+    let key = manifest.package.citation_key(opt, date);
+    let mut package = CitationEntry::from_package(&manifest.package, &key, cargo_path.to_path_buf(), date);
+    if opt.resolve_orcid {
+        resolve_orcid_names(fetcher, &mut package).await;
+    }
+    if let Some(repository) = manifest.package.repository.as_deref().filter(|_| opt.swhid) {
+        package.swhid = archive::archived_swhid(fetcher, &manifest.package.name, repository).await;
+    }
+    if formatter.renders_bibtex() && !opt.no_doi_bibtex && !opt.offline {
+        if let Some(doi) = &package.doi {
+            package.preferred_bibtex = archive::doi_entry(fetcher, doi, &key).await;
+        }
+    }
+    if opt.zenodo_json {
+        let path = cargo_path.parent().unwrap().join(zenodo::ZENODO_JSON);
+        let json = format!("{}\n", serde_json::to_string_pretty(&ZenodoMetadata::from_entry(&package))?);
+        if output::write(&path, &json, "Zenodo metadata file", opt.overwrite, opt)? == Outcome::Written {
+            diagnostics::info(format!("Created Zenodo metadata file at {:?}", path));
+            written.push(path);
+        }
+    }
+    let mut dependencies: Vec<CitationEntry> = if formatter.includes_dependencies() {
+        dependencies_of(&manifest, cargo_path, opt, &opt.dependency_filter(&config.dependencies)?, &config.overrides, fetcher, snapshot).await.iter().map(CitationEntry::from_dependency).collect()
+    } else {
+        Vec::new()
+    };
+    entry::unique_keys(Some(&package), &mut dependencies);
+    entry::sort(&mut dependencies, opt.sort.unwrap_or_default());
+    let output_file = citation_file_name(opt, formatter);
+    let file_path = cargo_path.parent().unwrap().join(PathBuf::from(&output_file));
+    keep_dates(&file_path, Some(&mut package), &mut dependencies);
+    let r = match formatter.document(Some(&package), &dependencies) {
+        Ok(r) => r,
+        Err(e) => {
+            diagnostics::warning(Code::FormatterFailed, format!("{}\nSkipping this file.", e));
+            return Ok(Processed::Skipped);
+        }
+    };
+    diagnostics::entry(&key, EntryKind::Package, cargo_path, &r);
+
+    let content = match citation_content(&file_path, r.clone(), formatter, opt) {
+        Ok(content) => content,
+        Err(e) => {
+            diagnostics::warning_in(e.code, &file_path, format!("{}\nSkipping this file.", e.message));
+            return Ok(Processed::Skipped);
+        }
+    };
+    let replace = opt.overwrite || opt.merge || opt.update
+        || (opt.interactive && !opt.check && file_path.exists() && interactive::confirm(&format!("Overwrite {:?}?", file_path), false));
+    let outcome = output::write(&file_path, &content, "citation file", replace, opt)?;
+    if outcome == Outcome::Written {
+        diagnostics::info(format!("Created citation file at {:?}", file_path));
+        written.push(file_path);
+    }
+
+    if (opt.readme_append || opt.badge || opt.rustdoc || opt.interactive) && !opt.check {
+        // The citation file itself when it holds only this entry, so that
+        // `cargo cite verify-readme` finds them identical
+        let bibtex = match formatter.renders_bibtex() && dependencies.is_empty() {
+            true => Some(r.clone()),
+            false => opt.bibtex()?.document(Some(&package), &[]).ok(),
+        };
+        let dir = cargo_path.parent().unwrap();
+        if opt.readme_append || opt.badge || opt.interactive {
+            update_readme(dir, &package, config, opt, &output_file, bibtex.as_deref())?;
+        }
+        if opt.rustdoc {
+            update_rustdoc(dir, &package, config, opt, &output_file, bibtex.as_deref())?;
+        }
+    }
+
+    if outcome == Outcome::Kept {
+        return Ok(Processed::Skipped);
+    }
+    Ok(Processed::Package(Box::new(package)))
+}
+
+/// Add the "Citing" section and the badge selected by `opt` to the README
+/// in `dir`, asking first with `--interactive`. `bibtex` is the entry of
+/// `package` embedded in the section.
+fn update_readme(dir: &Path, package: &CitationEntry, config: &Config, opt: &CitationOptions, citation_file: &str, bibtex: Option<&str>) -> std::io::Result<()> {
+    let Some(path) = readme::find_section_readme(dir) else {
+        diagnostics::note(format!("No README.md or README.rst in {:?} to add a \"Citing\" section to.", dir));
+        return Ok(());
+    };
+    let wanted = |requested: bool, question: String| match opt.interactive {
+        true => interactive::confirm(&question, requested),
+        false => requested,
+    };
+    let mut updated = fs::read_to_string(&path)?;
+    if wanted(opt.readme_append, format!("Add or update the \"Citing\" section of {:?}?", path)) {
+        match readme::section(package, config.readme.template.as_deref(), &path, citation_file, bibtex) {
+            Ok(section) => updated = readme::insert_section(&updated, &path, &section),
+            Err(e) => diagnostics::warning(Code::InvalidConfig, format!("{}\nSkipping the \"Citing\" section.", e)),
+        }
+    }
+    if wanted(opt.badge, format!("Add or update the citation badge of {:?}?", path)) {
+        updated = readme::insert_badge(&updated, &path, &readme::badge(package, &path, citation_file));
+    }
+    if output::write(&path, &updated, "README", true, opt)? == Outcome::Written {
+        diagnostics::info(format!("Updated the citation details in {:?}", path));
+    }
+    Ok(())
+}
+
+/// Add the "Citing" section to the crate-level documentation of the crate
+/// in `dir`: to the `//!` comments of src/lib.rs, or to the file it includes
+/// as its documentation.
+fn update_rustdoc(dir: &Path, package: &CitationEntry, config: &Config, opt: &CitationOptions, citation_file: &str, bibtex: Option<&str>) -> std::io::Result<()> {
+    let root = dir.join(rustdoc::LIB_FILE);
+    let Ok(source) = fs::read_to_string(&root) else {
+        diagnostics::note(format!("No {} in {:?} to document how to cite the crate in.", rustdoc::LIB_FILE, dir));
+        return Ok(());
+    };
+    if opt.interactive && !interactive::confirm(&format!("Add or update the \"Citing\" section of the documentation in {:?}?", root), true) {
+        return Ok(());
+    }
+    let (path, updated) = match rustdoc::included_doc(&source, &root) {
+        Some(path) => {
+            let section = readme::section(package, config.readme.template.as_deref(), &path, citation_file, bibtex);
+            let included = fs::read_to_string(&path)?;
+            (path.clone(), section.map(|section| readme::insert_section(&included, &path, &section)))
+        }
+        // Markdown, linking to the files next to the manifest
+        None => {
+            let section = readme::section(package, config.readme.template.as_deref(), &dir.join(CARGO_FILE), citation_file, bibtex);
+            (root, section.map(|section| rustdoc::insert_section(&source, &section)))
+        }
+    };
+    match updated {
+        Ok(updated) => {
+            if output::write(&path, &updated, "crate documentation", true, opt)? == Outcome::Written {
+                diagnostics::info(format!("Wrote the \"Citing\" section of the documentation in {:?}", path));
+            }
+        }
+        Err(e) => diagnostics::warning(Code::InvalidConfig, format!("{}\nSkipping the crate documentation.", e)),
+    }
+    Ok(())
+}
+
+impl CitationOptions {
+    /// The dependency kinds selected by `dev_deps` and `build_deps`, normal
+    /// dependencies first.
+    pub fn dependency_kinds(&self) -> Vec<DependencyKind> {
+        let mut kinds = vec![DependencyKind::Normal];
+        if self.build_deps {
+            kinds.push(DependencyKind::Build);
+        }
+        if self.dev_deps {
+            kinds.push(DependencyKind::Dev);
+        }
+        kinds
+    }
+
+    /// The crates cited according to `--exclude` and `--only`, together with
+    /// the patterns of the `[dependencies]` configuration.
+    pub fn dependency_filter(&self, config: &DependenciesConfig) -> Result<DependencyFilter, Error> {
+        let patterns = |cli: &[String], configured: &[String]| {
+            cli.iter().chain(configured)
+                .map(|glob| glob::Pattern::new(glob)
+                    .map_err(|e| Error::new(Code::InvalidCommand, format!("Invalid crate name pattern {:?}: {}", glob, e))))
+                .collect::<Result<Vec<_>, Error>>()
+        };
+        Ok(DependencyFilter {
+            exclude: patterns(&self.exclude, &config.exclude)?,
+            only: patterns(&self.only, &config.only)?,
+        })
+    }
+
+    /// The feature flags passed on to cargo's resolver.
+    fn features(&self) -> graph::Features<'_> {
+        graph::Features {
+            features: &self.features,
+            all_features: self.all_features,
+            no_default_features: self.no_default_features,
+        }
+    }
+
+    /// The formatter selected by `template`, `format` and `style`.
+    pub fn formatter(&self) -> Result<Box<dyn Formatter>, Error> {
+        let formatter: Box<dyn Formatter> = match &self.template {
+            Some(_) if self.format.is_some() || self.style.is_some() => {
+                return Err(Error::new(Code::InvalidCommand, "--template cannot be used with --format or --style."));
+            }
+            Some(template) => Box::new(formats::template::Template::load(Path::new(template))
+                .map_err(|e| Error::new(Code::InvalidCommand, e))?),
+            None => formats::select(self.format.as_deref(), self.style, self.bibtex()?, self.embed_bibtex)
+                .map_err(|e| Error::new(Code::InvalidCommand, e))?,
+        };
+        if self.merge && self.update {
+            return Err(Error::new(Code::InvalidCommand, "--merge and --update cannot be used together."));
+        }
+        if self.update_dates && !self.update {
+            return Err(Error::new(Code::InvalidCommand, "--update-dates needs --update."));
+        }
+        if self.split && self.group_by.is_none() {
+            return Err(Error::new(Code::InvalidCommand, "--split needs --group-by."));
+        }
+        if self.group_by.is_some() && !self.split && formatter.extension() != "bib" {
+            return Err(Error::new(Code::InvalidCommand, "--group-by writes sections in BibTeX files only; add --split to write each group to its own file."));
+        }
+        if (self.merge || self.update) && formatter.extension() != "bib" {
+            return Err(Error::new(Code::InvalidCommand, format!("--{} only applies to BibTeX citation files.", if self.merge { "merge" } else { "update" })));
+        }
+        Ok(formatter)
+    }
+
+    /// The BibTeX conventions selected by `entry_type`, `biblatex`,
+    /// `no_latex_escape` and `month_format`, and the layout selected by
+    /// `indent`, `align_fields`, `field_order` and `no_trailing_comma`.
+    pub fn bibtex(&self) -> Result<Bibtex, Error> {
+        if let Some(entry_type) = self.entry_type.as_deref().filter(|t| t.is_empty() || !t.chars().all(|c| c.is_ascii_alphanumeric())) {
+            return Err(Error::new(Code::InvalidCommand, format!("Invalid entry type {:?}: expected a BibTeX entry type such as \"software\".", entry_type)));
+        }
+        Ok(Bibtex {
+            entry_type: self.entry_type.clone(),
+            biblatex: self.biblatex,
+            no_escape: self.no_latex_escape,
+            month_format: self.month_format.unwrap_or_default(),
+            layout: Layout {
+                indent: self.indent.unwrap_or_default(),
+                align: self.align_fields,
+                order: self.field_order.iter()
+                    .flat_map(|order| order.split(','))
+                    .map(|field| field.trim().to_lowercase())
+                    .filter(|field| !field.is_empty())
+                    .collect(),
+                no_trailing_comma: self.no_trailing_comma,
+            },
+        })
+    }
+
+    /// The response cache selected by `no_cache`, `refresh` and `cache_ttl`.
+    pub fn cache(&self) -> Option<Cache> {
+        if self.no_cache {
+            return None;
+        }
+        let ttl = self.cache_ttl.map_or(cache::DEFAULT_TTL, |hours| Duration::from_secs(hours * 60 * 60));
+        fetch::cache_dir().map(|dir| Cache::new(dir, ttl, self.refresh))
+    }
+
+    /// The verbosity given by `--quiet` and `--verbose`.
+    pub fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// A fetcher using the cache, offline with `offline`, sending requests as
+    /// configured by `timeout`, `proxy` and `registry_api_url`.
+    pub fn fetcher(&self) -> Result<MetadataFetcher, Error> {
+        let invalid = |e: String| Error::new(Code::InvalidCommand, e);
+        let mut client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS)));
+        if let Some(proxy) = &self.proxy {
+            client = client.proxy(reqwest::Proxy::all(proxy).map_err(|e| invalid(format!("Invalid proxy {:?}: {}", proxy, e)))?);
+        }
+        let client = client.build().map_err(|e| invalid(e.to_string()))?;
+        let credentials = Credentials::load().map_err(|e| Error::new(Code::InvalidConfig, e))?;
+        let mut fetcher = MetadataFetcher::with_cache(self.cache()).client(client).credentials(credentials);
+        if let Some(url) = &self.registry_api_url {
+            fetcher = fetcher.registry_api(url);
+        }
+        Ok(if self.offline { fetcher.offline() } else { fetcher })
+    }
+}
+
+/// A failure that stops citation generation altogether, with the code it is
+/// reported under.
+#[derive(Debug)]
+pub struct Error {
+    pub code: Code,
+    pub message: String,
+}
+
+impl Error {
+    /// The process exit status for this error: `EXIT_USAGE` for invalid
+    /// arguments, `EXIT_FAILURE` otherwise.
+    pub fn exit_code(&self) -> u8 {
+        match self.code {
+            Code::InvalidCommand => EXIT_USAGE,
+            _ => EXIT_FAILURE,
+        }
+    }
+
+    pub fn new(code: Code, message: impl Into<String>) -> Self {
+        Error { code, message: message.into() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// What a run processed and produced.
+#[derive(Debug, Default)]
+pub struct Generation {
+    /// Manifests processed successfully
+    pub processed: usize,
+    /// Manifests skipped after a warning
+    pub skipped: usize,
+    /// Dependencies collected with `dependencies`
+    pub dependencies: Vec<ResolvedDependency>,
+    /// Combined dependencies citation file, or `STDOUT`
+    pub dependencies_file: PathBuf,
+    /// Citation files written
+    pub written: Vec<PathBuf>,
+    /// Manifests found, including those skipped
+    pub manifests: Vec<PathBuf>,
+    /// Names of the packages whose dependencies were collected, by manifest
+    pub packages: BTreeMap<PathBuf, String>,
+    /// Entries cited: the packages and, once written, the dependencies
+    pub cited: Vec<CitationEntry>,
+}
+
+/// The directory searched for manifests, given a directory or a Cargo.toml.
+fn start_dir(path: &Path) -> PathBuf {
+    if path.is_file() {
+        // A bare `Cargo.toml` has an empty parent
+        match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        }
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// The `[package]` settings for the manifest at `cargo_path`: those of
+/// `config` for the crate in its directory, else those of the member's own
+/// cargo-cite.toml, if any.
+fn package_config(config: &Config, cargo_path: &Path) -> Result<PackageConfig, Error> {
+    let dir = start_dir(cargo_path);
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if canonical(&dir) == canonical(&config.dir) {
+        return Ok(config.package.clone());
+    }
+    let (own, path) = Config::load(None, &dir).map_err(|e| Error::new(Code::InvalidConfig, e))?;
+    if let Some(path) = path {
+        diagnostics::debug(format!("Read the package configuration from {:?}", path));
+    }
+    Ok(own.package)
+}
+
+fn load_config(start_dir: &Path, options: &CitationOptions) -> Result<Config, Error> {
+    let (mut config, path) = Config::load(options.config.as_deref().map(Path::new), start_dir)
+        .map_err(|e| Error::new(Code::InvalidConfig, e))?;
+    if let Some(path) = path {
+        diagnostics::debug(format!("Read the configuration from {:?}", path));
+    }
+    config.dir = start_dir.to_path_buf();
+    if let Some(doi) = &options.doi {
+        config.package.doi = Some(doi.clone());
+    }
+    if let Some(file) = &options.readme_template {
+        let template = fs::read_to_string(file)
+            .map_err(|e| Error::new(Code::InvalidConfig, format!("Could not read README template {:?}: {}", file, e)))?;
+        config.readme.template = Some(template);
+    }
+    Ok(config)
+}
+
+/// The file of dependency group `slug` with `--split`: `slug` appended to
+/// the name of the dependencies file `path`, before its extension.
+fn group_file(path: &Path, slug: &str, extension: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = match name.strip_suffix(&format!(".{}", extension)) {
+        Some(stem) => format!("{}-{}.{}", stem, slug, extension),
+        None => format!("{}-{}", name, slug),
+    };
+    path.with_file_name(name)
+}
+
+/// The dependencies in `groups`, each in a section headed by a comment.
+fn sections(formatter: &dyn Formatter, groups: &[entry::Group]) -> Result<String, String> {
+    groups.iter()
+        .map(|group| {
+            let heading = formatter.comment(&group.title).unwrap_or_default();
+            Ok(format!("{}\n\n{}", heading, formatter.document(None, &group.entries)?))
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(|sections| sections.join(""))
+}
+
+/// Write one citation file at the workspace root in `start_dir` citing every
+/// package in `packages`. It replaces the root package's own citation file,
+/// written earlier in the same run.
+fn write_workspace_citation(start_dir: &Path, options: &CitationOptions, formatter: &dyn Formatter, packages: &[CitationEntry], written: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut packages = packages.to_vec();
+    entry::unique_keys(None, &mut packages);
+    entry::sort(&mut packages, options.sort.unwrap_or_default());
+    let file_path = start_dir.join(citation_file_name(options, formatter));
+    keep_dates(&file_path, None, &mut packages);
+    let content = formatter.document(None, &packages)
+        .map_err(|e| Error::new(Code::FormatterFailed, e))?;
+    let own_file = written.iter().any(|p| p == &file_path);
+    let content = citation_content(&file_path, content, formatter, options)?;
+    let outcome = output::write(&file_path, &content, "workspace citation file", options.overwrite || options.merge || options.update || own_file, options)
+        .map_err(|e| Error::new(Code::ProcessingFailed, format!("Could not write {:?}: {}", file_path, e)))?;
+    if outcome != Outcome::Written {
+        return Ok(());
+    }
+    diagnostics::info(format!("Created workspace citation file at {:?} with {} package{}",
+        file_path,
+        packages.len(),
+        if packages.len() == 1 { "" } else { "s" }
+    ));
+    if !own_file {
+        written.push(file_path);
+    }
+    Ok(())
+}
+
+/// Write the package citations of the manifests at `path` and collect their
+/// dependencies, without writing the dependencies citation file.
+///
+/// `path` is either a Cargo.toml, the only manifest processed, or a directory.
+/// With `dependencies`, every manifest under the directory is processed;
+/// otherwise only the one directly in it.
+pub async fn collect_citations(path: &Path, options: &CitationOptions, fetcher: &MetadataFetcher) -> Result<Generation, Error> {
+    let formatter = options.formatter()?;
+    let start_dir = start_dir(path);
+    if !start_dir.exists() {
+        return Err(Error::new(Code::NoManifest, format!("Directory {:?} does not exist.", start_dir)));
+    }
+    let config = load_config(&start_dir, options)?;
+    // Invalid patterns fail the run rather than each manifest
+    options.dependency_filter(&config.dependencies)?;
+    if let Some(template) = &options.key_template {
+        check_key_template(template)?;
+    }
+    if (options.transitive || options.features().is_selected()) && options.as_of.is_some() {
+        return Err(Error::new(Code::InvalidCommand, "--transitive and feature selection cannot be used with --as-of."));
+    }
+
+    let snapshot = match &options.as_of {
+        Some(as_of) => {
+            let snapshot = Snapshot::resolve(&start_dir, as_of).map_err(|e| Error::new(Code::RevisionNotFound, e))?;
+            diagnostics::info(format!("Reading manifests as of commit {} ({})", snapshot.commit, snapshot.date));
+            Some(snapshot)
+        }
+        None => None,
+    };
+
+    let mut generation = Generation {
+        dependencies_file: start_dir.join(match &options.filename {
+            Some(o) => o.clone(),
+            None => format!("{}.{}", DEPENDENCIES_FILE, formatter.extension()),
+        }),
+        ..Default::default()
+    };
+
+    let cargo_files = if path.is_file() {
+        vec![path.to_path_buf()]
+    } else if options.dependencies {
+        // Only do directory walking for dependencies option
+        diagnostics::info(format!("Searching for Cargo.toml files in {:?}{}",
+            start_dir,
+            match options.max_depth {
+                Some(depth) if depth < 0 => String::from(" and all subdirectories"),
+                Some(0) => String::from(" (current directory only)"),
+                Some(depth) => format!(" (max depth: {})", depth),
+                None => String::from(" (searching all subdirectories)"),
+            }
+        ));
+        find_cargo_files(&start_dir, options.max_depth)
+    } else {
+        // For other operations, just look in the current directory
+        let cargo_path = start_dir.join(CARGO_FILE);
+        if cargo_path.exists() {
+            vec![cargo_path]
+        } else {
+            return Err(Error::new(Code::NoManifest, format!("No Cargo.toml found in {:?}.", start_dir)));
+        }
+    };
+    
+    let workspace = match cargo_files.as_slice() {
+        [root] if !options.dependencies => workspace::read(root),
+        _ => None,
+    };
+    let cargo_files = workspace::expand_manifests(cargo_files);
+
+    if cargo_files.is_empty() {
+        if options.max_depth == Some(0) {
+            diagnostics::info(format!("No Cargo.toml found in {:?}.", start_dir));
+            diagnostics::note("You can use --max-depth N to search subdirectories (N levels deep)\nor --max-depth -1 to search all subdirectories.");
+        } else {
+            diagnostics::info(format!("No Cargo.toml files found in {:?} or its subdirectories{}",
+                start_dir,
+                match options.max_depth {
+                    Some(depth) if depth < 0 => String::new(),
+                    Some(depth) if depth > 0 => format!(" (searched {} level{} deep)", 
+                        depth,
+                        if depth == 1 { "" } else { "s" }
+                    ),
+                    None => String::new(),
+                    _ => String::new(),
+                }
+            ));
+        }
+        return Ok(generation);
+    }
+
+    diagnostics::info(format!("\nFound {} Cargo.toml file{}",
+        cargo_files.len(),
+        if cargo_files.len() == 1 { "" } else { "s" }
+    ));
+
+    let mut packages = Vec::new();
+    let progress = Progress::new(cargo_files.len(), "manifests");
+    generation.manifests = cargo_files.clone();
+    for cargo_path in cargo_files {
+        progress.start();
+        match process_cargo_file(&cargo_path, options, formatter.as_ref(), &config, fetcher, snapshot.as_ref(), &mut generation.written).await {
+            Ok(Processed::Skipped) => generation.skipped += 1,
+            Ok(Processed::Dependencies(package, deps)) => {
+                generation.processed += 1;
+                generation.packages.insert(cargo_path.clone(), package);
+                generation.dependencies.extend(deps);
+            }
+            Ok(Processed::Package(package)) => {
+                generation.processed += 1;
+                packages.push(*package);
+            }
+            Ok(Processed::Declined) => {}
+            Err(e) => {
+                diagnostics::warning_in(Code::ProcessingFailed, &cargo_path, format!("Error processing {:?}: {}\nSkipping this file.", cargo_path, e));
+                generation.skipped += 1;
+            }
+        }
+        progress.finish();
+    }
+    drop(progress);
+
+    if options.workspace_citation {
+        match workspace {
+            Some(_) => write_workspace_citation(&start_dir, options, formatter.as_ref(), &packages, &mut generation.written)?,
+            None => diagnostics::note(format!("No workspace at {:?}; --workspace-citation is ignored.", start_dir)),
+        }
+    }
+    generation.cited = packages;
+    Ok(generation)
+}
+
+/// Generate the citation files for `path` as `cargo cite` does: the package
+/// citations, the combined dependencies citation file with `dependencies`,
+/// the mdBook chapter with `mdbook`, then the configured post-generate hooks.
+pub async fn generate_citations(path: &Path, options: &CitationOptions) -> Result<Generation, Error> {
+    let fetcher = options.fetcher()?;
+    let mut generation = collect_citations(path, options, &fetcher).await?;
+    let formatter = options.formatter()?;
+    let start_dir = start_dir(path);
+
+    // Overridden DOIs and entries are not looked up
+    if options.adopt_recommended {
+        for dep in generation.dependencies.iter_mut().filter(|d| !d.overridden()) {
+            let info = archive::lookup(&fetcher, dep).await;
+            dep.adopt(info);
+        }
+    } else if options.lookup_doi {
+        for dep in generation.dependencies.iter_mut().filter(|d| !d.overridden()) {
+            if let Some(record) = archive::doi_record(&fetcher, dep).await {
+                dep.adopt_record(record);
+            }
+        }
+    }
+    if options.swhid {
+        for dep in generation.dependencies.iter_mut().filter(|d| d.swhid.is_none()) {
+            if let Some(repository) = dep.repository() {
+                dep.swhid = archive::archived_swhid(&fetcher, &dep.name, repository).await;
+            }
+        }
+    }
+    if formatter.renders_bibtex() && !options.no_doi_bibtex && !options.offline {
+        // An entry from the dependency's own CITATION.bib is kept, and
+        // corrected fields are generated
+        for dep in generation.dependencies.iter_mut().filter(|d| d.preferred_entry.is_none() && !d.overrides.as_ref().is_some_and(DependencyOverride::edits_fields)) {
+            if let Some(doi) = &dep.doi {
+                dep.preferred_entry = archive::doi_entry(&fetcher, doi, &dep.key).await;
+            }
+        }
+    }
+
+    // Write combined dependencies to a single file
+    if options.dependencies && !generation.dependencies.is_empty() {
+        let mut entries: Vec<CitationEntry> = generation.dependencies.iter().map(CitationEntry::from_dependency).collect();
+        if generation.packages.len() > 1 {
+            let before = entries.len();
+            entries = entry::dedupe(entries, |manifest_path| generation.packages.get(manifest_path).cloned().unwrap_or_default());
+            if entries.len() < before {
+                let merged = before - entries.len();
+                diagnostics::verbose(format!("Merged {} duplicate dependency entr{}", merged, if merged == 1 { "y" } else { "ies" }));
+            }
+        }
+        entry::unique_keys(None, &mut entries);
+        entry::sort(&mut entries, options.sort.unwrap_or_default());
+        for (i, entry) in entries.iter().enumerate() {
+            if let Some(text) = formatter.entry(entry, i + 1) {
+                diagnostics::entry(&entry.key, EntryKind::Dependency, &entry.manifest_path, &text);
+            }
+        }
+        generation.cited.extend(entries.iter().cloned());
+
+        let file_path = &generation.dependencies_file;
+        // Each group in a file of its own with --split, else in sections
+        let files: Vec<(PathBuf, Vec<entry::Group>)> = match options.group_by {
+            Some(by) if options.split => entry::group(entries, by).into_iter()
+                .map(|group| (group_file(file_path, &group.slug, formatter.extension()), vec![group]))
+                .collect(),
+            Some(by) => vec![(file_path.clone(), entry::group(entries, by))],
+            None => vec![(file_path.clone(), vec![entry::Group { title: String::new(), slug: String::new(), entries }])],
+        };
+        for (file_path, mut groups) in files {
+            for group in &mut groups {
+                keep_dates(&file_path, None, &mut group.entries);
+            }
+            let content = match options.group_by {
+                Some(_) if !options.split => sections(formatter.as_ref(), &groups),
+                _ => formatter.document(None, &groups[0].entries),
+            }.map_err(|e| Error::new(Code::FormatterFailed, e))?;
+
+            if options.filename.as_deref() == Some("STDOUT") {
+                // Machine-readable output already carries every entry
+                if diagnostics::message_format() != MessageFormat::Json {
+                    print!("{}", content);
+                }
+                continue;
+            }
+            let content = citation_content(&file_path, content, formatter.as_ref(), options)?;
+            if output::write(&file_path, &content, "dependencies citation file", options.overwrite || options.merge || options.update, options)
+                .map_err(|e| Error::new(Code::ProcessingFailed, format!("Could not write {:?}: {}", file_path, e)))? == Outcome::Written
+            {
+                diagnostics::info(format!("Created combined dependencies citation file at {:?}", file_path));
+                generation.written.push(file_path);
+            }
+        }
+    }
+
+    if let Some(book_dir) = &options.mdbook {
+        let written = mdbook(&start_dir, Path::new(book_dir), options, &fetcher).await?;
+        generation.written.extend(written);
+    }
+
+    if !generation.written.is_empty() {
+        let config = load_config(&start_dir, options)?;
+        hooks::run_post_generate(config.hooks.post_generate.as_slice(), &start_dir, &generation.written);
+    }
+    Ok(generation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_internal_compares_with_root() {
+        let root = Path::new("/nonexistent/ws");
+        assert!(is_internal(Path::new("/nonexistent/ws/crates/a/../util"), root));
+        assert!(!is_internal(Path::new("/nonexistent/ws/../vendored"), root));
+        assert!(!is_internal(Path::new("/nonexistent/wsx"), root));
+    }
+
+    #[test]
+    fn publish_restricts_crates_io_lookups() {
+        let package = |publish: &str| toml::from_str::<PackageInfo>(&format!("name = \"a\"\nversion = \"0.1.0\"\n{}", publish)).unwrap();
+        assert!(package("").publishes_to_crates_io());
+        assert!(!package("publish = false").publishes_to_crates_io());
+        assert!(!package("publish = [\"my-company\"]").publishes_to_crates_io());
+        assert!(package("publish = [\"crates-io\"]").publishes_to_crates_io());
+    }
+}
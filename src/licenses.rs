@@ -0,0 +1,52 @@
+//! `--license-summary`: the license of each cited crate, for the
+//! acknowledgments of a paper or a distribution's notices.
+
+use crate::entry::CitationEntry;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct CrateLicense {
+    pub name: String,
+    pub version: Option<String>,
+    /// SPDX expression, or where a custom license is found; `None` when
+    /// neither is known
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LicenseSummary {
+    pub crates: Vec<CrateLicense>,
+}
+
+impl LicenseSummary {
+    /// The licenses of `entries`, listing each crate version once.
+    pub fn collect(entries: &[CitationEntry]) -> Self {
+        let mut crates: Vec<CrateLicense> = Vec::new();
+        for entry in entries {
+            if crates.iter().any(|c| c.name == entry.name && c.version == entry.version) {
+                continue;
+            }
+            crates.push(CrateLicense {
+                name: entry.name.clone(),
+                version: entry.version.clone(),
+                license: entry.license_text(),
+            });
+        }
+        LicenseSummary { crates }
+    }
+
+    pub fn render(&self) -> String {
+        let rows: Vec<[&str; 3]> = self.crates.iter()
+            .map(|c| [c.name.as_str(), c.version.as_deref().unwrap_or("-"), c.license.as_deref().unwrap_or("unknown")])
+            .collect();
+        let header = ["Crate", "Version", "License"];
+        let width = |column: usize| rows.iter().map(|row| row[column].len()).chain([header[column].len()]).max().unwrap_or_default();
+        let (name_width, version_width) = (width(0), width(1));
+        let mut text = String::from("Licenses:\n");
+        for row in [header].iter().chain(&rows) {
+            text.push_str(&format!("  {:<name_width$}  {:<version_width$}  {}\n", row[0], row[1], row[2]));
+        }
+        text.push_str(&format!("{} crate{}", rows.len(), if rows.len() == 1 { "" } else { "s" }));
+        text
+    }
+}
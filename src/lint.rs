@@ -0,0 +1,273 @@
+//! `cargo cite lint`: problems in existing citation files, whether generated
+//! or written by hand.
+//!
+//! BibTeX files are checked for malformed entries, duplicate keys, entries
+//! missing the fields reference managers need, and versions that no longer
+//! match Cargo.toml. CITATION.cff files get the same checks where they apply.
+
+use crate::bibtex;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// The file cannot be read back
+    Malformed,
+    DuplicateKey,
+    MissingField,
+    VersionMismatch,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    pub file: PathBuf,
+    /// Line of the entry or field concerned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Citation key of the BibTeX entry concerned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub rule: Rule,
+    pub message: String,
+}
+
+/// What the citation files are checked against.
+#[derive(Debug, Default)]
+pub struct Expected {
+    pub package: String,
+    pub version: String,
+    /// Version requirements of the dependencies, by crate name
+    pub requirements: BTreeMap<String, VersionReq>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintReport {
+    pub files: Vec<PathBuf>,
+    pub findings: Vec<Finding>,
+}
+
+/// The crate an entry cites: its title up to the description, as written by
+/// cargo-cite, e.g. `{serde: A serialization framework}`.
+fn cited_crate(title: &str) -> &str {
+    title.split(':').next().unwrap_or_default().trim_matches(|c: char| c == '{' || c == '}' || c.is_whitespace())
+}
+
+/// Whether `version`, as cited, still matches what Cargo.toml declares for
+/// `name`: the package's own version, or a dependency's requirement.
+fn version_problem(name: &str, version: &str, expected: &Expected) -> Option<String> {
+    if name == expected.package {
+        return (version != expected.version)
+            .then(|| format!("{} is cited at version {}, but Cargo.toml has {}", name, version, expected.version));
+    }
+    let req = expected.requirements.get(name)?;
+    let parsed = Version::parse(version).ok()?;
+    (!req.matches(&parsed))
+        .then(|| format!("{} is cited at version {}, which does not match the requirement {} in Cargo.toml", name, version, req))
+}
+
+fn lint_bibtex(path: &Path, text: &str, expected: &Expected, findings: &mut Vec<Finding>) {
+    let finding = |line, key: Option<&str>, rule, message| Finding {
+        file: path.to_path_buf(),
+        line,
+        key: key.map(str::to_string),
+        rule,
+        message,
+    };
+    let first = findings.len();
+    // Entries after a malformed one are still checked
+    let (entries, errors) = bibtex::parse_all(text);
+    for error in errors {
+        findings.push(finding(Some(error.line), None, Rule::Malformed, error.message));
+    }
+    let mut keys = BTreeSet::new();
+    for entry in &entries {
+        let line = Some(bibtex::line_of(text, entry.span.start));
+        let key = Some(entry.key);
+        if !keys.insert(entry.key.to_lowercase()) {
+            findings.push(finding(line, key, Rule::DuplicateKey, format!("Duplicate key {}", entry.key)));
+        }
+        let software = ["misc", "software"].iter().any(|t| entry.entry_type.eq_ignore_ascii_case(t));
+        let missing: Vec<&str> = [
+            ("author", entry.field("author").is_some()),
+            ("version", !software || entry.field("version").is_some()),
+            ("year", entry.field("year").or(entry.field("date")).is_some()),
+            ("url or doi", ["url", "doi", "howpublished"].iter().any(|f| entry.field(f).is_some())),
+        ].into_iter().filter(|(_, present)| !present).map(|(name, _)| name).collect();
+        if !missing.is_empty() {
+            findings.push(finding(line, key, Rule::MissingField, format!("Entry {} has no {}", entry.key, missing.join(", "))));
+        }
+        if let (Some(title), Some(version)) = (entry.field("title"), entry.field("version")) {
+            if let Some(problem) = version_problem(cited_crate(title), version, expected) {
+                findings.push(finding(line, key, Rule::VersionMismatch, problem));
+            }
+        }
+    }
+    findings[first..].sort_by_key(|finding| finding.line);
+}
+
+/// A YAML scalar or list that is present and not empty.
+fn present(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::String(s)) => !s.trim().is_empty(),
+        Some(Value::Sequence(items)) => !items.is_empty(),
+        Some(_) => true,
+    }
+}
+
+fn lint_cff(path: &Path, text: &str, expected: &Expected, findings: &mut Vec<Finding>) {
+    let finding = |rule, message| Finding { file: path.to_path_buf(), line: None, key: None, rule, message };
+    let cff: Value = match serde_yaml::from_str(text) {
+        Ok(cff) => cff,
+        Err(e) => {
+            findings.push(Finding {
+                line: e.location().map(|l| l.line()),
+                ..finding(Rule::Malformed, format!("Invalid YAML: {}", e))
+            });
+            return;
+        }
+    };
+    let missing: Vec<&str> = [
+        ("title", present(cff.get("title"))),
+        ("authors", present(cff.get("authors"))),
+        ("version", present(cff.get("version"))),
+        ("date-released", present(cff.get("date-released"))),
+        ("doi or url", ["doi", "url", "repository-code", "identifiers"].iter().any(|f| present(cff.get(f)))),
+    ].into_iter().filter(|(_, present)| !present).map(|(name, _)| name).collect();
+    if !missing.is_empty() {
+        findings.push(finding(Rule::MissingField, format!("No {}", missing.join(", "))));
+    }
+    let version = match cff.get("version") {
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    if let Some(problem) = version.and_then(|v| version_problem(&expected.package, &v, expected)) {
+        findings.push(finding(Rule::VersionMismatch, problem));
+    }
+}
+
+impl LintReport {
+    /// Check each of `files`, BibTeX or CFF by extension, against `expected`.
+    /// Files that cannot be read are reported as malformed.
+    pub fn collect(files: &[PathBuf], expected: &Expected) -> Self {
+        let mut findings = Vec::new();
+        for path in files {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(e) => {
+                    findings.push(Finding { file: path.clone(), line: None, key: None, rule: Rule::Malformed, message: format!("Could not read the file: {}", e) });
+                    continue;
+                }
+            };
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("cff") => lint_cff(path, &text, expected, &mut findings),
+                _ => lint_bibtex(path, &text, expected, &mut findings),
+            }
+        }
+        LintReport { files: files.to_vec(), findings }
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for finding in &self.findings {
+            let location = match finding.line {
+                Some(line) => format!("{}:{}", finding.file.display(), line),
+                None => finding.file.display().to_string(),
+            };
+            text.push_str(&format!("{}: {}\n", location, finding.message));
+        }
+        let files = self.files.len();
+        match self.findings.len() {
+            0 => text.push_str(&format!("No problems found in {} file{}.", files, if files == 1 { "" } else { "s" })),
+            n => text.push_str(&format!("{} problem{} found in {} file{}.", n, if n == 1 { "" } else { "s" }, files, if files == 1 { "" } else { "s" })),
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected() -> Expected {
+        Expected {
+            package: String::from("demo"),
+            version: String::from("1.2.0"),
+            requirements: BTreeMap::from([(String::from("serde"), VersionReq::parse("1.0").unwrap())]),
+        }
+    }
+
+    fn bibtex(text: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        lint_bibtex(Path::new("CITATION.bib"), text, &expected(), &mut findings);
+        findings
+    }
+
+    fn cff(text: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        lint_cff(Path::new("CITATION.cff"), text, &expected(), &mut findings);
+        findings
+    }
+
+    fn rules(findings: &[Finding]) -> Vec<(Option<usize>, Rule)> {
+        findings.iter().map(|finding| (finding.line, finding.rule)).collect()
+    }
+
+    const COMPLETE: &str = "author = {Jane Doe}, version = {1.2.0}, year = 2024, url = {https://example.org}";
+
+    #[test]
+    fn duplicate_keys_differ_in_case() {
+        let findings = bibtex(&format!("@misc{{demo, title = {{demo}}, {c}}}\n@misc{{Demo, title = {{demo}}, {c}}}\n", c = COMPLETE));
+        assert_eq!(rules(&findings), [(Some(2), Rule::DuplicateKey)]);
+        assert_eq!(findings[0].key.as_deref(), Some("Demo"));
+    }
+
+    #[test]
+    fn missing_fields_depend_on_entry_type() {
+        let findings = bibtex("@misc{tool, title = {tool}, year = 2024}\n@article{paper, title = {Paper}, year = 2024}\n");
+        assert_eq!(rules(&findings), [(Some(1), Rule::MissingField), (Some(2), Rule::MissingField)]);
+        assert_eq!(findings[0].message, "Entry tool has no author, version, url or doi");
+        assert_eq!(findings[1].message, "Entry paper has no author, url or doi");
+    }
+
+    #[test]
+    fn versions_are_checked_against_cargo_toml() {
+        let text = "@misc{demo, title = {demo: A demo}, author = {A}, version = {1.1.0}, year = 2024, url = {u}}\n\
+                    @misc{serde, title = {serde}, author = {B}, version = {2.0.0}, year = 2024, url = {u}}\n\
+                    @misc{serde-2, title = {serde}, author = {B}, version = {1.0.200}, year = 2024, url = {u}}\n\
+                    @misc{other, title = {other}, author = {C}, version = {9.9.9}, year = 2024, url = {u}}\n";
+        let findings = bibtex(text);
+        assert_eq!(rules(&findings), [(Some(1), Rule::VersionMismatch), (Some(2), Rule::VersionMismatch)]);
+        assert_eq!(findings[0].message, "demo is cited at version 1.1.0, but Cargo.toml has 1.2.0");
+        assert_eq!(findings[1].message, "serde is cited at version 2.0.0, which does not match the requirement ^1.0 in Cargo.toml");
+    }
+
+    #[test]
+    fn malformed_bibtex_is_reported_with_the_rest() {
+        let findings = bibtex(&format!("@misc{{broken, title = {{x}}\n@misc{{ok, title = {{ok}}, {}}}\n", COMPLETE));
+        assert_eq!(rules(&findings), [(Some(2), Rule::Malformed)]);
+    }
+
+    #[test]
+    fn cff_version_and_fields() {
+        let findings = cff("cff-version: 1.2.0\ntitle: demo\nauthors:\n  - name: Jane\nversion: 1.0.0\ndate-released: 2024-05-01\nrepository-code: https://example.org\n");
+        assert_eq!(rules(&findings), [(None, Rule::VersionMismatch)]);
+        let findings = cff("cff-version: 1.2.0\ntitle: demo\nauthors: []\nversion: 1.2.0\n");
+        assert_eq!(rules(&findings), [(None, Rule::MissingField)]);
+        assert_eq!(findings[0].message, "No authors, date-released, doi or url");
+    }
+
+    #[test]
+    fn malformed_yaml_reports_its_line() {
+        let findings = cff("cff-version: 1.2.0\ntitle: demo\nauthors: - name: Jane\nversion: 1.2.0\n");
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, Rule::Malformed);
+        assert_eq!(findings[0].line, Some(3));
+    }
+}
@@ -0,0 +1,122 @@
+//! Cargo.lock, for the exact versions dependencies resolved to.
+
+use crate::registries::AltRegistry;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::path::Path;
+
+pub const LOCK_FILE: &str = "Cargo.lock";
+
+/// Source of packages from crates.io, which need no further identification.
+pub const CRATES_IO_SOURCE: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+#[derive(Debug, Deserialize)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub(crate) package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `registry+<index>` or `git+<url>#<commit>`; absent for path dependencies
+    pub source: Option<String>,
+    /// SHA-256 of the downloaded registry package
+    pub checksum: Option<String>,
+}
+
+impl LockedPackage {
+    /// The source, unless it is crates.io.
+    pub fn source_url(&self) -> Option<&str> {
+        self.source.as_deref().filter(|s| *s != CRATES_IO_SOURCE)
+    }
+
+    /// The commit a git dependency is locked to.
+    pub fn git_commit(&self) -> Option<&str> {
+        self.source.as_deref()?.strip_prefix("git+")?.rsplit_once('#').map(|(_, commit)| commit)
+    }
+}
+
+impl Lockfile {
+    pub fn parse(content: &str) -> Option<Lockfile> {
+        toml::from_str(content).ok()
+    }
+
+    /// The lockfile next to `manifest_path` or, for workspace members, in
+    /// the closest directory above it, read through `read`.
+    pub fn find(manifest_path: &Path, read: &dyn Fn(&Path) -> Option<String>) -> Option<Lockfile> {
+        manifest_path.ancestors().skip(1)
+            .find_map(|dir| read(&dir.join(LOCK_FILE)))
+            .and_then(|content| Lockfile::parse(&content))
+    }
+
+    /// The package a dependency on `name` resolved to: from `git` if given,
+    /// a path dependency if `path`, otherwise the newest release matching
+    /// `req` from `registry`, or any registry when it is not known.
+    pub fn locked(&self, name: &str, req: &VersionReq, git: Option<&str>, path: bool, registry: Option<&AltRegistry>) -> Option<&LockedPackage> {
+        self.package.iter()
+            .filter(|p| p.name == name)
+            .filter(|p| match (&p.source, git) {
+                (Some(source), Some(git)) => source.starts_with(&format!("git+{}", git.trim_end_matches(".git"))),
+                (None, None) => path,
+                (Some(source), None) => !path && match registry {
+                    Some(registry) => registry.is_source(source),
+                    None => source.starts_with("registry+") || source.starts_with("sparse+"),
+                },
+                (None, Some(_)) => false,
+            })
+            .filter_map(|p| Some((Version::parse(&p.version).ok()?, p)))
+            .filter(|(v, _)| git.is_some() || path || req.matches(v))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, p)| p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCK: &str = r#"
+[[package]]
+name = "rand"
+version = "0.7.3"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "rand"
+version = "0.9.0"
+source = "git+https://github.com/rust-random/rand#0123abc"
+
+[[package]]
+name = "util"
+version = "0.1.0"
+"#;
+
+    fn version(package: Option<&LockedPackage>) -> Option<&str> {
+        package.map(|p| p.version.as_str())
+    }
+
+    #[test]
+    fn locked_picks_newest_matching_release() {
+        let lock = Lockfile::parse(LOCK).unwrap();
+        assert_eq!(version(lock.locked("rand", &VersionReq::parse("0.8").unwrap(), None, false, None)), Some("0.8.5"));
+        assert_eq!(version(lock.locked("rand", &VersionReq::parse("0.7").unwrap(), None, false, None)), Some("0.7.3"));
+        assert_eq!(version(lock.locked("rand", &VersionReq::parse("0.6").unwrap(), None, false, None)), None);
+    }
+
+    #[test]
+    fn locked_matches_git_and_path_sources() {
+        let lock = Lockfile::parse(LOCK).unwrap();
+        let git = lock.locked("rand", &VersionReq::STAR, Some("https://github.com/rust-random/rand.git"), false, None).unwrap();
+        assert_eq!(git.version, "0.9.0");
+        assert_eq!(git.git_commit(), Some("0123abc"));
+        assert_eq!(version(lock.locked("util", &VersionReq::STAR, None, true, None)), Some("0.1.0"));
+        assert_eq!(version(lock.locked("util", &VersionReq::STAR, None, false, None)), None);
+    }
+}
@@ -1,448 +1,314 @@
+use cargo_cite::codes::Code;
+use cargo_cite::diagnostics::{self, MessageFormat};
+use cargo_cite::{cite_crate, collect_citations, coverage, deposit, doctor, generate_citations, install_hook, joss, licenses, lint_citations, notice, output, recommend, stats, verify_readme, watch};
+use cargo_cite::{CitationOptions, Error, Generation, CARGO_FILE, EXIT_FAILURE, EXIT_USAGE};
 use gumdrop::Options;
 use std::env;
-use std::fs;
-use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
-use chrono::Datelike;
-use serde::Deserialize;
-
-const CARGO_FILE: &str = "Cargo.toml";
-const CITATION_FILE: &str = "CITATION.bib";
-
-#[derive(Debug, Deserialize)]
-struct ManifestInfo {
-    package: PackageInfo,
-    dependencies: Option<std::collections::BTreeMap<String, DependencyInfo>>,
-}
-
-#[derive(Debug, Deserialize)]
-struct PackageInfo {
-    name: String,
-    version: String,
-    #[serde(default)]
-    authors: Vec<String>,
-    description: Option<String>,
-    repository: Option<String>,
-    keywords: Option<Vec<String>>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(untagged)]
-enum DependencyInfo {
-    Simple(String),
-    Detailed {
-        version: Option<String>,
-        path: Option<String>,
-        git: Option<String>,
-    },
+use std::process::ExitCode;
+
+/// Subcommands, given as the first free argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Generate,
+    VerifyReadme,
+    Lint,
+    Doctor,
+    Stats,
+    Coverage,
+    Deposit,
+    Joss,
+    Notice,
+    Crate,
+    HookInstall,
 }
 
-#[derive(Debug, Options)]
-struct CitationOption {
-    #[options(free)]
-    free: Vec<String>,
-
-    #[options(help = "print help message", short = "h")]
-    help: bool,
-
-    #[options(help = "Generate CITATION.bib file", short = "g")]
-    generate: bool,
-
-    #[options(help = "Over-write existing CITATION.bib file", short = "o")]
-    overwrite: bool,
-
-    #[options(help = "Append a \"Citing\" section to the README", short = "r")]
-    readme_append: bool,
-
-    #[options(help = "Path to the crate, default to current directory. If not specified, will use current directory and recursively search all subdirectories for Cargo.toml files", short = "p")]
-    path: Option<String>,
-
-    #[options(help = "Citation file to add, default to CITATION.bib (recommended). \"STDOUT\" for outputing to standard output.", short = "f")]
-    filename: Option<String>,
-
-    #[options(help = "Generate BibTeX entries for all explicit dependencies", short = "d")]
-    dependencies: bool,
-
-    #[options(help = "Maximum depth for recursive search (default: unlimited). 0 means only current directory, -1 means unlimited depth.", short = "m")]
-    max_depth: Option<i32>,
-}
-
-#[derive(Debug, Deserialize)]
-struct CratesIoResponse {
-    #[serde(rename = "crate")]
-    crate_info: CrateInfo,
-}
-
-#[derive(Debug, Deserialize)]
-struct CrateInfo {
-    description: Option<String>,
-    repository: Option<String>,
-    homepage: Option<String>,
-    authors: Option<Vec<String>>,
-}
-
-impl PackageInfo {
-    pub fn build_bibtex(&self) -> String {
-        let t = chrono::prelude::Local::now();
-        let description_part = self.description.as_ref()
-            .map(|s| format!(": {}", s))
-            .unwrap_or_default();
-
-        format!(
-            "@misc{{{name},\n\
-             \ttitle={{{name}{desc}}},\n\
-             \tauthor={{{authors}}},\n\
-             \tversion = {{{version}}},\n\
-             \tmonth = {month},\n\
-             \tyear = {year},\n\
-             {repository}\
-             {keywords}\
-             }}\n",
-            name = self.name,
-            desc = description_part,
-            authors = self.authors.join(" and "),
-            version = self.version,
-            month = t.month(),
-            year = t.year(),
-            repository = self.repository.as_ref()
-                .map(|url| format!("\turl = {{{}}},\n", url))
-                .unwrap_or_default(),
-            keywords = self.keywords.as_ref()
-                .map(|k| format!("\tkeywords = {{{}}}\n", k.join(", ")))
-                .unwrap_or_default()
-        )
-    }
-
-    fn readme_section(&self) -> String {
-        String::from(
-"
-## Citing
-
-If you found this software useful consider citing it. See CITATION.bib for the recommended BibTeX entry.
-"
-        )
+/// Header of `--help`, listing the subcommands above the options.
+const USAGE: &str = "\
+Usage: cargo cite [OPTIONS] [COMMAND]
+
+Commands:
+  verify-readme  Check that the README \"Citing\" section matches the citation file
+  lint           Check the citation files for missing fields, duplicate keys and stale versions
+  doctor         Check the environment, configuration and network access
+  stats          Summarise the dependencies and how citable they are
+  coverage       Report which dependencies lack citation metadata
+  deposit        Archive the current release and mint a DOI (--zenodo)
+  joss           Start a JOSS paper: paper/paper.md and paper/paper.bib
+  crate NAME     Print the citation of a crate from crates.io, NAME or NAME@VERSION
+  notice         Write a NOTICE file with the citation, license and copyright holders of each dependency
+  hook install   Install a git pre-commit hook that runs --check with the given options
+";
+
+impl Command {
+    fn parse(free: &[String]) -> Result<Command, String> {
+        match free {
+            [] => Ok(Command::Generate),
+            [command] if command == "verify-readme" => Ok(Command::VerifyReadme),
+            [command] if command == "lint" => Ok(Command::Lint),
+            [command] if command == "doctor" => Ok(Command::Doctor),
+            [command] if command == "stats" => Ok(Command::Stats),
+            [command] if command == "coverage" => Ok(Command::Coverage),
+            [command] if command == "deposit" => Ok(Command::Deposit),
+            [command] if command == "joss" => Ok(Command::Joss),
+            [command] if command == "notice" => Ok(Command::Notice),
+            // The crate is read from the free arguments again when run
+            [command, _] if command == "crate" => Ok(Command::Crate),
+            [command, ..] if command == "crate" => Err(String::from("expected `crate <name>[@version]`")),
+            [command, sub] if command == "hook" && sub == "install" => Ok(Command::HookInstall),
+            [command, ..] if command == "hook" => Err(String::from("expected `hook install`")),
+            [command, ..] => Err(format!("unknown command {:?}", command)),
+        }
     }
 }
+/// Report `e` and the exit status it warrants.
+fn fail(e: Error) -> ExitCode {
+    diagnostics::error(e.code, e.message.as_str());
+    ExitCode::from(e.exit_code())
+}
 
-impl DependencyInfo {
-    fn get_version(&self) -> Option<String> {
-        match self {
-            DependencyInfo::Simple(v) => Some(v.clone()),
-            DependencyInfo::Detailed { version, .. } => version.clone(),
-        }
+/// The exit status of a completed run: a failure when no manifest could be
+/// processed, when `--check` found files out of date or, with `--strict`,
+/// when a file was skipped or a warning reported.
+fn status(opt: &CitationOptions, generation: &Generation) -> ExitCode {
+    if generation.processed == 0 || output::stale_count() > 0 {
+        return ExitCode::from(EXIT_FAILURE);
     }
-
-    fn get_source_info(&self) -> (Option<String>, Option<String>) {
-        match self {
-            DependencyInfo::Simple(_) => (None, None),
-            DependencyInfo::Detailed { path, git, .. } => (path.clone(), git.clone()),
-        }
+    if opt.strict && (generation.skipped > 0 || diagnostics::warning_count() > 0) {
+        diagnostics::note("Failing because of the warnings above (--strict).");
+        return ExitCode::from(EXIT_FAILURE);
     }
+    ExitCode::SUCCESS
 }
 
-impl ManifestInfo {
-    async fn fetch_crate_metadata(crate_name: &str) -> Option<CrateInfo> {
-        let client = reqwest::Client::new();
-        let url = format!("https://crates.io/api/v1/crates/{}", crate_name);
-        
-        match client.get(&url)
-            .header("User-Agent", "cargo-cite")
-            .send()
-            .await {
-                Ok(response) => {
-                    if let Ok(data) = response.json::<CratesIoResponse>().await {
-                        Some(data.crate_info)
-                    } else {
-                        None
-                    }
-                }
-                Err(_) => None
-            }
-    }
-
-    async fn build_dependencies_bibtex(&self) -> String {
-        let mut result = String::new();
-        if let Some(deps) = &self.dependencies {
-            for (name, info) in deps {
-                result.push_str("@misc{");
-                result.push_str(&format!("rust-{},\n", name));
-                result.push_str(&format!("\ttitle={{{}}},\n", name));
-                
-                // Try to fetch metadata for crates.io dependencies
-                let (path_source, git_source) = info.get_source_info();
-                let is_regular_dependency = path_source.is_none() && git_source.is_none();
-                
-                if let Some(path) = path_source {
-                    result.push_str(&format!("\tnote = {{Local dependency from path: {}}},\n", path));
-                } else if let Some(git) = git_source {
-                    result.push_str(&format!("\turl = {{{}}},\n", git));
-                    result.push_str("\tnote = {Git dependency},\n");
-                } else {
-                    // Regular crates.io dependency
-                    if let Some(metadata) = Self::fetch_crate_metadata(name).await {
-                        if let Some(desc) = metadata.description {
-                            result.push_str(&format!("\tnote = {{{}}},\n", desc));
-                        }
-                        
-                        if let Some(authors) = metadata.authors {
-                            if !authors.is_empty() {
-                                result.push_str(&format!("\tauthor = {{{}}},\n", authors.join(" and ")));
-                            }
-                        }
-
-                        // Prefer repository URL, fallback to homepage
-                        if let Some(url) = metadata.repository.or(metadata.homepage) {
-                            result.push_str(&format!("\turl = {{{}}},\n", url));
-                        }
-                    }
-                }
-
-                if let Some(version) = info.get_version() {
-                    result.push_str(&format!("\tversion = {{{}}},\n", version));
-                }
-                
-                let t = chrono::prelude::Local::now();
-                result.push_str(&format!("\tyear = {},\n", t.year()));
-                result.push_str(&format!("\tmonth = {},\n", t.month()));
-                
-                // Only add crates.io link for regular dependencies
-                if is_regular_dependency {
-                    result.push_str(&format!("\thowpublished = {{https://crates.io/crates/{}}},\n", name));
-                }
-                
-                result.push_str("}\n\n");
+/// Regenerate the citations of `path` whenever the files they are generated
+/// from change, until interrupted.
+async fn watch_citations(path: &Path, start_dir: &Path, opt: &CitationOptions) -> ExitCode {
+    let mut files = watch::watched_files(start_dir, &[]);
+    loop {
+        diagnostics::reset_warning_count();
+        match generate_citations(path, opt).await {
+            Ok(generation) => {
+                diagnostics::summary(generation.processed, generation.skipped);
+                files = watch::watched_files(start_dir, &generation.manifests);
             }
+            Err(e) => diagnostics::error(e.code, e.message),
         }
-        result
+        diagnostics::info("\nWatching for changes (press Ctrl-C to stop)");
+        watch::changed(&files).await;
     }
 }
 
-fn find_cargo_files(start_dir: &Path, max_depth: Option<i32>) -> Vec<PathBuf> {
-    let walker = WalkDir::new(start_dir).follow_links(true);
-    
-    // Apply max depth if specified, otherwise unlimited
-    let walker = match max_depth {
-        Some(depth) if depth >= 0 => walker.max_depth(depth as usize),
-        Some(_) => walker, // negative means unlimited
-        None => walker, // default to unlimited
+#[tokio::main]
+async fn main() -> ExitCode {
+    // `cargo cite ...` runs `cargo-cite cite ...`
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match args.first() {
+        Some(first) if first == "cite" => &args[1..],
+        _ => &args[..],
     };
-
-    walker
-        .into_iter()
-        .filter_map(|e| match e {
-            Ok(entry) => Some(entry),
-            Err(err) => {
-                println!("Warning: Error accessing path: {}", err);
-                None
-            }
-        })
-        .filter(|e| e.file_type().is_file() && e.file_name() == CARGO_FILE)
-        .map(|e| {
-            println!("Found Cargo.toml at: {:?}", e.path());
-            e.path().to_path_buf()
-        })
-        .collect()
-}
-
-async fn process_cargo_file(cargo_path: &Path, opt: &CitationOption) -> Result<(bool, String), Box<dyn std::error::Error>> {
-    println!("\nProcessing {:?}", cargo_path);
-    
-    let mut cargo_file = match fs::File::open(cargo_path) {
-        Ok(file) => file,
+    let mut opt = match CitationOptions::parse_args_default(args) {
+        Ok(opt) => opt,
         Err(e) => {
-            println!("Warning: Could not open {:?}: {}", cargo_path, e);
-            println!("         Skipping this file.");
-            return Ok((false, String::new()));
+            eprintln!("cargo cite: {}", e);
+            return ExitCode::from(EXIT_USAGE);
         }
     };
-
-    let mut cargo_content = String::new();
-    if let Err(e) = cargo_file.read_to_string(&mut cargo_content) {
-        println!("Warning: Could not read {:?}: {}", cargo_path, e);
-        println!("         Skipping this file.");
-        return Ok((false, String::new()));
+    if opt.help_requested() {
+        println!("{}\n{}", USAGE, CitationOptions::usage());
+        return ExitCode::SUCCESS;
+    }
+    diagnostics::set_message_format(opt.message_format.unwrap_or(MessageFormat::Human));
+    diagnostics::set_verbosity(opt.verbosity());
+    // Citations written to stdout must be pipeable
+    if opt.filename.as_deref() == Some("STDOUT") {
+        diagnostics::reserve_stdout();
     }
 
-    let manifest: ManifestInfo = match toml::from_str(&cargo_content) {
-        Ok(manifest) => manifest,
-        Err(e) => {
-            println!("Warning: Invalid Cargo.toml at {:?}:", cargo_path);
-            println!("         {}", e);
-            println!("         Skipping this file.");
-            return Ok((false, String::new()));
-        }
-    };
-    
-    if opt.dependencies {
-        let deps_bibtex = manifest.build_dependencies_bibtex().await;
-        return Ok((true, deps_bibtex));
+    if let Some(code) = opt.explain {
+        println!("{}\n\n{}", code, code.explanation());
+        return ExitCode::SUCCESS;
     }
 
-    if opt.readme_append {
-        let parent_dir = cargo_path.parent().unwrap();
-        for dir_entry in (fs::read_dir(parent_dir)?).flatten() {
-            let p = dir_entry.path();
-            if p.to_string_lossy().contains("README") {
-                println!("Appending to readme file: {:?}", p);
-                let mut readme_file = fs::OpenOptions::new().append(true).open(&p)?;
-                let readme_section = manifest.package.readme_section();
-                readme_file.write_all(readme_section.as_bytes())?;
-            }
-        }
+    if let Err(e) = opt.formatter() {
+        return fail(e);
     }
 
-    let r = manifest.package.build_bibtex();
-    let output_file = if let Some(o) = &opt.filename {
-        o.clone()
-    } else {
-        String::from(CITATION_FILE)
+    let command = match Command::parse(&opt.command) {
+        Ok(command) => command,
+        Err(e) => return fail(Error::new(Code::InvalidCommand, e)),
     };
-
-    let file_path = cargo_path.parent().unwrap().join(PathBuf::from(&output_file));
-    if file_path.exists() && !opt.overwrite {
-        println!("Note: Citation file already exists at {:?}.", &file_path);
-        println!("      Use --overwrite to replace it.");
-        return Ok((false, String::new()));
+    // The citation of `crate` is printed
+    if command == Command::Crate {
+        diagnostics::reserve_stdout();
+    }
+    // Reports are gathered over the same dependency set as --dependencies
+    if matches!(command, Command::Stats | Command::Coverage) || opt.recommend {
+        opt.dependencies = true;
     }
-    
-    fs::write(&file_path, r.as_bytes())?;
-    println!("Created citation file at {:?}", file_path);
-    Ok((true, String::new()))
-}
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let opt = CitationOption::parse_args_default_or_exit();
+    let manifest_path = match &opt.manifest_path {
+        Some(_) if opt.path.is_some() => return fail(Error::new(Code::InvalidCommand, "--manifest-path and --path cannot be used together.")),
+        Some(p) => {
+            let p = PathBuf::from(p);
+            if p.file_name() != Some(CARGO_FILE.as_ref()) {
+                return fail(Error::new(Code::NoManifest, format!("The manifest-path must be a path to a Cargo.toml file, got {:?}.", p)));
+            }
+            if !p.is_file() {
+                return fail(Error::new(Code::NoManifest, format!("Manifest {:?} does not exist.", p)));
+            }
+            // A bare `Cargo.toml` has an empty parent
+            match p.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => Some(p),
+                _ => Some(Path::new(".").join(CARGO_FILE)),
+            }
+        }
+        None => None,
+    };
 
-    let start_dir = if let Some(ref s) = opt.path {
+    let start_dir = if let Some(ref p) = manifest_path {
+        p.parent().unwrap().to_path_buf()
+    } else if let Some(ref s) = opt.path {
         PathBuf::from(s)
+    } else if let Some(dir) = env::var_os("CARGO_MANIFEST_DIR") {
+        // Set when run from `cargo run` or a build script
+        PathBuf::from(dir)
     } else {
         match env::current_dir() {
             Ok(dir) => dir,
-            Err(e) => {
-                println!("Error: Could not access current directory: {}", e);
-                return Ok(());
-            }
+            Err(e) => return fail(Error::new(Code::NoManifest, format!("Could not access current directory: {}", e))),
         }
     };
 
     // Check if the start directory exists
     if !start_dir.exists() {
-        println!("Error: Directory {:?} does not exist.", start_dir);
-        return Ok(());
+        return fail(Error::new(Code::NoManifest, format!("Directory {:?} does not exist.", start_dir)));
     }
 
-    let cargo_files = if opt.dependencies {
-        // Only do directory walking for dependencies option
-        println!("Searching for Cargo.toml files in {:?}{}", 
-            start_dir,
-            match opt.max_depth {
-                Some(depth) if depth < 0 => String::from(" and all subdirectories"),
-                Some(0) => String::from(" (current directory only)"),
-                Some(depth) => format!(" (max depth: {})", depth),
-                None => String::from(" (searching all subdirectories)"),
-            }
-        );
-        find_cargo_files(&start_dir, opt.max_depth)
-    } else {
-        // For other operations, just look in the current directory
-        let cargo_path = start_dir.join(CARGO_FILE);
-        if cargo_path.exists() {
-            vec![cargo_path]
-        } else {
-            println!("Error: No Cargo.toml found in {:?}.", start_dir);
-            return Ok(());
-        }
+    let fetcher = match opt.fetcher() {
+        Ok(fetcher) => fetcher,
+        Err(e) => return fail(e),
     };
-    
-    if cargo_files.is_empty() {
-        if opt.max_depth == Some(0) {
-            println!("No Cargo.toml found in {:?}.", start_dir);
-            println!("Note: You can use --max-depth N to search subdirectories (N levels deep)");
-            println!("      or --max-depth -1 to search all subdirectories.");
-        } else {
-            println!("No Cargo.toml files found in {:?} or its subdirectories{}", 
-                start_dir,
-                match opt.max_depth {
-                    Some(depth) if depth < 0 => String::new(),
-                    Some(depth) if depth > 0 => format!(" (searched {} level{} deep)", 
-                        depth,
-                        if depth == 1 { "" } else { "s" }
-                    ),
-                    None => String::new(),
-                    _ => String::new(),
-                }
-            );
+
+    // Runs before the configuration is loaded so that it can report problems with it
+    if command == Command::Doctor {
+        if !doctor::run(&fetcher, opt.config.as_deref().map(Path::new), &start_dir).await {
+            return ExitCode::from(EXIT_FAILURE);
         }
-        return Ok(());
+        return ExitCode::SUCCESS;
     }
 
-    println!("\nFound {} Cargo.toml file{}", 
-        cargo_files.len(),
-        if cargo_files.len() == 1 { "" } else { "s" }
-    );
-
-    let mut processed = 0;
-    let mut skipped = 0;
-    let mut all_dependencies = String::new();
+    if command == Command::VerifyReadme {
+        match verify_readme(&start_dir, &opt, &fetcher).await {
+            Ok(true) => return ExitCode::SUCCESS,
+            Ok(false) => return ExitCode::from(EXIT_FAILURE),
+            Err(e) => return fail(e),
+        }
+    }
 
-    for cargo_path in cargo_files {
-        match process_cargo_file(&cargo_path, &opt).await {
-            Ok((success, deps_bibtex)) => {
-                if success {
-                    processed += 1;
-                    if opt.dependencies {
-                        all_dependencies.push_str(&deps_bibtex);
-                    }
-                } else {
-                    skipped += 1;
+    if command == Command::Lint {
+        return match lint_citations(&start_dir, &opt) {
+            Ok(report) => {
+                diagnostics::report("lint", &report, &report.render());
+                match report.findings.is_empty() {
+                    true => ExitCode::SUCCESS,
+                    false => ExitCode::from(EXIT_FAILURE),
                 }
             }
-            Err(e) => {
-                println!("Warning: Error processing {:?}: {}", cargo_path, e);
-                println!("         Skipping this file.");
-                skipped += 1;
-            }
-        }
+            Err(e) => fail(e),
+        };
     }
 
-    // Write combined dependencies to a single file
-    if opt.dependencies && !all_dependencies.is_empty() {
-        let output_file = if let Some(o) = &opt.filename {
-            o.clone()
-        } else {
-            String::from("DEPENDENCIES.bib")
+    if command == Command::HookInstall {
+        // The hook checks the files generated with the other options given
+        let hook_args: Vec<String> = args.iter()
+            .filter(|arg| !opt.command.contains(arg) && !matches!(arg.as_str(), "-o" | "--overwrite"))
+            .cloned()
+            .collect();
+        return match install_hook(&start_dir, &hook_args, &opt) {
+            Ok(path) => {
+                diagnostics::info(format!("Installed a pre-commit hook at {:?}", path));
+                ExitCode::SUCCESS
+            }
+            Err(e) => fail(e),
         };
+    }
 
-        if output_file == "STDOUT" {
-            print!("{}", all_dependencies);
-        } else {
-            let file_path = start_dir.join(&output_file);
-            if file_path.exists() && !opt.overwrite {
-                println!("Note: Dependencies citation file already exists at {:?}.", &file_path);
-                println!("      Use --overwrite to replace it.");
-            } else {
-                fs::write(&file_path, all_dependencies.as_bytes())?;
-                println!("Created combined dependencies citation file at {:?}", file_path);
+    if command == Command::Joss {
+        if let Err(e) = joss(&start_dir, &opt, &fetcher).await {
+            return fail(e);
+        }
+        return ExitCode::SUCCESS;
+    }
+    if command == Command::Crate {
+        return match cite_crate(&opt.command[1], &start_dir, &opt, &fetcher).await {
+            Ok(citation) => {
+                print!("{}", citation);
+                ExitCode::SUCCESS
             }
+            Err(e) => fail(e),
+        };
+    }
+    if command == Command::Notice {
+        if let Err(e) = notice(&start_dir, &opt, &fetcher).await {
+            return fail(e);
         }
+        return ExitCode::SUCCESS;
     }
 
-    if processed > 0 || skipped > 0 {
-        println!("\nSummary:");
-        if processed > 0 {
-            println!("- Successfully processed: {} file{}", 
-                processed,
-                if processed == 1 { "" } else { "s" }
-            );
-        }
-        if skipped > 0 {
-            println!("- Skipped due to errors: {} file{}", 
-                skipped,
-                if skipped == 1 { "" } else { "s" }
-            );
+    // The deposited package's citation files are then regenerated with the new
+    // DOI, which doi.org does not resolve yet
+    let mut path = manifest_path.unwrap_or_else(|| start_dir.clone());
+    if command == Command::Deposit {
+        match deposit(&start_dir, &opt, &fetcher).await {
+            Ok(doi) => {
+                diagnostics::info(format!("Deposited with DOI {}", doi));
+                diagnostics::note(format!("Add doi = \"{}\" under [package] in cargo-cite.toml to keep citing it.", doi));
+                opt.doi = Some(doi);
+                opt.overwrite = true;
+                opt.no_doi_bibtex = true;
+                path = start_dir.join(CARGO_FILE);
+            }
+            Err(e) => return fail(e),
         }
     }
-    Ok(())
+
+    if opt.watch && command == Command::Generate && !opt.recommend {
+        // Regenerated files replace those of the previous run
+        opt.overwrite = true;
+        return watch_citations(&path, &start_dir, &opt).await;
+    }
+    let result = if matches!(command, Command::Stats | Command::Coverage) || opt.recommend {
+        collect_citations(&path, &opt, &fetcher).await
+    } else {
+        generate_citations(&path, &opt).await
+    };
+    let generation = match result {
+        Ok(generation) => generation,
+        Err(e) => return fail(e),
+    };
+
+    if command == Command::Stats {
+        let stats = stats::DependencyStats::collect(&generation.dependencies);
+        diagnostics::report("stats", &stats, &stats.render());
+        return status(&opt, &generation);
+    }
+    if command == Command::Coverage {
+        let report = coverage::CoverageReport::collect(&generation.dependencies);
+        diagnostics::report("coverage", &report, &report.render());
+        return status(&opt, &generation);
+    }
+    if opt.recommend {
+        let report = recommend::Recommendations::collect(&fetcher, &generation.dependencies, &generation.dependencies_file).await;
+        diagnostics::report("recommendations", &report, &report.render());
+        return status(&opt, &generation);
+    }
+
+    if opt.license_summary {
+        let summary = licenses::LicenseSummary::collect(&generation.cited);
+        diagnostics::report("licenses", &summary, &summary.render());
+    }
+
+    diagnostics::summary(generation.processed, generation.skipped);
+    status(&opt, &generation)
 }
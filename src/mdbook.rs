@@ -0,0 +1,73 @@
+//! A bibliography chapter for books built with mdBook.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The chapter, in the book's source directory.
+pub const CHAPTER: &str = "citations.md";
+pub const SUMMARY: &str = "SUMMARY.md";
+const BOOK_FILE: &str = "book.toml";
+const DEFAULT_SRC: &str = "src";
+
+/// The source directory of the book in `book_dir`: `src` under `[book]` in
+/// book.toml, by default `src`.
+pub fn src_dir(book_dir: &Path) -> Result<PathBuf, String> {
+    let path = book_dir.join(BOOK_FILE);
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("No mdBook in {:?}: could not read {:?}: {}", book_dir, path, e))?;
+    let book: toml::Value = toml::from_str(&content).map_err(|e| format!("Invalid {:?}: {}", path, e))?;
+    let src = book.get("book")
+        .and_then(|book| book.get("src"))
+        .and_then(toml::Value::as_str)
+        .unwrap_or(DEFAULT_SRC);
+    Ok(book_dir.join(src))
+}
+
+/// `summary`, the content of SUMMARY.md, linking to the chapter after the
+/// others. `None` if it already links to it.
+pub fn link_chapter(summary: &str) -> Option<String> {
+    if summary.split("](").skip(1).any(links_chapter) {
+        return None;
+    }
+    // A suffix chapter, which may follow both numbered and suffix chapters
+    let separator = if summary.is_empty() || summary.ends_with('\n') { "\n" } else { "\n\n" };
+    Some(format!("{}{}[Citations]({})\n", summary, separator, CHAPTER))
+}
+
+/// Whether the link target at the start of `link`, the text after `](`,
+/// is the chapter, written as `citations.md`, `./citations.md` or
+/// `<citations.md>`, and with or without an anchor.
+fn links_chapter(link: &str) -> bool {
+    let Some(end) = link.find(')') else { return false };
+    let target = link[..end].trim();
+    let target = target.strip_prefix('<').and_then(|t| t.strip_suffix('>')).unwrap_or(target);
+    let target = target.split('#').next().unwrap_or_default();
+    target.trim_start_matches("./") == CHAPTER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_chapter_appends_a_suffix_chapter() {
+        let summary = "# Summary\n\n- [Intro](intro.md)\n";
+        assert_eq!(link_chapter(summary).unwrap(), "# Summary\n\n- [Intro](intro.md)\n\n[Citations](citations.md)\n");
+    }
+
+    #[test]
+    fn link_chapter_ends_a_summary_without_a_newline() {
+        let summary = "# Summary\n\n- [Intro](intro.md)";
+        assert_eq!(link_chapter(summary).unwrap(), "# Summary\n\n- [Intro](intro.md)\n\n[Citations](citations.md)\n");
+    }
+
+    #[test]
+    fn link_chapter_is_idempotent() {
+        let linked = link_chapter("# Summary\n\n- [Intro](intro.md)\n").unwrap();
+        assert_eq!(link_chapter(&linked), None);
+        for summary in ["[References](./citations.md)\n", "- [Cite](<citations.md>)", "[Cite]( ./citations.md#serde )\n"] {
+            assert_eq!(link_chapter(summary), None, "{}", summary);
+        }
+        assert!(link_chapter("[Old](old/citations.md)\n").is_some());
+    }
+}
@@ -0,0 +1,142 @@
+//! `cargo cite notice`: a NOTICE file crediting every dependency with how to
+//! cite it, its license and its copyright holders, serving academic credit
+//! and license attribution at once.
+
+use crate::entry::CitationEntry;
+use crate::formats::style::{Style, Styled};
+use crate::formats::Formatter;
+use crate::person_name;
+use std::fs;
+use std::path::Path;
+
+pub const NOTICE_FILE: &str = "NOTICE";
+
+/// Files of a crate source whose copyright lines are credited, by prefix of
+/// their lowercase name.
+const LICENSE_FILES: &[&str] = &["license", "licence", "copying", "copyright", "notice"];
+
+const RULE: &str = "--------------------------------------------------------------------------------";
+
+/// Copyright lines of the license files in the crate source `dir`. Template
+/// lines such as `Copyright [yyyy] [name of copyright owner]` carry no year
+/// and are left out.
+pub fn copyright_lines(dir: &Path) -> Vec<String> {
+    let Ok(files) = fs::read_dir(dir) else { return Vec::new() };
+    let mut paths: Vec<_> = files.filter_map(Result::ok)
+        .map(|file| file.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+            LICENSE_FILES.iter().any(|prefix| name.starts_with(prefix))
+        })
+        .collect();
+    paths.sort();
+    let mut lines: Vec<String> = Vec::new();
+    for text in paths.iter().filter_map(|path| fs::read_to_string(path).ok()) {
+        for line in text.lines().map(str::trim) {
+            let notice = (line.starts_with("Copyright") || line.starts_with('©')) && line.contains(|c: char| c.is_ascii_digit());
+            if notice && !lines.iter().any(|l| l == line) {
+                lines.push(line.to_string());
+            }
+        }
+    }
+    lines
+}
+
+/// `name version`, or the name of an unversioned crate.
+fn heading(entry: &CitationEntry) -> String {
+    match &entry.version {
+        Some(version) => format!("{} {}", entry.name, version),
+        None => entry.name.clone(),
+    }
+}
+
+/// Who holds the copyright of `entry`: the notices of its license files,
+/// else its authors.
+fn holders(entry: &CitationEntry) -> Vec<String> {
+    let lines = entry.local_source.as_deref().map(copyright_lines).unwrap_or_default();
+    if !lines.is_empty() {
+        return lines;
+    }
+    match entry.authors.as_slice() {
+        [] => vec![String::from("Copyright holders unknown")],
+        authors => vec![format!("Copyright the {} authors: {}", entry.name, authors.iter().map(|a| person_name(a)).collect::<Vec<_>>().join(", "))],
+    }
+}
+
+/// The NOTICE of `package`, crediting each of `dependencies` with a
+/// reference in `style`.
+pub fn document(package: &CitationEntry, dependencies: &[CitationEntry], style: Style) -> String {
+    let mut text = format!("{}\n", heading(package));
+    if let Some(license) = package.license_text() {
+        text.push_str(&format!("Licensed under {}\n", license));
+    }
+    text.push_str(&format!(
+        "\n{} includes or builds on the software below. Each entry gives how to cite\nit, its license and its copyright holders.\n",
+        package.name,
+    ));
+    let styled = Styled(style);
+    for (i, entry) in dependencies.iter().enumerate() {
+        text.push_str(&format!("\n{}\n\n{}\n", RULE, heading(entry)));
+        text.push_str(&format!("License: {}\n", entry.license_text().as_deref().unwrap_or("unknown")));
+        for line in holders(entry) {
+            text.push_str(&format!("{}\n", line));
+        }
+        if let Some(reference) = styled.entry(entry, i + 1) {
+            text.push_str(&format!("Cite as: {}\n", reference));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::EntryKind;
+    use crate::PackageInfo;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    fn entry(manifest: &str) -> CitationEntry {
+        let package: PackageInfo = toml::from_str(manifest).unwrap();
+        let mut entry = CitationEntry::from_package(&package, &package.name, PathBuf::from("Cargo.toml"), NaiveDate::default());
+        entry.kind = EntryKind::Dependency;
+        entry
+    }
+
+    #[test]
+    fn copyright_lines_skip_templates_and_repeats() {
+        let dir = std::env::temp_dir().join(format!("cargo-cite-notice-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("LICENSE-MIT"), "MIT License\n\n  Copyright (c) 2020 Jane Doe\n").unwrap();
+        fs::write(dir.join("LICENSE-APACHE"), "Copyright [yyyy] [name of copyright owner]\nCopyright (c) 2020 Jane Doe\n© 2021 Acme\n").unwrap();
+        fs::write(dir.join("README.md"), "Copyright 2019 Someone Else\n").unwrap();
+        let lines = copyright_lines(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(lines, ["Copyright (c) 2020 Jane Doe", "© 2021 Acme"]);
+    }
+
+    #[test]
+    fn holders_fall_back_to_authors() {
+        let credited = entry("name = \"rand\"\nversion = \"0.8.5\"\nauthors = [\"Jane Doe <jane@example.com>\", \"John Roe\"]");
+        assert_eq!(holders(&credited), ["Copyright the rand authors: Jane Doe, John Roe"]);
+        let anonymous = entry("name = \"rand\"\nversion = \"0.8.5\"");
+        assert_eq!(holders(&anonymous), ["Copyright holders unknown"]);
+    }
+
+    #[test]
+    fn document_gives_licenses() {
+        let mut package = entry("name = \"demo\"\nversion = \"1.2.0\"\nlicense = \"MIT OR Apache-2.0\"");
+        package.kind = EntryKind::Package;
+        let dependencies = [
+            entry("name = \"serde\"\nversion = \"1.0.0\"\nlicense = \"MIT OR Apache-2.0\"\nauthors = [\"Jane Doe\"]"),
+            entry("name = \"ring\"\nversion = \"0.17.0\"\nlicense-file = \"LICENSE\""),
+            entry("name = \"odd\"\nversion = \"0.1.0\""),
+        ];
+        let text = document(&package, &dependencies, Style::Apa);
+        assert!(text.starts_with("demo 1.2.0\nLicensed under MIT OR Apache-2.0\n"));
+        assert!(text.contains("\nserde 1.0.0\nLicense: MIT OR Apache-2.0\nCopyright the serde authors: Jane Doe\n"));
+        assert!(text.contains("\nring 0.17.0\nLicense: See LICENSE\nCopyright holders unknown\n"));
+        assert!(text.contains("\nodd 0.1.0\nLicense: unknown\n"));
+    }
+}
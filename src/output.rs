@@ -0,0 +1,110 @@
+//! Writing of generated files, which `--check` compares with those on disk
+//! and `--dry-run` only describes.
+
+use crate::codes::Code;
+use crate::diagnostics::{self, Verbosity};
+use crate::CitationOptions;
+use serde::Serialize;
+use similar::TextDiff;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Files found missing or out of date by `check`.
+static STALE: AtomicUsize = AtomicUsize::new(0);
+
+/// The changes to a file, reported with reason `diff`.
+#[derive(Serialize)]
+struct FileDiff<'a> {
+    path: &'a Path,
+    diff: &'a str,
+}
+
+/// Report a unified diff of the changes from `old` to `new` in `path`.
+pub fn report_diff(old: &str, new: &str, path: &Path) {
+    let diff = diff(old, new, path);
+    diagnostics::report("diff", &FileDiff { path, diff: &diff }, &diff);
+}
+
+/// A unified diff turning `old` into `new`, both contents of `path`.
+fn diff(old: &str, new: &str, path: &Path) -> String {
+    let name = path.display().to_string();
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .header(&name, &name)
+        .to_string()
+}
+
+/// Compare `content` with the file at `path`, reporting a missing file or a
+/// diff of what regenerating it would change. Returns whether it is up to date.
+fn check(path: &Path, content: &str) -> bool {
+    match fs::read_to_string(path) {
+        Ok(current) if current == content => {
+            diagnostics::info(format!("{:?} is up to date", path));
+            return true;
+        }
+        Ok(current) => {
+            diagnostics::error_in(Code::OutOfDate, path, format!("{:?} is out of date:", path));
+            report_diff(&current, content, path);
+        }
+        Err(_) => diagnostics::error_in(Code::OutOfDate, path, format!("{:?} is missing.", path)),
+    }
+    STALE.fetch_add(1, Ordering::Relaxed);
+    false
+}
+
+/// What `write` did with a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Written,
+    /// An existing file kept for lack of `--overwrite`
+    Kept,
+    /// Left alone as it already has the content
+    Unchanged,
+    /// Only compared or described, with `--check` or `--dry-run`
+    Previewed,
+}
+
+/// Write `content` to the file at `path`, the `what` in messages, keeping
+/// an existing file unless `replace`. Replacing a file prints a diff of the
+/// changes, and a file that already has `content` is not rewritten. With
+/// `--check` the file is compared with `content` instead, and with
+/// `--dry-run` the change is only described.
+pub(crate) fn write(path: &Path, content: &str, what: &str, replace: bool, options: &CitationOptions) -> io::Result<Outcome> {
+    if options.check {
+        check(path, content);
+        return Ok(Outcome::Previewed);
+    }
+    if path.exists() && !replace {
+        diagnostics::note(format!("The {} already exists at {:?}.\nUse --overwrite to replace it.", what, path));
+        return Ok(Outcome::Kept);
+    }
+    // Rewriting an identical file would only touch its modification time
+    let current = fs::read_to_string(path).ok();
+    if current.as_deref() == Some(content) {
+        diagnostics::info(format!("The {} at {:?} is up to date", what, path));
+        return Ok(Outcome::Unchanged);
+    }
+    match &current {
+        Some(current) if options.dry_run || diagnostics::verbosity() > Verbosity::Quiet => {
+            diagnostics::info(format!("{} the {} at {:?}:", if options.dry_run { "Would overwrite" } else { "Overwriting" }, what, path));
+            report_diff(current, content, path);
+        }
+        None if options.dry_run => {
+            diagnostics::info(format!("Would create the {} at {:?}:", what, path));
+            report_diff("", content, path);
+        }
+        _ => {}
+    }
+    if options.dry_run {
+        return Ok(Outcome::Previewed);
+    }
+    fs::write(path, content)?;
+    Ok(Outcome::Written)
+}
+
+/// The number of files `check` found missing or out of date.
+pub fn stale_count() -> usize {
+    STALE.load(Ordering::Relaxed)
+}
@@ -0,0 +1,65 @@
+//! Progress bars on stderr for long runs: the manifests processed and the
+//! dependencies whose metadata is being fetched. They are only drawn when
+//! stderr is a terminal and messages are human-readable; otherwise the
+//! messages printed along the way are the only feedback.
+
+use crate::diagnostics::{self, MessageFormat};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+fn bars() -> &'static MultiProgress {
+    BARS.get_or_init(|| {
+        let visible = io::stderr().is_terminal() && diagnostics::message_format() == MessageFormat::Human;
+        MultiProgress::with_draw_target(if visible { ProgressDrawTarget::stderr() } else { ProgressDrawTarget::hidden() })
+    })
+}
+
+/// Run `print` with the progress bars hidden, so that messages printed
+/// meanwhile do not garble them.
+pub fn suspend<R>(print: impl FnOnce() -> R) -> R {
+    match BARS.get() {
+        Some(bars) => bars.suspend(print),
+        None => print(),
+    }
+}
+
+/// A bar counting up to a known total, removed once dropped.
+pub struct Progress {
+    bar: ProgressBar,
+    /// Items started but not finished
+    in_flight: AtomicUsize,
+}
+
+impl Progress {
+    /// A bar for `total` items of `what`, such as "manifests".
+    pub fn new(total: usize, what: &str) -> Self {
+        let style = ProgressStyle::with_template(&format!("{{bar:30}} {{pos}}/{{len}} {} {{msg}}", what))
+            .unwrap_or_else(|_| ProgressStyle::default_bar());
+        let bar = bars().add(ProgressBar::new(total as u64).with_style(style));
+        Progress { bar, in_flight: AtomicUsize::new(0) }
+    }
+
+    /// Record that work on an item started.
+    pub fn start(&self) {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.bar.set_message(format!("({} in flight)", in_flight));
+    }
+
+    /// Record that an item is done.
+    pub fn finish(&self) {
+        let in_flight = self.in_flight.fetch_sub(1, Ordering::Relaxed).saturating_sub(1);
+        self.bar.set_message(if in_flight > 0 { format!("({} in flight)", in_flight) } else { String::new() });
+        self.bar.inc(1);
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        bars().remove(&self.bar);
+    }
+}
@@ -0,0 +1,351 @@
+//! The "Citing" section that cargo-cite maintains in README files.
+
+use crate::entry::CitationEntry;
+use chrono::Datelike;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const START_MARKER: &str = "<!-- cargo-cite:start -->";
+pub const END_MARKER: &str = "<!-- cargo-cite:end -->";
+/// The markers as reStructuredText comments, which HTML comments are not.
+const RST_START_MARKER: &str = ".. cargo-cite:start";
+const RST_END_MARKER: &str = ".. cargo-cite:end";
+
+/// Markers around the badge, inline in a Markdown badge row.
+const BADGE_START_MARKER: &str = "<!-- cargo-cite:badge:start -->";
+const BADGE_END_MARKER: &str = "<!-- cargo-cite:badge:end -->";
+/// The badge markers as reStructuredText comments, on lines of their own.
+const RST_BADGE_START_MARKER: &str = ".. cargo-cite:badge:start";
+const RST_BADGE_END_MARKER: &str = ".. cargo-cite:badge:end";
+
+/// Text of the "Citing" section when no template is configured.
+pub const DEFAULT_TEMPLATE: &str = "## Citing
+
+If you found this software useful consider citing it. See {{citation_file}} for the recommended BibTeX entry.";
+
+/// `DEFAULT_TEMPLATE` in reStructuredText.
+const RST_DEFAULT_TEMPLATE: &str = "Citing
+------
+
+If you found this software useful consider citing it. See {{citation_file}} for the recommended BibTeX entry.";
+
+const README_NAMES: &[&str] = &["README.md", "README.rst", "README.txt", "README"];
+/// READMEs the section is added to, whose markup hides the markers.
+const SECTION_README_NAMES: &[&str] = &["README.md", "README.rst"];
+
+/// The start and end markers for the README at `path`.
+fn markers(path: &Path) -> (&'static str, &'static str) {
+    if is_rst(path) { (RST_START_MARKER, RST_END_MARKER) } else { (START_MARKER, END_MARKER) }
+}
+
+fn is_rst(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rst")
+}
+
+/// The template of the section in the README at `path` when none is
+/// configured.
+pub fn default_template(path: &Path) -> &'static str {
+    if is_rst(path) { RST_DEFAULT_TEMPLATE } else { DEFAULT_TEMPLATE }
+}
+
+/// Wrap `content` in the cargo-cite markers for the README at `path`.
+pub fn wrap_section(content: &str, path: &Path) -> String {
+    let (start, end) = markers(path);
+    // Comments in reStructuredText end at a blank line
+    let blank = if is_rst(path) { "\n" } else { "" };
+    format!("{}\n{}{}\n{}{}\n", start, blank, content.trim_matches('\n'), blank, end)
+}
+
+/// `readme`, the content of the README at `path`, with `section` between
+/// the markers: in place of the previous section if there is one, else at
+/// the end.
+pub fn insert_section(readme: &str, path: &Path, section: &str) -> String {
+    let wrapped = wrap_section(section, path);
+    let (start_marker, end_marker) = markers(path);
+    if let Some(start) = readme.find(start_marker) {
+        if let Some(end) = readme[start..].find(end_marker).map(|end| start + end + end_marker.len()) {
+            let rest = readme[end..].strip_prefix('\n').unwrap_or(&readme[end..]);
+            return format!("{}{}{}", &readme[..start], wrapped, rest);
+        }
+    }
+    let separator = match readme {
+        "" => "",
+        _ if readme.ends_with('\n') => "\n",
+        _ => "\n\n",
+    };
+    format!("{}{}{}", readme, separator, wrapped)
+}
+
+/// A badge for the README at `path`: the package's DOI badge, or a "Cite"
+/// badge linking to `citation_file` when it has no DOI.
+pub fn badge(package: &CitationEntry, path: &Path, citation_file: &str) -> String {
+    if let Some(doi) = &package.doi {
+        return doi_badge(doi, path);
+    }
+    // Dashes and underscores are separators in shields.io badge paths
+    let label = citation_file.replace('-', "--").replace('_', "__");
+    let image = format!("https://img.shields.io/badge/cite-{}-blue", label);
+    match is_rst(path) {
+        true => format!(".. image:: {}\n   :alt: Cite\n   :target: {}", image, citation_file),
+        false => format!("[![Cite]({})]({})", image, citation_file),
+    }
+}
+
+fn doi_badge(doi: &str, path: &Path) -> String {
+    match is_rst(path) {
+        true => format!(".. image:: https://zenodo.org/badge/DOI/{doi}.svg\n   :alt: DOI\n   :target: https://doi.org/{doi}"),
+        false => format!("[![DOI](https://zenodo.org/badge/DOI/{doi}.svg)](https://doi.org/{doi})"),
+    }
+}
+
+/// `readme`, the content of the README at `path`, with `badge` between the
+/// badge markers: in place of the previous badge if there is one, else at
+/// the end of the badge row under the title, or in a new row there.
+pub fn insert_badge(readme: &str, path: &Path, badge: &str) -> String {
+    let (start_marker, end_marker) = match is_rst(path) {
+        true => (RST_BADGE_START_MARKER, RST_BADGE_END_MARKER),
+        false => (BADGE_START_MARKER, BADGE_END_MARKER),
+    };
+    let wrapped = match is_rst(path) {
+        true => format!("{}\n\n{}\n\n{}", start_marker, badge, end_marker),
+        false => format!("{}{}{}", start_marker, badge, end_marker),
+    };
+    if let Some(start) = readme.find(start_marker) {
+        if let Some(end) = readme[start..].find(end_marker).map(|end| start + end + end_marker.len()) {
+            return format!("{}{}{}", &readme[..start], wrapped, &readme[end..]);
+        }
+    }
+
+    let lines: Vec<&str> = readme.split_inclusive('\n').collect();
+    let mut after_title = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let text = line.trim();
+        let next = lines.get(i + 1).map_or("", |l| l.trim());
+        if !is_rst(path) && (text.starts_with("[![") || text.starts_with("![")) {
+            return format!("{}{} {}\n{}", lines[..i].concat(), line.trim_end(), wrapped, lines[i + 1..].concat());
+        }
+        if text.is_empty() || text.starts_with('<') {
+            continue;
+        }
+        if after_title == 0 && !is_rst(path) && text.starts_with('#') {
+            after_title = i + 1;
+            continue;
+        }
+        if after_title == 0 && is_rst(path) && is_underline(next) {
+            after_title = i + 2;
+            continue;
+        }
+        // An overline above the title
+        if after_title == 0 && is_rst(path) && is_underline(text) {
+            continue;
+        }
+        break;
+    }
+    let (head, rest) = (lines[..after_title].concat(), lines[after_title..].concat());
+    let head = match head.as_str() {
+        "" => head,
+        _ => format!("{}\n", head),
+    };
+    let separator = if rest.is_empty() || rest.starts_with('\n') { "" } else { "\n" };
+    format!("{}{}\n{}{}", head, wrapped, separator, rest)
+}
+
+/// Whether `line` underlines a reStructuredText title.
+fn is_underline(line: &str) -> bool {
+    let mut chars = line.chars();
+    chars.next().is_some_and(|c| c.is_ascii_punctuation() && line.len() >= 3 && chars.all(|d| d == c))
+}
+
+/// Files other than the README that describe how to cite the package.
+fn is_citation_file(name: &str) -> bool {
+    name.starts_with("CITATION.") || name == "codemeta.json"
+}
+
+/// Links to the citation files next to the README at `path`, in its markup.
+fn citation_links(path: &Path) -> String {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut names: Vec<String> = fs::read_dir(dir).into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_citation_file(name))
+        .collect();
+    names.sort();
+    let links: Vec<String> = names.iter()
+        .map(|name| match is_rst(path) {
+            true => format!("`{} <{}>`_", name, name),
+            false => format!("[{}]({})", name, name),
+        })
+        .collect();
+    links.join(" · ")
+}
+
+/// The text of the "Citing" section of the README at `path` for `package`,
+/// rendered from `template` or the default one for the README. `bibtex` is
+/// the package's BibTeX entry, when it is known.
+pub fn section(package: &CitationEntry, template: Option<&str>, path: &Path, citation_file: &str, bibtex: Option<&str>) -> Result<String, String> {
+    let join = |items: &[String]| Some(items.join(", ")).filter(|s| !s.is_empty());
+    let names = |people: &[String]| people.iter().map(|p| crate::person_name(p).to_string()).collect::<Vec<_>>();
+    let doi_badge = package.doi.as_ref().map(|doi| doi_badge(doi, path));
+    let year = package.date.map(|date| date.year().to_string());
+    render_template(template.unwrap_or(default_template(path)), &[
+        ("name", Some(&package.name)),
+        ("version", package.version.as_deref()),
+        ("description", package.description.as_deref()),
+        ("authors", join(&names(&package.authors)).as_deref()),
+        ("maintainers", join(&names(&package.maintainers)).as_deref()),
+        ("license", package.license.as_deref()),
+        ("repository", package.repository.as_deref()),
+        ("keywords", join(&package.keywords).as_deref()),
+        ("year", year.as_deref()),
+        ("doi", package.doi.as_deref()),
+        ("doi_badge", doi_badge.as_deref()),
+        ("key", Some(&package.key)),
+        ("citation_file", Some(citation_file)),
+        ("citation_links", Some(&citation_links(path))),
+        ("bibtex", bibtex.map(str::trim_end)),
+    ])
+}
+
+/// Replace each `{{variable}}` in `template` with its value from `vars`.
+/// Variables without a value render as empty text; unknown ones are an error.
+pub fn render_template(template: &str, vars: &[(&str, Option<&str>)]) -> Result<String, String> {
+    let mut rendered = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}")
+            .ok_or_else(|| String::from("unterminated {{ in README template"))?;
+        let name = rest[start + 2..start + end].trim();
+        let value = vars.iter()
+            .find(|(var, _)| *var == name)
+            .ok_or_else(|| format!("unknown README template variable {{{{{}}}}} (expected one of: {})",
+                name,
+                vars.iter().map(|(var, _)| *var).collect::<Vec<_>>().join(", ")))?
+            .1;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(value.unwrap_or_default());
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+pub fn find_readme(dir: &Path) -> Option<PathBuf> {
+    README_NAMES.iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// The README in `dir` the "Citing" section is added to.
+pub fn find_section_readme(dir: &Path) -> Option<PathBuf> {
+    SECTION_README_NAMES.iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+/// Text between the cargo-cite markers, if present.
+pub fn extract_section(readme: &str) -> Option<&str> {
+    [(START_MARKER, END_MARKER), (RST_START_MARKER, RST_END_MARKER)].iter().find_map(|(start_marker, end_marker)| {
+        let start = readme.find(start_marker)? + start_marker.len();
+        let end = start + readme[start..].find(end_marker)?;
+        Some(&readme[start..end])
+    })
+}
+
+/// Fenced ```bibtex code blocks in `text`.
+fn bibtex_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+    for line in text.lines() {
+        let fence = line.trim_start();
+        match current.as_mut() {
+            None if fence.starts_with("```bib") => {
+                current = Some(String::new());
+            }
+            Some(block) if fence.starts_with("```") => {
+                blocks.push(std::mem::take(block));
+                current = None;
+            }
+            Some(block) => {
+                block.push_str(line);
+                block.push('\n');
+            }
+            None => {}
+        }
+    }
+    blocks
+}
+
+/// Check the README section against the citation file. BibTeX blocks in the
+/// section must match `citation`; a section without one must match
+/// `expected_section`. Returns a description of each mismatch.
+pub fn verify(readme: &str, expected_section: &str, citation: Option<&str>) -> Vec<String> {
+    let section = match extract_section(readme) {
+        Some(section) => section,
+        None => return vec![format!("no section between {} and {} markers", START_MARKER, END_MARKER)],
+    };
+
+    let blocks = bibtex_blocks(section);
+    if blocks.is_empty() {
+        let expected = extract_section(expected_section).unwrap_or(expected_section);
+        if section.trim() != expected.trim() {
+            return vec![String::from("the citing text differs from the one cargo-cite generates")];
+        }
+        return Vec::new();
+    }
+
+    let citation = match citation {
+        Some(citation) => citation.trim(),
+        None => return vec![String::from("the README embeds BibTeX but the citation file is missing")],
+    };
+    blocks.iter()
+        .enumerate()
+        .filter(|(_, block)| block.trim() != citation)
+        .map(|(i, _)| format!("BibTeX block {} differs from the citation file", i + 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD: &str = "README.md";
+
+    #[test]
+    fn insert_section_appends_then_replaces() {
+        let readme = "# demo\n\nSome text.\n";
+        let inserted = insert_section(readme, Path::new(MD), "## Citing\nOld\n");
+        assert_eq!(inserted, "# demo\n\nSome text.\n\n<!-- cargo-cite:start -->\n## Citing\nOld\n<!-- cargo-cite:end -->\n");
+        let replaced = insert_section(&format!("{}\n## License\n", inserted), Path::new(MD), "## Citing\nNew");
+        assert_eq!(replaced, "# demo\n\nSome text.\n\n<!-- cargo-cite:start -->\n## Citing\nNew\n<!-- cargo-cite:end -->\n\n## License\n");
+        assert_eq!(extract_section(&replaced), Some("\n## Citing\nNew\n"));
+    }
+
+    #[test]
+    fn insert_section_uses_rst_comments() {
+        let inserted = insert_section("demo\n====\n", Path::new("README.rst"), "Citing\n------");
+        assert_eq!(inserted, "demo\n====\n\n.. cargo-cite:start\n\nCiting\n------\n\n.. cargo-cite:end\n");
+        assert_eq!(insert_section(&inserted, Path::new("README.rst"), "Citing\n------"), inserted);
+    }
+
+    #[test]
+    fn insert_section_without_end_marker_appends() {
+        let readme = "<!-- cargo-cite:start -->\nunterminated\n";
+        assert_eq!(insert_section(readme, Path::new(MD), "x"), format!("{}\n<!-- cargo-cite:start -->\nx\n<!-- cargo-cite:end -->\n", readme));
+    }
+
+    #[test]
+    fn insert_badge_joins_badge_row_then_replaces() {
+        let readme = "# demo\n\n[![CI](ci.svg)](ci)\n\nText\n";
+        let inserted = insert_badge(readme, Path::new(MD), "[![Cite](a.svg)](CITATION.bib)");
+        assert_eq!(inserted, "# demo\n\n[![CI](ci.svg)](ci) <!-- cargo-cite:badge:start -->[![Cite](a.svg)](CITATION.bib)<!-- cargo-cite:badge:end -->\n\nText\n");
+        let replaced = insert_badge(&inserted, Path::new(MD), "[![DOI](b.svg)](doi)");
+        assert_eq!(replaced, inserted.replace("[![Cite](a.svg)](CITATION.bib)", "[![DOI](b.svg)](doi)"));
+    }
+
+    #[test]
+    fn insert_badge_starts_row_under_title() {
+        let inserted = insert_badge("# demo\n\nText\n", Path::new(MD), "[![Cite](a.svg)](CITATION.bib)");
+        assert_eq!(inserted, "# demo\n\n<!-- cargo-cite:badge:start -->[![Cite](a.svg)](CITATION.bib)<!-- cargo-cite:badge:end -->\n\nText\n");
+    }
+}
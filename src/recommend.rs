@@ -0,0 +1,120 @@
+//! `--recommend`: archival records and citation files of dependencies that
+//! the existing bibliography does not use yet.
+
+use crate::archive;
+use crate::bibtex;
+use crate::fetch::MetadataFetcher;
+use crate::ResolvedDependency;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecommendationKind {
+    Doi,
+    Swhid,
+    CitationFile,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Recommendation {
+    pub name: String,
+    pub key: String,
+    pub kind: RecommendationKind,
+    pub value: String,
+    /// Where the record was found: Zenodo, DataCite, Software Heritage or a
+    /// citation file
+    pub source: String,
+}
+
+impl Recommendation {
+    fn render(&self) -> String {
+        match self.kind {
+            RecommendationKind::Doi => format!("{}: cite DOI {} (from {})", self.name, self.value, self.source),
+            RecommendationKind::Swhid => format!("{}: add archive identifier {} (from {})", self.name, self.value, self.source),
+            RecommendationKind::CitationFile => format!("{}: use the entry from its own {}", self.name, self.value),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Recommendations {
+    /// Bibliography the records were compared against, if it exists
+    pub bibliography: Option<PathBuf>,
+    pub recommendations: Vec<Recommendation>,
+}
+
+/// Whether `entry` is still the plain entry generated from crates.io metadata.
+fn is_generated(entry: &str, name: &str) -> bool {
+    entry.contains(&format!("howpublished = {{https://crates.io/crates/{}}}", name))
+}
+
+impl Recommendations {
+    /// Look up archival records for each distinct dependency and keep those
+    /// missing from the bibliography at `bibliography`.
+    pub async fn collect(fetcher: &MetadataFetcher, dependencies: &[ResolvedDependency], bibliography: &Path) -> Self {
+        let existing = fs::read_to_string(bibliography).ok();
+        let mut recommendations = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        for dep in dependencies {
+            if !seen.insert(dep.name.as_str()) {
+                continue;
+            }
+            let info = archive::lookup(fetcher, dep).await;
+            let entry = existing.as_deref()
+                .and_then(|bib| bibtex::find_entry(bib, &dep.key))
+                .unwrap_or_default();
+            let recommend = |kind, value: &str, source: &str| Recommendation {
+                name: dep.name.clone(),
+                key: dep.key.clone(),
+                kind,
+                value: value.to_string(),
+                source: source.to_string(),
+            };
+            let citation_file_name = info.citation_file.as_ref()
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            if info.citation_entry(&dep.key).is_some() && (entry.is_empty() || is_generated(entry, &dep.name)) {
+                recommendations.push(recommend(RecommendationKind::CitationFile, &citation_file_name, &citation_file_name));
+            } else if let Some(doi) = info.doi() {
+                if !entry.to_lowercase().contains(&doi.to_lowercase()) {
+                    let source = match &info.record {
+                        Some(record) if info.citation_doi.is_none() => record.archive,
+                        _ => citation_file_name.as_str(),
+                    };
+                    recommendations.push(recommend(RecommendationKind::Doi, doi, source));
+                }
+            }
+            if let Some(swhid) = &info.swhid {
+                if !entry.contains(swhid.as_str()) {
+                    recommendations.push(recommend(RecommendationKind::Swhid, swhid, "Software Heritage"));
+                }
+            }
+        }
+
+        Recommendations {
+            bibliography: existing.is_some().then(|| bibliography.to_path_buf()),
+            recommendations,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        if self.recommendations.is_empty() {
+            return String::from("No unused archival records or citation files found.\n");
+        }
+        let mut text = match &self.bibliography {
+            Some(path) => format!("Not yet used in {:?}:\n", path),
+            None => String::from("Available for your dependencies:\n"),
+        };
+        for recommendation in &self.recommendations {
+            text.push_str(&format!("- {}\n", recommendation.render()));
+        }
+        text.push_str("\nAdopt them with: cargo cite --dependencies --adopt-recommended --overwrite\n");
+        text
+    }
+}
@@ -0,0 +1,100 @@
+//! Alternative registries, as configured for cargo in `.cargo/config.toml`
+//! and `$CARGO_HOME/credentials.toml`, or with `CARGO_REGISTRIES_<NAME>_*`
+//! environment variables. See https://doc.rust-lang.org/cargo/reference/registries.html.
+
+use crate::registry::cargo_home;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+struct CargoConfig {
+    #[serde(default)]
+    registries: BTreeMap<String, RegistryConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RegistryConfig {
+    index: Option<String>,
+    token: Option<String>,
+}
+
+/// A registry other than crates.io that dependencies are published on.
+#[derive(Debug, Clone)]
+pub struct AltRegistry {
+    pub name: String,
+    /// Index URL, prefixed with `sparse+` for sparse indexes
+    pub index: String,
+    /// Token cargo authenticates to the registry with
+    pub token: Option<String>,
+}
+
+impl AltRegistry {
+    /// The URL of the index, if it is a sparse index rather than a git
+    /// repository.
+    pub fn sparse_index(&self) -> Option<&str> {
+        self.index.strip_prefix("sparse+").map(|url| url.trim_end_matches('/'))
+    }
+
+    /// Whether packages with `source` in Cargo.lock or `cargo metadata`, such
+    /// as `sparse+https://…/`, come from this registry.
+    pub fn is_source(&self, source: &str) -> bool {
+        let bare = |url: &str| url.trim_start_matches("sparse+").trim_start_matches("registry+").trim_end_matches('/').to_string();
+        bare(source) == bare(&self.index)
+    }
+}
+
+/// Configuration files cargo reads for a package in `dir`, most specific
+/// first.
+fn config_files(dir: &Path) -> Vec<PathBuf> {
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    dir.ancestors()
+        .map(|d| d.join(".cargo"))
+        .chain(cargo_home())
+        .flat_map(|d| [d.join("config.toml"), d.join("config")])
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+fn read_config(path: &Path) -> Option<CargoConfig> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+/// `CARGO_REGISTRIES_<NAME>_<KEY>`, as cargo names it for `name`.
+fn env_var(name: &str, key: &str) -> Option<String> {
+    let var = format!("CARGO_REGISTRIES_{}_{}", name.to_uppercase().replace('-', "_"), key);
+    env::var(var).ok().filter(|v| !v.is_empty())
+}
+
+/// The registry `name` as configured for a package in `dir`. An index URL
+/// such as `sparse+https://…/` names a registry that is not configured.
+pub fn find(name: &str, dir: &Path) -> Option<AltRegistry> {
+    if name.contains("://") {
+        let index = name.trim_start_matches("registry+").to_string();
+        return Some(AltRegistry { name: name.to_string(), index, token: None });
+    }
+    let configs: Vec<CargoConfig> = config_files(dir).iter().filter_map(|p| read_config(p)).collect();
+    let configured = |key: fn(&RegistryConfig) -> Option<&String>| {
+        configs.iter().find_map(|c| c.registries.get(name).and_then(key).cloned())
+    };
+    let index = env_var(name, "INDEX").or_else(|| configured(|r| r.index.as_ref()))?;
+    let credentials = cargo_home().into_iter()
+        .flat_map(|home| [home.join("credentials.toml"), home.join("credentials")])
+        .find_map(|p| read_config(&p)?.registries.remove(name)?.token);
+    let token = env_var(name, "TOKEN").or(credentials).or_else(|| configured(|r| r.token.as_ref()));
+    Some(AltRegistry { name: name.to_string(), index, token })
+}
+
+/// The configured registry packages with `source` come from, for a package
+/// in `dir`.
+pub fn by_source(source: &str, dir: &Path) -> Option<AltRegistry> {
+    let names: Vec<String> = config_files(dir).iter()
+        .filter_map(|p| read_config(p))
+        .flat_map(|c| c.registries.into_keys())
+        .collect();
+    names.iter()
+        .filter_map(|name| find(name, dir))
+        .find(|registry| registry.is_source(source))
+}
@@ -0,0 +1,149 @@
+//! Crate sources already downloaded by cargo under `~/.cargo/registry/src/`,
+//! and git dependencies checked out under `~/.cargo/git/checkouts/`.
+
+use crate::fetch::CrateInfo;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct SourceManifest {
+    package: SourcePackage,
+}
+
+#[derive(Debug, Deserialize)]
+struct SourcePackage {
+    description: Option<String>,
+    #[serde(default)]
+    authors: Vec<String>,
+    repository: Option<String>,
+    homepage: Option<String>,
+    license: Option<String>,
+    #[serde(rename = "license-file")]
+    license_file: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    categories: Vec<String>,
+    /// A path, or a boolean enabling/disabling the default README
+    readme: Option<toml::Value>,
+}
+
+/// Citation files a crate may ship, in order of preference.
+pub const CITATION_FILES: &[&str] = &["CITATION.cff", "CITATION.bib"];
+
+/// Citation files present in a downloaded crate source.
+pub fn citation_files(dir: &Path) -> Vec<PathBuf> {
+    CITATION_FILES.iter()
+        .map(|name| dir.join(name))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// `$CARGO_HOME`, defaulting to `~/.cargo`.
+pub fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).map(|h| PathBuf::from(h).join(".cargo")))
+}
+
+/// Directory of the newest downloaded version of `name` that satisfies
+/// `requirement`, across all registries.
+pub fn find_source(name: &str, requirement: Option<&str>) -> Option<PathBuf> {
+    let req = match requirement {
+        Some(r) => VersionReq::parse(r).ok()?,
+        None => VersionReq::STAR,
+    };
+    let prefix = format!("{}-", name);
+    let src = cargo_home()?.join("registry").join("src");
+
+    let mut best: Option<(Version, PathBuf)> = None;
+    for registry in fs::read_dir(src).ok()?.flatten() {
+        let Ok(entries) = fs::read_dir(registry.path()) else { continue };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name();
+            let Some(version) = file_name.to_str()
+                .and_then(|n| n.strip_prefix(&prefix))
+                .and_then(|v| Version::parse(v).ok()) else { continue };
+            if req.matches(&version) && best.as_ref().is_none_or(|(b, _)| version > *b) {
+                best = Some((version, entry.path()));
+            }
+        }
+    }
+    best.map(|(_, dir)| dir)
+}
+
+/// Checkout of the git repository at `url`, at `commit` when known or else
+/// the most recently checked out revision.
+pub fn find_checkout(url: &str, commit: Option<&str>) -> Option<PathBuf> {
+    // Checkouts live in `<repository name>-<hash of the URL>/<short commit>`
+    let name = url.trim_end_matches('/').trim_end_matches(".git").rsplit('/').next()?;
+    let prefix = format!("{}-", name);
+    let checkouts = cargo_home()?.join("git").join("checkouts");
+
+    let mut best: Option<(std::time::SystemTime, PathBuf)> = None;
+    for repository in fs::read_dir(checkouts).ok()?.flatten() {
+        let file_name = repository.file_name();
+        let is_repository = file_name.to_str()
+            .and_then(|n| n.strip_prefix(&prefix))
+            .is_some_and(|hash| hash.chars().all(|c| c.is_ascii_hexdigit()));
+        if !is_repository {
+            continue;
+        }
+        let Ok(revisions) = fs::read_dir(repository.path()) else { continue };
+        for revision in revisions.flatten() {
+            let short = revision.file_name().to_string_lossy().into_owned();
+            match commit {
+                Some(commit) if commit.starts_with(&short) => return Some(revision.path()),
+                Some(_) => {}
+                None => {
+                    let Ok(modified) = revision.metadata().and_then(|m| m.modified()) else { continue };
+                    if best.as_ref().is_none_or(|(b, _)| modified > *b) {
+                        best = Some((modified, revision.path()));
+                    }
+                }
+            }
+        }
+    }
+    best.map(|(_, dir)| dir)
+}
+
+/// First paragraph of prose in a README, skipping headings, badges and HTML.
+fn readme_summary(readme: &str) -> Option<String> {
+    readme.split("\n\n")
+        .map(|p| p.trim())
+        .find(|p| !p.is_empty() && !p.starts_with(['#', '[', '!', '<', '=', '`', '|']))
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Metadata read from the bundled Cargo.toml of a downloaded crate, with the
+/// README providing a description when the manifest has none.
+pub fn read_metadata(dir: &Path) -> Option<CrateInfo> {
+    let content = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    let manifest: SourceManifest = toml::from_str(&content).ok()?;
+    let package = manifest.package;
+
+    let description = package.description.or_else(|| {
+        let readme = match &package.readme {
+            Some(toml::Value::String(path)) => path.as_str(),
+            Some(toml::Value::Boolean(false)) => return None,
+            _ => "README.md",
+        };
+        fs::read_to_string(dir.join(readme)).ok().and_then(|r| readme_summary(&r))
+    });
+
+    Some(CrateInfo {
+        description,
+        repository: package.repository,
+        homepage: package.homepage,
+        authors: Some(package.authors).filter(|a| !a.is_empty()),
+        created_at: None,
+        license: package.license,
+        license_file: package.license_file,
+        keywords: Some(package.keywords).filter(|k| !k.is_empty()),
+        categories: Some(package.categories).filter(|c| !c.is_empty()),
+    })
+}
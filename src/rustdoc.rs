@@ -0,0 +1,54 @@
+//! The "Citing" section that cargo-cite maintains in the crate-level
+//! documentation, so that docs.rs shows how to cite the crate.
+
+use crate::readme;
+use std::path::{Path, PathBuf};
+
+/// The crate root whose `//!` comments document the crate.
+pub const LIB_FILE: &str = "src/lib.rs";
+
+/// The file that `source`, the crate root at `root`, includes as its
+/// documentation with `#![doc = include_str!("...")]`.
+pub fn included_doc(source: &str, root: &Path) -> Option<PathBuf> {
+    const INCLUDE: &str = "include_str!(\"";
+    source.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("#![doc"))
+        .find_map(|line| {
+            let start = line.find(INCLUDE)? + INCLUDE.len();
+            let end = start + line[start..].find('"')?;
+            Some(root.parent()?.join(&line[start..end]))
+        })
+}
+
+/// The text of a `//!` line, if `line` is one.
+fn doc_text(line: &str) -> Option<&str> {
+    line.trim().strip_prefix("//!").map(str::trim)
+}
+
+/// `source`, a crate root, with `section` as `//!` lines between the
+/// cargo-cite markers: in place of the previous section if there is one,
+/// else at the end of the crate documentation, or at the top of the file.
+pub fn insert_section(source: &str, section: &str) -> String {
+    let wrapped: String = readme::wrap_section(section, Path::new(LIB_FILE))
+        .lines()
+        .map(|line| match line {
+            "" => String::from("//!\n"),
+            _ => format!("//! {}\n", line),
+        })
+        .collect();
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    if let Some(start) = lines.iter().position(|line| doc_text(line) == Some(readme::START_MARKER)) {
+        if let Some(end) = lines[start..].iter().position(|line| doc_text(line) == Some(readme::END_MARKER)) {
+            return format!("{}{}{}", lines[..start].concat(), wrapped, lines[start + end + 1..].concat());
+        }
+    }
+    match lines.iter().position(|line| doc_text(line).is_some()) {
+        Some(first) => {
+            let end = first + lines[first..].iter().take_while(|line| doc_text(line).is_some()).count();
+            format!("{}//!\n{}{}", lines[..end].concat(), wrapped, lines[end..].concat())
+        }
+        None if source.is_empty() => wrapped,
+        None => format!("{}\n{}", wrapped, source),
+    }
+}
@@ -0,0 +1,93 @@
+//! `cargo cite stats`: how citable the dependency set is, in numbers.
+
+use crate::archive::find_doi;
+use crate::registry;
+use crate::{person_name, ResolvedDependency};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+
+#[derive(Debug, Default, Serialize)]
+pub struct DependencyStats {
+    pub total: usize,
+    /// Number of crates per license expression
+    pub licenses: BTreeMap<String, usize>,
+    pub with_repository: usize,
+    pub with_citation_file: usize,
+    pub with_doi: usize,
+    /// Distinct authors across all crates
+    pub authors: usize,
+}
+
+/// Which citation-relevant details are known for a dependency.
+#[derive(Debug, Clone, Copy)]
+pub struct Citability {
+    pub authors: bool,
+    pub repository: bool,
+    pub citation_file: bool,
+    pub doi: bool,
+}
+
+impl Citability {
+    pub fn of(dep: &ResolvedDependency) -> Self {
+        let metadata = dep.metadata.as_ref();
+        let citation_files = dep.local_source.as_deref()
+            .map(registry::citation_files)
+            .unwrap_or_default();
+        Citability {
+            authors: metadata.and_then(|m| m.authors.as_ref()).is_some_and(|a| !a.is_empty()),
+            repository: dep.git.is_some()
+                || metadata.is_some_and(|m| m.repository.is_some() || m.homepage.is_some()),
+            citation_file: !citation_files.is_empty(),
+            doi: citation_files.iter()
+                .filter_map(|p| fs::read_to_string(p).ok())
+                .any(|content| find_doi(&content).is_some()),
+        }
+    }
+}
+
+impl DependencyStats {
+    /// Statistics over `dependencies`, counting each crate name once.
+    pub fn collect(dependencies: &[ResolvedDependency]) -> Self {
+        let mut stats = DependencyStats::default();
+        let mut seen = BTreeSet::new();
+        let mut authors = BTreeSet::new();
+
+        for dep in dependencies {
+            if !seen.insert(dep.name.as_str()) {
+                continue;
+            }
+            stats.total += 1;
+
+            let metadata = dep.metadata.as_ref();
+            let license = metadata
+                .and_then(|m| m.license.clone())
+                .unwrap_or_else(|| String::from("unknown"));
+            *stats.licenses.entry(license).or_default() += 1;
+
+            for author in metadata.and_then(|m| m.authors.as_ref()).into_iter().flatten() {
+                authors.insert(person_name(author).to_string());
+            }
+
+            let citability = Citability::of(dep);
+            stats.with_repository += usize::from(citability.repository);
+            stats.with_citation_file += usize::from(citability.citation_file);
+            stats.with_doi += usize::from(citability.doi);
+        }
+        stats.authors = authors.len();
+        stats
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = format!("Dependency statistics:\n- Crates: {}\n", self.total);
+        text.push_str(&format!("- With repository URL: {}\n", self.with_repository));
+        text.push_str(&format!("- With CITATION.cff/CITATION.bib: {}\n", self.with_citation_file));
+        text.push_str(&format!("- With DOI: {}\n", self.with_doi));
+        text.push_str(&format!("- Distinct authors: {}\n", self.authors));
+        text.push_str(&format!("- Licenses ({}):\n", self.licenses.len()));
+        for (license, count) in &self.licenses {
+            text.push_str(&format!("    {:>4}  {}\n", count, license));
+        }
+        text
+    }
+}
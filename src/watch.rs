@@ -0,0 +1,50 @@
+//! `--watch`: regenerating the citation files when the manifests they are
+//! generated from change. Files are polled, which works the same on every
+//! platform and file system.
+
+use crate::config::CONFIG_FILE;
+use crate::lockfile::LOCK_FILE;
+use crate::CARGO_FILE;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Delay between two checks for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The files whose changes regenerate the citations of `manifests`: the
+/// manifests themselves and the lockfiles and configuration files next to
+/// them or in the directories above, up to `start_dir`.
+pub fn watched_files(start_dir: &Path, manifests: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = manifests.to_vec();
+    for manifest in manifests {
+        for dir in manifest.ancestors().skip(1) {
+            files.extend([dir.join(LOCK_FILE), dir.join(CONFIG_FILE)]);
+            if dir == start_dir {
+                break;
+            }
+        }
+    }
+    files.push(start_dir.join(CARGO_FILE));
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// When each of `files` was last modified, `None` for missing ones.
+fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files.iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Wait until one of `files` is modified, created or removed.
+pub async fn changed(files: &[PathBuf]) {
+    let before = modified(files);
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if modified(files) != before {
+            return;
+        }
+    }
+}
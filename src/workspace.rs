@@ -0,0 +1,304 @@
+//! Cargo workspaces.
+
+use crate::diagnostics;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct RootManifest {
+    package: Option<toml::Table>,
+    workspace: Option<WorkspaceTable>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WorkspaceTable {
+    members: Vec<String>,
+    exclude: Vec<String>,
+}
+
+/// Expand `patterns` relative to `root`, returning matching directories.
+fn expand_patterns(root: &Path, patterns: &[String]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for pattern in patterns {
+        let full = root.join(pattern);
+        match glob::glob(&full.to_string_lossy()) {
+            Ok(paths) => dirs.extend(paths.flatten().filter(|p| p.is_dir())),
+            Err(e) => diagnostics::note(format!("Ignoring invalid workspace pattern {:?}: {}", pattern, e)),
+        }
+    }
+    dirs
+}
+
+/// Member manifests of the workspace declared in `manifest_path`.
+fn members(manifest_path: &Path, workspace: &WorkspaceTable) -> Vec<PathBuf> {
+    let root = manifest_path.parent().unwrap_or(Path::new("."));
+    let excluded: HashSet<PathBuf> = expand_patterns(root, &workspace.exclude)
+        .into_iter()
+        .filter_map(|p| p.canonicalize().ok())
+        .collect();
+
+    expand_patterns(root, &workspace.members)
+        .into_iter()
+        .filter(|dir| dir.canonicalize().map(|c| !excluded.contains(&c)).unwrap_or(true))
+        .map(|dir| dir.join("Cargo.toml"))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// A workspace declared by a root manifest.
+pub struct Workspace {
+    /// Whether the root has no `[package]` of its own
+    pub is_virtual: bool,
+    /// Member manifests, without the root
+    pub members: Vec<PathBuf>,
+}
+
+/// The workspace declared in `manifest_path`, or `None` if it declares none.
+pub fn read(manifest_path: &Path) -> Option<Workspace> {
+    let content = fs::read_to_string(manifest_path).ok()?;
+    let manifest: RootManifest = toml::from_str(&content).ok()?;
+    let workspace = manifest.workspace?;
+    Some(Workspace {
+        is_virtual: manifest.package.is_none(),
+        members: members(manifest_path, &workspace),
+    })
+}
+
+/// Directory of the root of the workspace `manifest_path` belongs to, if
+/// any; that of `manifest_path` itself when it is the root.
+pub fn root_dir(manifest_path: &Path) -> Option<PathBuf> {
+    let manifest: toml::Table = toml::from_str(&fs::read_to_string(manifest_path).ok()?).ok()?;
+    let (root_path, _) = find_root(manifest_path, &manifest, &|path| fs::read_to_string(path).ok())?;
+    match root_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => Some(dir.to_path_buf()),
+        _ => Some(PathBuf::from(".")),
+    }
+}
+
+/// Add the members of workspace roots after them, replacing virtual roots,
+/// and drop manifests that are listed more than once.
+pub fn expand_manifests(cargo_files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut expanded = Vec::new();
+    for cargo_path in cargo_files {
+        let paths = match read(&cargo_path) {
+            Some(workspace) => {
+                diagnostics::info(format!("{} at {:?} with {} member{}",
+                    if workspace.is_virtual { "Virtual workspace" } else { "Workspace" },
+                    cargo_path,
+                    workspace.members.len(),
+                    if workspace.members.len() == 1 { "" } else { "s" }
+                ));
+                let root = (!workspace.is_virtual).then_some(cargo_path);
+                root.into_iter().chain(workspace.members).collect()
+            }
+            None => vec![cargo_path],
+        };
+        for path in paths {
+            let id = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if seen.insert(id) {
+                expanded.push(path);
+            }
+        }
+    }
+    expanded
+}
+
+/// Tables of a manifest whose entries may be inherited from
+/// `[workspace.dependencies]`.
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Whether `value` is a `{ workspace = true }` table.
+fn is_inherited(value: &toml::Value) -> bool {
+    value.get("workspace").and_then(toml::Value::as_bool) == Some(true)
+}
+
+/// Whether `manifest` inherits any package field or dependency.
+fn inherits(manifest: &toml::Table) -> bool {
+    let package = manifest.get("package").and_then(toml::Value::as_table);
+    let targets = manifest.get("target").and_then(toml::Value::as_table);
+    let tables = std::iter::once(manifest)
+        .chain(targets.into_iter().flat_map(|t| t.values().filter_map(toml::Value::as_table)));
+    package.is_some_and(|p| p.values().any(is_inherited))
+        || tables.flat_map(|t| DEPENDENCY_TABLES.iter().filter_map(|name| t.get(*name)?.as_table()))
+            .any(|deps| deps.values().any(is_inherited))
+}
+
+/// The root manifest of the workspace `manifest_path` belongs to: the one
+/// named by `package.workspace`, otherwise the closest Cargo.toml with a
+/// `[workspace]` table, starting from the manifest itself.
+fn find_root(manifest_path: &Path, manifest: &toml::Table, read: &dyn Fn(&Path) -> Option<String>) -> Option<(PathBuf, toml::Table)> {
+    let dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let explicit = manifest.get("package")
+        .and_then(|p| p.get("workspace"))
+        .and_then(toml::Value::as_str)
+        .map(|root| dir.join(root));
+    let candidates: Vec<PathBuf> = match explicit {
+        Some(root) => vec![root],
+        None => dir.ancestors().map(Path::to_path_buf).collect(),
+    };
+    candidates.into_iter().find_map(|root_dir| {
+        let path = root_dir.join("Cargo.toml");
+        let root: toml::Table = toml::from_str(&read(&path)?).ok()?;
+        root.contains_key("workspace").then_some((path, root))
+    })
+}
+
+/// `path` relative to `base`, both absolute and free of `..`.
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let common = path.components().zip(base.components()).take_while(|(a, b)| a == b).count();
+    let up = base.components().skip(common).map(|_| Component::ParentDir);
+    let relative: PathBuf = up.chain(path.components().skip(common)).collect();
+    match relative.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => relative,
+    }
+}
+
+/// A `[workspace.dependencies]` entry as seen from the member in
+/// `member_dir`: paths, relative to the root in `root_dir`, are made
+/// relative to the member's directory, and the member's own keys (such as
+/// `features` and `optional`) are merged in.
+fn inherited_dependency(inherited: &toml::Value, local: &toml::Value, root_dir: &Path, member_dir: &Path) -> toml::Value {
+    let mut table = match inherited {
+        toml::Value::String(version) => toml::Table::from_iter([(String::from("version"), toml::Value::String(version.clone()))]),
+        toml::Value::Table(table) => table.clone(),
+        other => return other.clone(),
+    };
+    // Absolute paths are the same from every member
+    if let Some(toml::Value::String(path)) = table.get("path").filter(|path| path.as_str().is_some_and(|p| Path::new(p).is_relative())) {
+        let target = crate::lexical_absolute(&root_dir.join(path));
+        let path = relative_to(&target, &crate::lexical_absolute(member_dir)).to_string_lossy().into_owned();
+        table.insert(String::from("path"), toml::Value::String(path));
+    }
+    for (key, value) in local.as_table().into_iter().flatten() {
+        match (key.as_str(), table.get_mut(key)) {
+            ("workspace", _) => {}
+            ("features", Some(toml::Value::Array(features))) => {
+                features.extend(value.as_array().cloned().unwrap_or_default());
+            }
+            _ => {
+                table.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    toml::Value::Table(table)
+}
+
+/// Replace the `field.workspace = true` package fields and
+/// `{ workspace = true }` dependencies of `manifest` by the values declared in
+/// its workspace root, read through `read`. Returns whether anything was
+/// inherited.
+pub fn inherit(manifest: &mut toml::Table, manifest_path: &Path, read: &dyn Fn(&Path) -> Option<String>) -> Result<bool, String> {
+    if !inherits(manifest) {
+        return Ok(false);
+    }
+    let (root_path, root) = find_root(manifest_path, manifest, read)
+        .ok_or_else(|| String::from("it inherits from a workspace, but no workspace root was found above it"))?;
+    let root_dir = root_path.parent().unwrap_or(Path::new("."));
+    let member_dir = manifest_path.parent().unwrap_or(Path::new("."));
+    let workspace = root.get("workspace").and_then(toml::Value::as_table);
+    let section = |name: &str| workspace.and_then(|w| w.get(name)).and_then(toml::Value::as_table);
+
+    if let Some(toml::Value::Table(package)) = manifest.get_mut("package") {
+        for (key, value) in package.iter_mut().filter(|(_, v)| is_inherited(v)) {
+            *value = section("package")
+                .and_then(|p| p.get(key))
+                .cloned()
+                .ok_or_else(|| format!("`{}` is inherited, but {:?} has no workspace.package.{}", key, root_path, key))?;
+        }
+    }
+
+    let mut tables: Vec<&mut toml::Value> = Vec::new();
+    for (key, value) in manifest.iter_mut() {
+        if DEPENDENCY_TABLES.contains(&key.as_str()) {
+            tables.push(value);
+        } else if key == "target" {
+            for target in value.as_table_mut().into_iter().flat_map(|t| t.iter_mut().map(|(_, v)| v)) {
+                for (name, deps) in target.as_table_mut().into_iter().flatten() {
+                    if DEPENDENCY_TABLES.contains(&name.as_str()) {
+                        tables.push(deps);
+                    }
+                }
+            }
+        }
+    }
+    for deps in tables.into_iter().filter_map(toml::Value::as_table_mut) {
+        for (name, value) in deps.iter_mut().filter(|(_, v)| is_inherited(v)) {
+            let inherited = section("dependencies")
+                .and_then(|d| d.get(name))
+                .ok_or_else(|| format!("dependency `{}` is inherited, but {:?} has no workspace.dependencies.{}", name, root_path, name))?;
+            *value = inherited_dependency(inherited, value, root_dir, member_dir);
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = r#"
+[workspace]
+members = ["crates/*"]
+
+[workspace.package]
+version = "1.2.0"
+authors = ["Jane Doe"]
+
+[workspace.dependencies]
+serde = { version = "1.0", features = ["derive"] }
+rand = "0.8"
+util = { path = "crates/util", version = "0.1" }
+shared = { path = "/opt/shared" }
+"#;
+
+    fn read(path: &Path) -> Option<String> {
+        (path == Path::new("/ws/Cargo.toml")).then(|| ROOT.to_string())
+    }
+
+    fn inherited(member: &str) -> Result<toml::Table, String> {
+        let mut manifest: toml::Table = toml::from_str(member).unwrap();
+        inherit(&mut manifest, Path::new("/ws/crates/a/Cargo.toml"), &read)?;
+        Ok(manifest)
+    }
+
+    #[test]
+    fn inherit_fills_package_fields() {
+        let manifest = inherited("[package]\nname = \"a\"\nversion.workspace = true\nauthors.workspace = true\n").unwrap();
+        assert_eq!(manifest["package"]["version"].as_str(), Some("1.2.0"));
+        assert_eq!(manifest["package"]["authors"][0].as_str(), Some("Jane Doe"));
+        assert_eq!(manifest["package"]["name"].as_str(), Some("a"));
+    }
+
+    #[test]
+    fn inherit_merges_member_dependency_keys() {
+        let manifest = inherited("[package]\nname = \"a\"\nversion = \"0.1.0\"\n[dependencies]\nserde = { workspace = true, features = [\"rc\"], optional = true }\n[dev-dependencies]\nrand.workspace = true\n").unwrap();
+        let serde = &manifest["dependencies"]["serde"];
+        assert_eq!(serde["version"].as_str(), Some("1.0"));
+        assert_eq!(serde["features"].as_array().unwrap().len(), 2);
+        assert_eq!(serde["optional"].as_bool(), Some(true));
+        assert!(serde.get("workspace").is_none());
+        assert_eq!(manifest["dev-dependencies"]["rand"]["version"].as_str(), Some("0.8"));
+    }
+
+    #[test]
+    fn inherit_makes_paths_relative_to_member() {
+        let manifest = inherited("[package]\nname = \"a\"\nversion = \"0.1.0\"\n[dependencies]\nutil.workspace = true\nshared.workspace = true\n").unwrap();
+        assert_eq!(manifest["dependencies"]["util"]["path"].as_str(), Some("../util"));
+        assert_eq!(manifest["dependencies"]["shared"]["path"].as_str(), Some("/opt/shared"));
+        assert_eq!(relative_to(Path::new("/ws"), Path::new("/ws/crates/a")), Path::new("../.."));
+        assert_eq!(relative_to(Path::new("/ws/crates/a"), Path::new("/ws/crates/a")), Path::new("."));
+    }
+
+    #[test]
+    fn inherit_reports_missing_fields() {
+        let error = inherited("[package]\nname = \"a\"\nversion = \"0.1.0\"\nlicense.workspace = true\n").unwrap_err();
+        assert!(error.contains("workspace.package.license"), "{}", error);
+        let mut manifest: toml::Table = toml::from_str("[package]\nname = \"a\"\nversion = \"0.1.0\"\n").unwrap();
+        assert_eq!(inherit(&mut manifest, Path::new("/ws/crates/a/Cargo.toml"), &read), Ok(false));
+    }
+}
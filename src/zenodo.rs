@@ -0,0 +1,248 @@
+//! Zenodo: `cargo cite deposit --zenodo` archives the package's current
+//! release through its REST API, reserving a DOI for it, and `--zenodo-json`
+//! describes the package to Zenodo's GitHub integration.
+//! See https://developers.zenodo.org/.
+
+use crate::codes::Code;
+use crate::credentials::Service;
+use crate::entry::CitationEntry;
+use crate::fetch::MetadataFetcher;
+use crate::formats::PersonName;
+use crate::{diagnostics, CitationOptions, Error};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+use std::process::Command;
+
+/// Metadata file read by Zenodo's GitHub integration when archiving a release.
+pub const ZENODO_JSON: &str = ".zenodo.json";
+
+const API: &str = "https://zenodo.org/api";
+const SANDBOX_API: &str = "https://sandbox.zenodo.org/api";
+
+/// Prefixes of the DOIs Zenodo and its sandbox mint, followed by the record id.
+const DOI_PREFIXES: &[&str] = &["10.5281/zenodo.", "10.5072/zenodo."];
+
+/// Description of an upload, as Zenodo's deposition API and `.zenodo.json`
+/// expect it.
+#[derive(Debug, Serialize)]
+pub struct ZenodoMetadata {
+    upload_type: &'static str,
+    title: String,
+    creators: Vec<Creator>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    contributors: Vec<Creator>,
+    description: String,
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_identifiers: Vec<RelatedIdentifier>,
+}
+
+#[derive(Debug, Serialize)]
+struct Creator {
+    /// `Family, Given`
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    orcid: Option<String>,
+    /// Role of contributors; absent for creators
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    kind: Option<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct RelatedIdentifier {
+    identifier: String,
+    relation: &'static str,
+    scheme: &'static str,
+}
+
+impl ZenodoMetadata {
+    /// The upload of the package cited by `entry`.
+    pub fn from_entry(entry: &CitationEntry) -> Self {
+        let person = |person: &String, kind| {
+            let name = PersonName::parse(person);
+            Creator {
+                name: name.inverted(),
+                orcid: entry.orcid.get(&name.full_name()).map(|id| id.trim_start_matches("https://orcid.org/").to_string()),
+                kind,
+            }
+        };
+        let version = entry.version.as_deref();
+        let related_identifiers = entry.repository.iter()
+            .map(|url| RelatedIdentifier { identifier: url.clone(), relation: "isSupplementTo", scheme: "url" })
+            .chain(version.map(|v| RelatedIdentifier {
+                identifier: format!("https://crates.io/crates/{}/{}", entry.name, v),
+                relation: "isSupplementTo",
+                scheme: "url",
+            }))
+            .collect();
+        ZenodoMetadata {
+            upload_type: "software",
+            title: match version {
+                Some(version) => format!("{} v{}", entry.name, version),
+                None => entry.name.clone(),
+            },
+            creators: entry.authors.iter().map(|a| person(a, None)).collect(),
+            contributors: entry.maintainers.iter().map(|m| person(m, Some("ContactPerson"))).collect(),
+            // Zenodo requires a description
+            description: entry.description.clone().unwrap_or_else(|| entry.name.clone()),
+            version: version.map(str::to_string),
+            license: entry.license.as_deref().and_then(license_id),
+            keywords: entry.keywords.clone(),
+            related_identifiers,
+        }
+    }
+}
+
+/// Zenodo's license id for an SPDX expression: the lowercase identifier of
+/// its first license.
+fn license_id(expression: &str) -> Option<String> {
+    expression.split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '/'))
+        .find(|part| !part.is_empty() && !matches!(*part, "OR" | "AND" | "WITH"))
+        .map(str::to_lowercase)
+}
+
+/// The id of the Zenodo record `doi` was minted for, if Zenodo minted it.
+fn record_id(doi: &str) -> Option<&str> {
+    DOI_PREFIXES.iter().find_map(|prefix| doi.strip_prefix(prefix))
+}
+
+#[derive(Debug, Deserialize)]
+struct Deposition {
+    id: u64,
+    links: DepositionLinks,
+    #[serde(default)]
+    files: Vec<DepositionFile>,
+    #[serde(default)]
+    metadata: serde_json::Value,
+    doi: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DepositionLinks {
+    /// Where the files of the deposition are uploaded
+    bucket: Option<String>,
+    /// Draft created by the `newversion` action
+    latest_draft: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DepositionFile {
+    id: String,
+}
+
+/// Zip archive of the git `HEAD` of the repository containing `dir`, its
+/// files under `prefix/`.
+fn source_archive(dir: &Path, prefix: &str) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["archive", "--format=zip", &format!("--prefix={}/", prefix), "HEAD"])
+        .output()
+        .map_err(|e| format!("could not run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("could not archive the sources: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(output.stdout)
+}
+
+/// Deposit the release of the package cited by `entry`, whose sources are in
+/// `crate_dir`, on Zenodo: as a new version of the record of its DOI when
+/// that is a Zenodo DOI, otherwise as a new upload. The upload is published
+/// with `--publish` and otherwise left as a draft to review on Zenodo.
+/// Returns the DOI minted or reserved for it.
+pub async fn deposit(fetcher: &MetadataFetcher, crate_dir: &Path, entry: &CitationEntry, options: &CitationOptions) -> Result<String, Error> {
+    let failed = |e: String| Error::new(Code::DepositFailed, e);
+    // The personal access token needs the `deposit:write` and, to publish,
+    // `deposit:actions` scopes
+    let token = fetcher.token(Service::Zenodo).map(str::to_string)
+        .ok_or_else(|| failed(format!("No Zenodo personal access token is configured. {}", Service::Zenodo.hint())))?;
+    let api = if options.zenodo_sandbox { SANDBOX_API } else { API };
+    let depositions = format!("{}/deposit/depositions", api);
+    let send = |method: Method, url: String, body: serde_json::Value| {
+        let token = &token;
+        async move { fetcher.send_json::<Deposition>(method, &url, token, &body).await.map_err(failed) }
+    };
+
+    let previous = entry.doi.as_deref().and_then(record_id);
+    let mut deposition = match previous {
+        Some(record) => {
+            diagnostics::info(format!("Creating a new version of Zenodo record {}", record));
+            let new_version = send(Method::POST, format!("{}/{}/actions/newversion", depositions, record), json!({})).await?;
+            let draft = new_version.links.latest_draft
+                .ok_or_else(|| failed(String::from("Zenodo did not return the draft of the new version.")))?;
+            let draft = send(Method::GET, draft, serde_json::Value::Null).await?;
+            // New versions start with the files of the previous one
+            for file in &draft.files {
+                fetcher.delete(&format!("{}/{}/files/{}", depositions, draft.id, file.id), &token).await.map_err(failed)?;
+            }
+            draft
+        }
+        None => send(Method::POST, depositions.clone(), json!({})).await?,
+    };
+
+    let mut metadata = serde_json::to_value(ZenodoMetadata::from_entry(entry)).map_err(|e| failed(e.to_string()))?;
+    metadata["prereserve_doi"] = json!(true);
+    deposition = send(Method::PUT, format!("{}/{}", depositions, deposition.id), json!({ "metadata": metadata })).await?;
+
+    let prefix = format!("{}-{}", entry.name, entry.version.as_deref().unwrap_or_default());
+    let archive = source_archive(crate_dir, &prefix).map_err(failed)?;
+    let bucket = deposition.links.bucket.as_deref()
+        .ok_or_else(|| failed(String::from("Zenodo did not return where to upload the files.")))?;
+    fetcher.upload(&format!("{}/{}.zip", bucket, prefix), &token, archive).await.map_err(failed)?;
+
+    if options.publish {
+        deposition = send(Method::POST, format!("{}/{}/actions/publish", depositions, deposition.id), json!({})).await?;
+        diagnostics::info(format!("Published Zenodo record {}", deposition.id));
+    } else {
+        diagnostics::note(format!("Zenodo deposition {} is a draft: review and publish it on Zenodo, or deposit again with --publish.", deposition.id));
+    }
+    deposition.doi.filter(|doi| !doi.is_empty())
+        .or_else(|| deposition.metadata["prereserve_doi"]["doi"].as_str().map(str::to_string))
+        .ok_or_else(|| failed(String::from("Zenodo did not reserve a DOI for the deposition.")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PackageInfo;
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+
+    #[test]
+    fn license_id_takes_the_first_license() {
+        assert_eq!(license_id("MIT OR Apache-2.0").as_deref(), Some("mit"));
+        assert_eq!(license_id("(Apache-2.0 WITH LLVM-exception)").as_deref(), Some("apache-2.0"));
+        assert_eq!(license_id("(Apache-2.0 WITH LLVM-exception) OR MIT").as_deref(), Some("apache-2.0"));
+        assert_eq!(license_id("MIT/Apache-2.0").as_deref(), Some("mit"));
+        assert_eq!(license_id(" ( ) "), None);
+    }
+
+    #[test]
+    fn record_id_of_zenodo_dois() {
+        assert_eq!(record_id("10.5281/zenodo.1234567"), Some("1234567"));
+        assert_eq!(record_id("10.5072/zenodo.42"), Some("42"));
+        assert_eq!(record_id("10.1000/xyz123"), None);
+    }
+
+    #[test]
+    fn from_entry_maps_authors_and_maintainers() {
+        let package: PackageInfo = toml::from_str("name = \"demo\"\nversion = \"1.2.0\"\nlicense = \"MIT OR Apache-2.0\"\nauthors = [\"Jane Doe <jane@example.com>\", \"John Roe\"]").unwrap();
+        let mut entry = CitationEntry::from_package(&package, "demo", PathBuf::from("Cargo.toml"), NaiveDate::default());
+        entry.maintainers = vec![String::from("Ann Poe")];
+        entry.orcid.insert(String::from("Jane Doe"), String::from("https://orcid.org/0000-0002-1825-0097"));
+        let metadata = serde_json::to_value(ZenodoMetadata::from_entry(&entry)).unwrap();
+        assert_eq!(metadata["title"], "demo v1.2.0");
+        assert_eq!(metadata["license"], "mit");
+        assert_eq!(metadata["creators"], json!([
+            {"name": "Doe, Jane", "orcid": "0000-0002-1825-0097"},
+            {"name": "Roe, John"},
+        ]));
+        assert_eq!(metadata["contributors"], json!([{"name": "Poe, Ann", "type": "ContactPerson"}]));
+    }
+}